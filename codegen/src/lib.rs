@@ -105,6 +105,18 @@ use syn::{
 /// }
 /// ```
 ///
+/// Systems can be labelled, and ordered relative to other labelled systems, with the `#[label]`,
+/// `#[before]` and `#[after]` attributes. The schedule builder sorts systems to satisfy these
+/// constraints.
+///
+/// ```ignore
+/// # use legion_codegen::system;
+/// #[system]
+/// #[label("physics")]
+/// #[after("input")]
+/// fn integrate() {}
+/// ```
+///
 /// Systems can contain their own state. Add a reference marked with the `#[state]` parameter to
 /// your function. This state will be initialized when you construct the system.
 ///
@@ -123,6 +135,42 @@ use syn::{
 ///     .build();
 /// ```
 ///
+/// Resources which are not `Send` or `Sync`, such as window handles, can be accessed by marking
+/// a reference with the `#[non_send]` attribute. These systems are always run on the thread
+/// which executes the schedule, while other systems continue to run in parallel.
+///
+/// ```ignore
+/// # use legion_codegen::system;
+/// # use std::rc::Rc;
+/// # struct Window(Rc<String>);
+/// #[system]
+/// fn render(#[non_send] window: &Window) {
+///     println!("drawing to {}", window.0);
+/// }
+/// ```
+///
+/// Systems can send and receive typed events stored in an `Events<T>` resource with `EventWriter<T>`
+/// and `EventReader<T>` parameters. Writers are scheduled as resource writes and readers as resource
+/// reads. Each reader keeps its own cursor inside the system, so it only sees each event once.
+/// Readers cannot be used in `for_each` or `par_for_each` systems.
+///
+/// ```ignore
+/// # use legion_codegen::system;
+/// # use legion::systems::{EventReader, EventWriter};
+/// # struct Collision(u32);
+/// #[system]
+/// fn detect_collisions(mut collisions: EventWriter<Collision>) {
+///     collisions.send(Collision(5));
+/// }
+///
+/// #[system]
+/// fn print_collisions(mut collisions: EventReader<Collision>) {
+///     for collision in collisions.iter() {
+///         println!("collision {}", collision.0);
+///     }
+/// }
+/// ```
+///
 /// Systems can contain generic parameters.
 ///
 /// ```ignore
@@ -192,6 +240,8 @@ enum Error {
     ExpectedComponentType(Span),
     #[error("expected filter expression")]
     ExpectedFilterExpression(Span),
+    #[error("expected system label")]
+    ExpectedLabel(Span),
     #[error(
         "system does not request any component access (sub-world will have no permissions), \
     consider using #[read_compnent(T)] or #[write_component(T)]"
@@ -210,6 +260,7 @@ impl Error {
             Error::InvalidArgument(span) => *span,
             Error::ExpectedComponentType(span) => *span,
             Error::ExpectedFilterExpression(span) => *span,
+            Error::ExpectedLabel(span) => *span,
             _ => Span::call_site(),
         }
     }
@@ -290,6 +341,7 @@ struct Sig {
     read_resources: Vec<Type>,
     write_resources: Vec<Type>,
     state_args: Vec<Type>,
+    event_cursors: Vec<Type>,
     generics: Generics,
 }
 
@@ -300,10 +352,25 @@ impl Sig {
         let mut read_resources = Vec::new();
         let mut write_resources = Vec::new();
         let mut state_args = Vec::new();
+        let mut event_cursors = Vec::new();
         for param in &mut item.inputs {
             match param {
                 syn::FnArg::Receiver(_) => return Err(Error::SelfNotAllowed),
                 syn::FnArg::Typed(arg) => match arg.ty.as_ref() {
+                    Type::Path(ty_path) if event_type(ty_path, "EventReader").is_some() => {
+                        let event = event_type(ty_path, "EventReader").unwrap();
+                        parameters.push(Parameter::EventReader(
+                            read_resources.len(),
+                            event_cursors.len(),
+                        ));
+                        read_resources.push(parse_quote!(::legion::systems::Events<#event>));
+                        event_cursors.push(event);
+                    }
+                    Type::Path(ty_path) if event_type(ty_path, "EventWriter").is_some() => {
+                        let event = event_type(ty_path, "EventWriter").unwrap();
+                        parameters.push(Parameter::EventWriter(write_resources.len()));
+                        write_resources.push(parse_quote!(::legion::systems::Events<#event>));
+                    }
                     Type::Path(ty_path) => {
                         let ident = &ty_path.path.segments[0].ident;
                         if ident == "Option" {
@@ -385,6 +452,15 @@ impl Sig {
                                     read_resources.push(ty.elem.as_ref().clone());
                                 }
                             }
+                            Some(ArgAttr::NonSend) => {
+                                if mutable {
+                                    parameters.push(Parameter::NonSendMut(write_resources.len()));
+                                    write_resources.push(ty.elem.as_ref().clone());
+                                } else {
+                                    parameters.push(Parameter::NonSend(read_resources.len()));
+                                    read_resources.push(ty.elem.as_ref().clone());
+                                }
+                            }
                             Some(ArgAttr::State) => {
                                 if mutable {
                                     parameters.push(Parameter::StateMut(state_args.len()));
@@ -417,6 +493,7 @@ impl Sig {
             read_resources,
             write_resources,
             state_args,
+            event_cursors,
         })
     }
 
@@ -431,6 +508,10 @@ impl Sig {
                     attributes.remove(i);
                     return Some(ArgAttr::State);
                 }
+                Some(ident) if ident == "non_send" => {
+                    attributes.remove(i);
+                    return Some(ArgAttr::NonSend);
+                }
                 _ => {}
             }
         }
@@ -441,6 +522,24 @@ impl Sig {
 enum ArgAttr {
     Resource,
     State,
+    NonSend,
+}
+
+/// Returns the event type `T` if the given type is `name<T>` or `name<'a, T>`.
+fn event_type(ty: &syn::TypePath, name: &str) -> Option<Type> {
+    let segment = ty.path.segments.last()?;
+    if segment.ident != name {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(bracketed) => {
+            bracketed.args.iter().find_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty.clone()),
+                _ => None,
+            })
+        }
+        _ => None,
+    }
 }
 
 fn is_type(ty: &Type, segments: &[&str]) -> bool {
@@ -479,8 +578,12 @@ enum Parameter {
     Component(usize),
     Resource(usize),
     ResourceMut(usize),
+    NonSend(usize),
+    NonSendMut(usize),
     State(usize),
     StateMut(usize),
+    EventReader(usize, usize),
+    EventWriter(usize),
 }
 
 struct Config {
@@ -489,16 +592,22 @@ struct Config {
     read_components: Vec<Type>,
     write_components: Vec<Type>,
     filters: Vec<Expr>,
+    labels: Vec<Expr>,
+    before: Vec<Expr>,
+    after: Vec<Expr>,
     signature: Sig,
 }
 
 impl Config {
     fn parse(attr: SystemAttr, item: &mut ItemFn) -> Result<Self, Error> {
-        // parse attributes, extract read/write component/resource and filters
+        // parse attributes, extract read/write component/resource, filters and ordering
         let mut to_remove = Vec::new();
         let mut read_components = Vec::new();
         let mut write_components = Vec::new();
         let mut filters = Vec::new();
+        let mut labels = Vec::new();
+        let mut before = Vec::new();
+        let mut after = Vec::new();
         for (i, attribute) in item.attrs.iter().enumerate() {
             if let Some(ident) = attribute.path.get_ident() {
                 if ident == "read_component" {
@@ -522,6 +631,22 @@ impl Config {
                     filters.push(filter);
                     to_remove.push(i);
                 }
+                let ordering = if ident == "label" {
+                    Some(&mut labels)
+                } else if ident == "before" {
+                    Some(&mut before)
+                } else if ident == "after" {
+                    Some(&mut after)
+                } else {
+                    None
+                };
+                if let Some(ordering) = ordering {
+                    let label = attribute
+                        .parse_args()
+                        .map_err(|_| Error::ExpectedLabel(ident.span()))?;
+                    ordering.push(label);
+                    to_remove.push(i);
+                }
             }
         }
 
@@ -539,6 +664,9 @@ impl Config {
             read_components,
             write_components,
             filters,
+            labels,
+            before,
+            after,
             signature,
         })
     }
@@ -582,6 +710,20 @@ impl Config {
             }
         }
 
+        // each entity's call would share the reader's cursor, so only the first entity would
+        // see any events
+        if system_type == SystemType::ForEach
+            && self
+                .signature
+                .parameters
+                .iter()
+                .any(|param| matches!(param, Parameter::EventReader(..)))
+        {
+            return Err(Error::Message(
+                "for_each systems cannot accept event readers".to_string(),
+            ));
+        }
+
         if system_type == SystemType::ParForEach {
             if self
                 .signature
@@ -597,7 +739,7 @@ impl Config {
                 .signature
                 .parameters
                 .iter()
-                .any(|param| matches!(param, Parameter::ResourceMut(_)))
+                .any(|param| matches!(param, Parameter::ResourceMut(_) | Parameter::EventWriter(_)))
             {
                 return Err(Error::Message(
                     "par_for_each systems cannot accept mutable resource references".to_string(),
@@ -614,6 +756,26 @@ impl Config {
                         .to_string(),
                 ));
             }
+            if self
+                .signature
+                .parameters
+                .iter()
+                .any(|param| matches!(param, Parameter::EventReader(..)))
+            {
+                return Err(Error::Message(
+                    "par_for_each systems cannot accept event readers".to_string(),
+                ));
+            }
+            if self
+                .signature
+                .parameters
+                .iter()
+                .any(|param| matches!(param, Parameter::NonSend(_) | Parameter::NonSendMut(_)))
+            {
+                return Err(Error::Message(
+                    "par_for_each systems cannot accept non-send resources".to_string(),
+                ));
+            }
         }
 
         Ok(())
@@ -628,6 +790,9 @@ impl Config {
             read_components,
             write_components,
             filters,
+            labels,
+            before,
+            after,
             signature,
         } = self;
 
@@ -652,6 +817,7 @@ impl Config {
             (signature.read_resources.len() + signature.write_resources.len()) == 1;
         let mut call_params = Vec::new();
         let mut fn_params = Vec::new();
+        let mut cursors = Vec::new();
         let mut world = None;
         for param in &signature.parameters {
             match param {
@@ -686,15 +852,17 @@ impl Config {
                     let idx = Index::from(*idx);
                     call_params.push(quote!(components.#idx));
                 }
-                Parameter::Resource(_) if single_resource => call_params.push(quote!(&*resources)),
-                Parameter::ResourceMut(_) if single_resource => {
+                Parameter::Resource(_) | Parameter::NonSend(_) if single_resource => {
+                    call_params.push(quote!(&*resources))
+                }
+                Parameter::ResourceMut(_) | Parameter::NonSendMut(_) if single_resource => {
                     call_params.push(quote!(&mut *resources))
                 }
-                Parameter::Resource(idx) => {
+                Parameter::Resource(idx) | Parameter::NonSend(idx) => {
                     let idx = Index::from(*idx);
                     call_params.push(quote!(&*resources.#idx));
                 }
-                Parameter::ResourceMut(idx) => {
+                Parameter::ResourceMut(idx) | Parameter::NonSendMut(idx) => {
                     let idx = Index::from(*idx + signature.read_resources.len());
                     call_params.push(quote!(&mut *resources.#idx));
                 }
@@ -710,6 +878,31 @@ impl Config {
                     call_params.push(quote!(&mut #arg_name));
                     fn_params.push(quote!(mut #arg_name: #arg_type));
                 }
+                Parameter::EventReader(idx, cursor) => {
+                    let events = if single_resource {
+                        quote!(&*resources)
+                    } else {
+                        let idx = Index::from(*idx);
+                        quote!(&*resources.#idx)
+                    };
+                    let cursor_name = format_ident!("event_cursor_{}", cursor);
+                    let event_type = &signature.event_cursors[*cursor];
+                    call_params.push(
+                        quote!(::legion::systems::EventReader::new(#events, &mut #cursor_name)),
+                    );
+                    cursors.push(quote! {
+                        let mut #cursor_name = ::legion::systems::EventCursor::<#event_type>::default();
+                    });
+                }
+                Parameter::EventWriter(idx) => {
+                    let events = if single_resource {
+                        quote!(&mut *resources)
+                    } else {
+                        let idx = Index::from(*idx + signature.read_resources.len());
+                        quote!(&mut *resources.#idx)
+                    };
+                    call_params.push(quote!(::legion::systems::EventWriter::new(#events)));
+                }
             }
         }
 
@@ -753,16 +946,40 @@ impl Config {
         } else {
             quote!(let generic_names = "";)
         };
-        let read_resources = &signature.read_resources;
-        let write_resources = &signature.write_resources;
+        let read_resources = signature.read_resources.iter().enumerate().map(|(i, ty)| {
+            let non_send = signature
+                .parameters
+                .iter()
+                .any(|param| matches!(param, Parameter::NonSend(idx) if *idx == i));
+            if non_send {
+                quote!(.read_non_send_resource::<#ty>())
+            } else {
+                quote!(.read_resource::<#ty>())
+            }
+        });
+        let write_resources = signature.write_resources.iter().enumerate().map(|(i, ty)| {
+            let non_send = signature
+                .parameters
+                .iter()
+                .any(|param| matches!(param, Parameter::NonSendMut(idx) if *idx == i));
+            if non_send {
+                quote!(.write_non_send_resource::<#ty>())
+            } else {
+                quote!(.write_resource::<#ty>())
+            }
+        });
         let builder = quote! {
             use legion::IntoQuery;
             #generic_parameter_names
+            #(#cursors)*
             ::legion::systems::SystemBuilder::new(format!("{}{}", #system_name, generic_names))
                 #(.read_component::<#read_components>())*
                 #(.write_component::<#write_components>())*
-                #(.read_resource::<#read_resources>())*
-                #(.write_resource::<#write_resources>())*
+                #(#read_resources)*
+                #(#write_resources)*
+                #(.label(#labels))*
+                #(.before(#before))*
+                #(.after(#after))*
                 #query
                 .build(move |cmd, world, resources, query| {
                     #body
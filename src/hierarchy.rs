@@ -0,0 +1,30 @@
+//! Parent-child relationships between entities.
+//!
+//! An entity's parent is stored in a [Parent](struct.Parent.html) component, and its children in
+//! a [Children](struct.Children.html) component. Both are maintained together by
+//! [World::set_parent](../world/struct.World.html#method.set_parent) and
+//! [World::remove_parent](../world/struct.World.html#method.remove_parent), or the equivalent
+//! [CommandBuffer](../systems/struct.CommandBuffer.html) methods. Removing an entity from the
+//! world also removes it from its parent's children.
+//!
+//! ```
+//! # use legion::*;
+//! # use legion::hierarchy::{descendants, Children, Parent};
+//! let mut world = World::default();
+//! let body = world.push((0usize,));
+//! let arm = world.push((1usize,));
+//! let hand = world.push((2usize,));
+//! world.set_parent(arm, body).unwrap();
+//! world.set_parent(hand, arm).unwrap();
+//!
+//! // iterate through the hierarchy depth-first
+//! assert_eq!(descendants(&world, body).collect::<Vec<_>>(), vec![arm, hand]);
+//!
+//! // remove an entity along with all of its descendants
+//! world.remove_recursive(arm);
+//! assert_eq!(world.len(), 1);
+//! ```
+
+pub use crate::internals::hierarchy::{
+    ancestors, descendants, Ancestors, Children, Descendants, HierarchyError, Parent,
+};
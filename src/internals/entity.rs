@@ -2,12 +2,17 @@ use super::{
     hash::U64Hasher,
     storage::{archetype::ArchetypeIndex, ComponentIndex},
 };
+use bit_set::BitSet;
+use parking_lot::Mutex;
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    collections::{HashMap, HashSet, VecDeque},
     fmt::Debug,
     hash::BuildHasherDefault,
-    sync::atomic::{AtomicU64, Ordering},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
 };
 
 /// An opaque identifier for an entity.
@@ -28,22 +33,325 @@ impl Clone for Entity {
     }
 }
 
+impl Entity {
+    pub(crate) fn from_parts(index: u32, generation: u32) -> Self {
+        Entity((generation as u64) << 32 | index as u64)
+    }
+
+    // The index and generation of entities allocated by a generational world. Entities from
+    // other sources are interpreted in the same way when they are inserted into such a world.
+    pub(crate) fn index(self) -> u32 {
+        self.0 as u32
+    }
+
+    pub(crate) fn generation(self) -> u32 {
+        (self.0 >> 32) as u32
+    }
+}
+
 const BLOCK_SIZE: u64 = 16;
 const BLOCK_SIZE_USIZE: usize = BLOCK_SIZE as usize;
 
 // Always divisible by BLOCK_SIZE.
 static NEXT_ENTITY: AtomicU64 = AtomicU64::new(0);
 
+/// Determines how a [world](../world/struct.World.html) allocates entity IDs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntityAllocation {
+    /// IDs are drawn from a counter shared by all worlds, and are never reused.
+    Global,
+    /// IDs are composed of an index and a generation. The indices of removed entities are
+    /// reused with an incremented generation, so that entity locations can be stored densely
+    /// and stale IDs can be detected with [World::is_stale](../world/struct.World.html#method.is_stale).
+    ///
+    /// Entities which were not allocated by the world, such as those moved from another world,
+    /// claim their index when they are inserted. If the index is in use by another entity, or
+    /// is far beyond the indices allocated so far, the entity is instead stored separately, and
+    /// its ID is never allocated by the world.
+    Generational,
+    /// IDs are drawn from counters owned by the world, and are never reused. The same sequence
+    /// of insertions into the world, including `CommandBuffer::push`, always yields the same
+    /// IDs, regardless of allocations made by other worlds.
+    ///
+    /// Each command buffer draws from its own range of IDs, which is assigned when the buffer is
+    /// created. Systems create their command buffers when they are first prepared, which
+    /// happens in schedule order, and so buffers which are recorded concurrently by systems
+    /// running in parallel still yield the same IDs.
+    ///
+    /// Entities which were not allocated by the world advance the counter past their ID when
+    /// they are inserted. Different deterministic worlds allocate the same IDs, and so entities
+    /// should not be moved or cloned between them without remapping.
+    Deterministic,
+}
+
+// `#[default]` on enum variants requires a newer compiler than this crate otherwise does
+#[allow(clippy::derivable_impls)]
+impl Default for EntityAllocation {
+    fn default() -> Self {
+        EntityAllocation::Global
+    }
+}
+
+/// The indices allocated by a generational world.
+#[derive(Debug, Default)]
+pub(crate) struct GenerationalIds {
+    // the current generation of each index
+    generations: Vec<u32>,
+    // free indices, in the order in which they were released; indices which have since been
+    // claimed are skipped
+    free: VecDeque<u32>,
+    is_free: BitSet,
+    // entities which were not allocated by this pool and whose locations are not stored with
+    // their index, which must not be allocated
+    foreign: HashSet<Entity, EntityHasher>,
+}
+
+impl GenerationalIds {
+    fn allocate(&mut self) -> Entity {
+        while let Some(index) = self.free.pop_front() {
+            if self.is_free.remove(index as usize) {
+                let generation = self.unused_generation(index, self.generations[index as usize]);
+                self.generations[index as usize] = generation;
+                return Entity::from_parts(index, generation);
+            }
+        }
+
+        let index = self.generations.len() as u32;
+        let generation = self.unused_generation(index, 0);
+        self.generations.push(generation);
+        Entity::from_parts(index, generation)
+    }
+
+    // Returns the first generation of an index, from `generation` onwards, which is not the
+    // generation of a foreign entity.
+    fn unused_generation(&self, index: u32, mut generation: u32) -> u32 {
+        while self
+            .foreign
+            .contains(&Entity::from_parts(index, generation))
+        {
+            generation = generation.wrapping_add(1);
+        }
+        generation
+    }
+
+    // Marks an entity's index as in use, which is only necessary for entities which were not
+    // allocated by this pool.
+    fn claim(&mut self, entity: Entity) {
+        let index = entity.index() as usize;
+        while self.generations.len() <= index {
+            let free = self.generations.len();
+            self.generations.push(0);
+            self.free.push_back(free as u32);
+            self.is_free.insert(free);
+        }
+        self.is_free.remove(index);
+        self.generations[index] = entity.generation();
+    }
+
+    fn reserve(&mut self, count: usize) -> EntityRange {
+        // reserved IDs are of the first generation, so the range must start after any foreign
+        // entity of that generation which it would otherwise include
+        let mut start = self.generations.len();
+        while let Some(index) = self
+            .foreign
+            .iter()
+            .filter(|entity| entity.generation() == 0)
+            .map(|entity| entity.index() as usize)
+            .filter(|index| (start..start + count).contains(index))
+            .max()
+        {
+            start = index + 1;
+        }
+
+        // skipped indices remain available for allocation
+        for index in self.generations.len()..start {
+            self.generations.push(0);
+            self.free.push_back(index as u32);
+            self.is_free.insert(index);
+        }
+
+        self.generations.resize(start + count, 0);
+        EntityRange::new(start as u64, (start + count) as u64)
+    }
+
+    fn claim_range(&mut self, range: &EntityRange) -> bool {
+        let len = self.generations.len() as u64;
+        if range.start < len {
+            return false;
+        }
+        if range.end > len {
+            self.claim(Entity(range.end - 1));
+            for index in range.start..range.end {
+                self.is_free.remove(index as usize);
+            }
+        }
+        true
+    }
+
+    fn release(&mut self, entity: Entity) {
+        let index = entity.index() as usize;
+        self.generations[index] = entity.generation().wrapping_add(1);
+        if self.is_free.insert(index) {
+            self.free.push_back(index as u32);
+        }
+    }
+}
+
+// The number of bits of the IDs in each range of a deterministic world. The world allocates
+// from the first range, and each of its command buffers from a later range.
+const STREAM_BITS: u32 = 40;
+
+/// The source from which a world allocates entity IDs.
+#[derive(Debug, Clone)]
+pub(crate) enum IdSource {
+    Global,
+    Generational(Arc<Mutex<GenerationalIds>>),
+    Deterministic {
+        next: Arc<AtomicU64>,
+        // the number of ranges which have been assigned to command buffers
+        streams: Arc<AtomicU64>,
+    },
+}
+
+impl IdSource {
+    pub(crate) fn new(allocation: EntityAllocation) -> Self {
+        match allocation {
+            EntityAllocation::Global => IdSource::Global,
+            EntityAllocation::Generational => IdSource::Generational(Arc::default()),
+            EntityAllocation::Deterministic => IdSource::Deterministic {
+                next: Arc::default(),
+                streams: Arc::default(),
+            },
+        }
+    }
+
+    // Returns a source for a command buffer. Deterministic worlds assign each buffer its own
+    // range of IDs, so that the IDs it allocates do not depend on other buffers.
+    pub(crate) fn stream(&self) -> IdSource {
+        match self {
+            IdSource::Deterministic { streams, .. } => {
+                let stream = streams.fetch_add(1, Ordering::Relaxed) + 1;
+                IdSource::Deterministic {
+                    next: Arc::new(AtomicU64::new(stream << STREAM_BITS)),
+                    streams: streams.clone(),
+                }
+            }
+            _ => self.clone(),
+        }
+    }
+
+    // `u64::div_ceil` requires a newer compiler than this crate otherwise does
+    #[allow(clippy::manual_div_ceil)]
+    pub(crate) fn reserve(&self, count: usize) -> EntityRange {
+        match self {
+            IdSource::Global => {
+                let blocks = (count as u64 + BLOCK_SIZE - 1) / BLOCK_SIZE;
+                let start = NEXT_ENTITY.fetch_add(blocks * BLOCK_SIZE, Ordering::Relaxed);
+                EntityRange::new(start, start + count as u64)
+            }
+            IdSource::Generational(ids) => ids.lock().reserve(count),
+            IdSource::Deterministic { next, .. } => {
+                let start = next.fetch_add(count as u64, Ordering::Relaxed);
+                EntityRange::new(start, start + count as u64)
+            }
+        }
+    }
+
+    #[allow(clippy::manual_div_ceil)]
+    pub(crate) fn claim(&self, range: &EntityRange) -> bool {
+        if range.start >= range.end {
+            return true;
+        }
+        match self {
+            IdSource::Global => {
+                let end = (range.end + BLOCK_SIZE - 1) / BLOCK_SIZE * BLOCK_SIZE;
+                NEXT_ENTITY.fetch_max(end, Ordering::Relaxed) <= range.start
+            }
+            IdSource::Generational(ids) => ids.lock().claim_range(range),
+            IdSource::Deterministic { next, .. } => {
+                next.fetch_max(range.end, Ordering::Relaxed) <= range.start
+            }
+        }
+    }
+}
+
+/// A contiguous range of reserved entity IDs.
+///
+/// Ranges are reserved with [World::reserve_entities](../world/struct.World.html#method.reserve_entities),
+/// and yield their IDs in order when iterated. An empty range can be constructed with `default`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct EntityRange {
+    start: u64,
+    end: u64,
+}
+
+impl EntityRange {
+    fn new(start: u64, end: u64) -> Self {
+        Self { start, end }
+    }
+
+    /// Returns `true` if the entity is within the range.
+    pub fn contains(&self, entity: Entity) -> bool {
+        (self.start..self.end).contains(&entity.0)
+    }
+
+    /// Splits off the first `count` IDs into a new range, leaving the remainder in this range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count` is greater than the length of the range.
+    pub fn split_off(&mut self, count: usize) -> EntityRange {
+        assert!(count <= self.len(), "entity range is too short");
+        let start = self.start;
+        self.start += count as u64;
+        Self::new(start, self.start)
+    }
+}
+
+impl Iterator for EntityRange {
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Entity> {
+        if self.start < self.end {
+            self.start += 1;
+            Some(Entity(self.start - 1))
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.end - self.start) as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for EntityRange {}
+
+impl std::iter::FusedIterator for EntityRange {}
+
 /// An iterator which yields new entity IDs.
 #[derive(Debug)]
 pub struct Allocate {
     next: u64,
+    source: IdSource,
 }
 
 impl Allocate {
     /// Constructs a new enity ID allocator iterator.
     pub fn new() -> Self {
-        Self { next: 0 }
+        Self {
+            next: 0,
+            source: IdSource::Global,
+        }
+    }
+
+    pub(crate) fn from_source(source: &IdSource) -> Self {
+        Self {
+            next: 0,
+            source: source.clone(),
+        }
     }
 }
 
@@ -58,6 +366,14 @@ impl<'a> Iterator for Allocate {
 
     #[inline(always)]
     fn next(&mut self) -> Option<Self::Item> {
+        match &self.source {
+            IdSource::Global => {}
+            IdSource::Generational(ids) => return Some(ids.lock().allocate()),
+            IdSource::Deterministic { next, .. } => {
+                return Some(Entity(next.fetch_add(1, Ordering::Relaxed)))
+            }
+        }
+
         if self.next % BLOCK_SIZE == 0 {
             // This is either the first block, or we overflowed to the next block.
             self.next = NEXT_ENTITY.fetch_add(BLOCK_SIZE, Ordering::Relaxed);
@@ -94,25 +410,87 @@ impl EntityLocation {
 /// A hasher optimized for entity IDs.
 pub type EntityHasher = BuildHasherDefault<U64Hasher>;
 
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    generation: u32,
+    location: Option<EntityLocation>,
+}
+
+// The number of indices beyond those allocated by a generational world for which an inserted
+// entity's location is stored densely. Entities with greater indices are stored separately.
+const MAX_DENSE_GROWTH: usize = 1024;
+
+#[derive(Clone)]
+enum Locations {
+    Blocks(HashMap<u64, Box<[Option<EntityLocation>; BLOCK_SIZE_USIZE]>, EntityHasher>),
+    Dense {
+        slots: Vec<Slot>,
+        // entities which were not allocated by the world and could not be stored in `slots`
+        foreign: HashMap<Entity, EntityLocation, EntityHasher>,
+        ids: Arc<Mutex<GenerationalIds>>,
+    },
+}
+
+impl Default for Locations {
+    fn default() -> Self {
+        Locations::Blocks(HashMap::default())
+    }
+}
+
 /// A map of entity IDs to their storage locations.
 #[derive(Clone, Default)]
 pub struct LocationMap {
     len: usize,
-    blocks: HashMap<u64, Box<[Option<EntityLocation>; BLOCK_SIZE_USIZE]>, EntityHasher>,
+    locations: Locations,
+    // the counter of a deterministic world, which is advanced past inserted IDs in its range
+    next: Option<Arc<AtomicU64>>,
 }
 
 impl Debug for LocationMap {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let entries = self.blocks.iter().flat_map(|(base, locs)| {
-            locs.iter()
-                .enumerate()
-                .filter_map(move |(i, loc)| loc.map(|loc| (Entity(*base + i as u64), loc)))
-        });
-        f.debug_map().entries(entries).finish()
+        let mut map = f.debug_map();
+        match &self.locations {
+            Locations::Blocks(blocks) => {
+                map.entries(blocks.iter().flat_map(|(base, locs)| {
+                    locs.iter()
+                        .enumerate()
+                        .filter_map(move |(i, loc)| loc.map(|loc| (Entity(*base + i as u64), loc)))
+                }));
+            }
+            Locations::Dense { slots, foreign, .. } => {
+                map.entries(slots.iter().enumerate().filter_map(|(i, slot)| {
+                    slot.location
+                        .map(|loc| (Entity::from_parts(i as u32, slot.generation), loc))
+                }));
+                map.entries(foreign.iter());
+            }
+        }
+        map.finish()
     }
 }
 
 impl LocationMap {
+    /// Constructs a location map for entities allocated from the given source.
+    pub(crate) fn new(source: &IdSource) -> Self {
+        let locations = match source {
+            IdSource::Global | IdSource::Deterministic { .. } => Locations::default(),
+            IdSource::Generational(ids) => Locations::Dense {
+                slots: Vec::new(),
+                foreign: HashMap::default(),
+                ids: ids.clone(),
+            },
+        };
+        let next = match source {
+            IdSource::Deterministic { next, .. } => Some(next.clone()),
+            _ => None,
+        };
+        Self {
+            len: 0,
+            locations,
+            next,
+        }
+    }
+
     /// Returns the number of entities in the map.
     pub fn len(&self) -> usize {
         self.len
@@ -128,6 +506,22 @@ impl LocationMap {
         self.get(entity).is_some()
     }
 
+    /// Returns `true` if the entity's ID has been reused by a later generation of entity, or
+    /// the entity has been removed. Always returns `false` for maps which do not use
+    /// generational IDs.
+    pub fn is_stale(&self, entity: Entity) -> bool {
+        match &self.locations {
+            Locations::Blocks(_) => false,
+            Locations::Dense { slots, foreign, .. } => {
+                !foreign.contains_key(&entity)
+                    && slots
+                        .get(entity.index() as usize)
+                        .map(|slot| slot.generation != entity.generation())
+                        .unwrap_or(false)
+            }
+        }
+    }
+
     /// Inserts a collection of adjacent entities into the location map.
     pub fn insert(
         &mut self,
@@ -135,29 +529,90 @@ impl LocationMap {
         arch: ArchetypeIndex,
         ComponentIndex(base): ComponentIndex,
     ) -> Vec<EntityLocation> {
-        let mut current_block = u64::MAX;
-        let mut block_vec = None;
         let mut removed = Vec::new();
-        for (i, entity) in ids.iter().enumerate() {
-            let block = entity.0 / BLOCK_SIZE;
-            if current_block != block {
-                block_vec = Some(
-                    self.blocks
-                        .entry(block)
-                        .or_insert_with(|| Box::new([None; BLOCK_SIZE_USIZE])),
-                );
-                current_block = block;
-            }
-
-            if let Some(ref mut vec) = block_vec {
-                let idx = (entity.0 % BLOCK_SIZE) as usize;
-                let loc = EntityLocation(arch, ComponentIndex(base + i));
-                if let Some(previous) = vec[idx].replace(loc) {
-                    removed.push(previous);
+        match &mut self.locations {
+            Locations::Blocks(blocks) => {
+                let mut current_block = u64::MAX;
+                let mut block_vec = None;
+                for (i, entity) in ids.iter().enumerate() {
+                    let block = entity.0 / BLOCK_SIZE;
+                    if current_block != block {
+                        block_vec = Some(
+                            blocks
+                                .entry(block)
+                                .or_insert_with(|| Box::new([None; BLOCK_SIZE_USIZE])),
+                        );
+                        current_block = block;
+                    }
+
+                    if let Some(ref mut vec) = block_vec {
+                        let idx = (entity.0 % BLOCK_SIZE) as usize;
+                        let loc = EntityLocation(arch, ComponentIndex(base + i));
+                        if let Some(previous) = vec[idx].replace(loc) {
+                            removed.push(previous);
+                        }
+                    }
+                }
+            }
+            Locations::Dense {
+                slots,
+                foreign,
+                ids: pool,
+            } => {
+                let mut pool = pool.lock();
+                for (i, entity) in ids.iter().enumerate() {
+                    let loc = EntityLocation(arch, ComponentIndex(base + i));
+                    if let Some(previous) = foreign.get_mut(entity) {
+                        removed.push(std::mem::replace(previous, loc));
+                        continue;
+                    }
+
+                    // entities which cannot claim their index are stored separately, rather
+                    // than displacing the entity using it or growing the slots without bound
+                    let idx = entity.index() as usize;
+                    let in_use = slots
+                        .get(idx)
+                        .map(|slot| {
+                            slot.location.is_some() && slot.generation != entity.generation()
+                        })
+                        .unwrap_or(false);
+                    if in_use || idx >= pool.generations.len() + MAX_DENSE_GROWTH {
+                        foreign.insert(*entity, loc);
+                        pool.foreign.insert(*entity);
+                        continue;
+                    }
+
+                    if slots.len() <= idx {
+                        slots.resize(
+                            idx + 1,
+                            Slot {
+                                generation: 0,
+                                location: None,
+                            },
+                        );
+                    }
+
+                    let slot = &mut slots[idx];
+                    match slot.location.replace(loc) {
+                        Some(previous) => removed.push(previous),
+                        None => pool.claim(*entity),
+                    }
+                    slot.generation = entity.generation();
                 }
             }
         }
 
+        if let Some(next) = &self.next {
+            // IDs in the ranges of command buffers were allocated by those buffers
+            let own = ids
+                .iter()
+                .map(|entity| entity.0)
+                .filter(|id| id >> STREAM_BITS == 0);
+            if let Some(max) = own.max() {
+                next.fetch_max(max + 1, Ordering::Relaxed);
+            }
+        }
+
         self.len += ids.len() - removed.len();
 
         removed
@@ -170,27 +625,66 @@ impl LocationMap {
 
     /// Returns the location of an entity.
     pub fn get(&self, entity: Entity) -> Option<EntityLocation> {
-        let block = entity.0 / BLOCK_SIZE;
-        let idx = (entity.0 % BLOCK_SIZE) as usize;
-        if let Some(&result) = self.blocks.get(&block).and_then(|v| v.get(idx)) {
-            result
-        } else {
-            None
+        match &self.locations {
+            Locations::Blocks(blocks) => {
+                let block = entity.0 / BLOCK_SIZE;
+                let idx = (entity.0 % BLOCK_SIZE) as usize;
+                if let Some(&result) = blocks.get(&block).and_then(|v| v.get(idx)) {
+                    result
+                } else {
+                    None
+                }
+            }
+            Locations::Dense { slots, foreign, .. } => slots
+                .get(entity.index() as usize)
+                .filter(|slot| slot.generation == entity.generation())
+                .and_then(|slot| slot.location)
+                .or_else(|| foreign.get(&entity).copied()),
         }
     }
 
     /// Removes an entity from the location map.
     pub fn remove(&mut self, entity: Entity) -> Option<EntityLocation> {
-        let block = entity.0 / BLOCK_SIZE;
-        let idx = (entity.0 % BLOCK_SIZE) as usize;
-        if let Some(loc) = self.blocks.get_mut(&block).and_then(|v| v.get_mut(idx)) {
-            let original = loc.take();
-            if original.is_some() {
-                self.len -= 1;
-            }
-            original
-        } else {
-            None
+        let original = match &mut self.locations {
+            Locations::Blocks(blocks) => {
+                let block = entity.0 / BLOCK_SIZE;
+                let idx = (entity.0 % BLOCK_SIZE) as usize;
+                blocks
+                    .get_mut(&block)
+                    .and_then(|v| v.get_mut(idx))
+                    .and_then(|loc| loc.take())
+            }
+            Locations::Dense {
+                slots,
+                foreign,
+                ids,
+            } => {
+                if let Some(original) = foreign.remove(&entity) {
+                    ids.lock().foreign.remove(&entity);
+                    self.len -= 1;
+                    return Some(original);
+                }
+
+                let original = slots
+                    .get_mut(entity.index() as usize)
+                    .filter(|slot| slot.generation == entity.generation())
+                    .and_then(|slot| {
+                        let original = slot.location.take();
+                        if original.is_some() {
+                            slot.generation = entity.generation().wrapping_add(1);
+                        }
+                        original
+                    });
+                if original.is_some() {
+                    ids.lock().release(entity);
+                }
+                original
+            }
+        };
+
+        if original.is_some() {
+            self.len -= 1;
         }
+        original
     }
 }
@@ -113,7 +113,7 @@ impl<'a> EntryRef<'a> {
         self.components
             .get_downcast::<T>()
             .and_then(move |storage| storage.get_mut(archetype))
-            .and_then(move |slice| slice.into_slice().get_mut(component.0))
+            .and_then(move |slice| slice.into_component(component))
             .ok_or_else(|| ComponentError::NotFound {
                 component_type,
                 component_name: std::any::type_name::<T>(),
@@ -161,7 +161,7 @@ impl<'a> EntryRef<'a> {
         self.components
             .get_downcast::<T>()
             .and_then(move |storage| storage.get_mut(archetype))
-            .and_then(move |slice| slice.into_slice().get_mut(component.0))
+            .and_then(move |slice| slice.into_component(component))
             .ok_or_else(|| ComponentError::NotFound {
                 component_type,
                 component_name: std::any::type_name::<T>(),
@@ -245,7 +245,7 @@ impl<'a> EntryMut<'a> {
         self.components
             .get_downcast::<T>()
             .and_then(move |storage| storage.get_mut(archetype))
-            .and_then(move |slice| slice.into_slice().get_mut(component.0))
+            .and_then(move |slice| slice.into_component(component))
             .ok_or_else(|| ComponentError::NotFound {
                 component_type,
                 component_name: std::any::type_name::<T>(),
@@ -305,7 +305,7 @@ impl<'a> EntryMut<'a> {
         self.components
             .get_downcast::<T>()
             .and_then(move |storage| storage.get_mut(archetype))
-            .and_then(move |slice| slice.into_slice().get_mut(component.0))
+            .and_then(move |slice| slice.into_component(component))
             .ok_or_else(|| ComponentError::NotFound {
                 component_type,
                 component_name: std::any::type_name::<T>(),
@@ -376,7 +376,7 @@ impl<'a> Entry<'a> {
             .components()
             .get_downcast::<T>()
             .and_then(move |storage| storage.get_mut(archetype))
-            .and_then(move |slice| slice.into_slice().get_mut(component.0))
+            .and_then(move |slice| slice.into_component(component))
             .ok_or_else(|| ComponentError::NotFound {
                 component_type: ComponentTypeId::of::<T>(),
                 component_name: std::any::type_name::<T>(),
@@ -416,7 +416,7 @@ impl<'a> Entry<'a> {
             .components()
             .get_downcast::<T>()
             .and_then(move |storage| storage.get_mut(archetype))
-            .and_then(move |slice| slice.into_slice().get_mut(component.0))
+            .and_then(move |slice| slice.into_component(component))
             .ok_or_else(|| ComponentError::NotFound {
                 component_type: ComponentTypeId::of::<T>(),
                 component_name: std::any::type_name::<T>(),
@@ -485,14 +485,180 @@ impl<'a> Entry<'a> {
             self.location = EntityLocation::new(target_arch, idx);
         };
     }
+
+    /// Adds a set of components to the entity, moving the entity to its new archetype only
+    /// once. Components which the entity already has are replaced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// let mut world = World::default();
+    /// let entity = world.push((1usize,));
+    /// let mut entry = world.entry(entity).unwrap();
+    /// entry.add_components((2usize, false, 0.5f32));
+    /// assert_eq!(entry.get_component::<usize>(), Ok(&2usize));
+    /// assert_eq!(entry.get_component::<bool>(), Ok(&false));
+    /// ```
+    pub fn add_components<T: ComponentSet>(&mut self, components: T) {
+        let previous = self.archetype().layout().clone();
+        let layout = T::layout();
+        let (add, add_constructors): (Vec<_>, Vec<_>) = layout
+            .component_types()
+            .iter()
+            .zip(layout.component_constructors())
+            .filter(|(type_id, _)| !previous.has_component_by_id(**type_id))
+            .unzip();
+
+        if !add.is_empty() {
+            let target_arch = {
+                let mut source = DynamicArchetype {
+                    base: previous.clone(),
+                    add: &add,
+                    add_constructors: &add_constructors,
+                    remove: &[],
+                };
+                self.world.get_archetype_for_components(&mut source)
+            };
+            unsafe {
+                let idx = self.world.transfer_archetype(
+                    self.location.archetype(),
+                    target_arch,
+                    self.location.component(),
+                );
+                self.location = EntityLocation::new(target_arch, idx);
+            }
+        }
+
+        private::WriteComponents::write(
+            components,
+            &mut ComponentWriter {
+                entry: self,
+                previous,
+            },
+        );
+    }
+
+    /// Removes a set of components from the entity, moving the entity to its new archetype only
+    /// once. Components which the entity does not have are ignored.
+    pub fn remove_components<T: ComponentSet>(&mut self) {
+        let remove = T::layout()
+            .component_types()
+            .iter()
+            .copied()
+            .filter(|type_id| self.archetype().layout().has_component_by_id(*type_id))
+            .collect::<Vec<_>>();
+        if remove.is_empty() {
+            return;
+        }
+
+        let target_arch = {
+            let mut source = DynamicArchetype {
+                base: self.archetype().layout().clone(),
+                add: &[],
+                add_constructors: &[],
+                remove: &remove,
+            };
+            self.world.get_archetype_for_components(&mut source)
+        };
+        unsafe {
+            let idx = self.world.transfer_archetype(
+                self.location.archetype(),
+                target_arch,
+                self.location.component(),
+            );
+            self.location = EntityLocation::new(target_arch, idx);
+        };
+    }
 }
 
+/// A tuple of components which can be added to, or removed from, an entity together.
+///
+/// This trait is sealed, and is implemented for tuples of components.
+///
+/// See [Entry::add_components](struct.Entry.html#method.add_components).
+pub trait ComponentSet: private::WriteComponents {
+    /// Returns a layout containing each of the components in the set.
+    fn layout() -> EntityLayout;
+}
+
+mod private {
+    use super::ComponentWriter;
+
+    // Writes each of the components of a set into an entity. This trait cannot be named outside
+    // of the crate, so `ComponentSet` cannot be implemented elsewhere.
+    pub trait WriteComponents: 'static {
+        fn write(self, writer: &mut ComponentWriter);
+    }
+}
+
+// Writes the components of a `ComponentSet` into an entity, after the entity has been moved into
+// an archetype which contains all of them.
+pub struct ComponentWriter<'a, 'b> {
+    entry: &'a mut Entry<'b>,
+    previous: Arc<EntityLayout>,
+}
+
+impl<'a, 'b> ComponentWriter<'a, 'b> {
+    pub(crate) fn write<T: Component>(&mut self, component: T) {
+        if self.previous.has_component::<T>() {
+            *self.entry.get_component_mut::<T>().unwrap() = component;
+        } else {
+            unsafe {
+                self.entry
+                    .world
+                    .components_mut()
+                    .get_downcast_mut::<T>()
+                    .unwrap()
+                    .extend_memcopy(self.entry.location.archetype(), &component as *const T, 1);
+            }
+            std::mem::forget(component);
+        }
+    }
+}
+
+macro_rules! component_set {
+    ($head_ty:ident) => {
+        impl_component_set!($head_ty);
+    };
+    ($head_ty:ident, $( $tail_ty:ident ),*) => (
+        impl_component_set!($head_ty, $( $tail_ty ),*);
+        component_set!($( $tail_ty ),*);
+    );
+}
+
+macro_rules! impl_component_set {
+    ( $( $ty: ident ),* ) => {
+        impl<$( $ty: Component ),*> ComponentSet for ($( $ty, )*) {
+            fn layout() -> EntityLayout {
+                let mut layout = EntityLayout::new();
+                $( layout.register_component::<$ty>(); )*
+                layout
+            }
+        }
+
+        impl<$( $ty: Component ),*> private::WriteComponents for ($( $ty, )*) {
+            fn write(self, writer: &mut ComponentWriter) {
+                #![allow(non_snake_case)]
+                let ($( $ty, )*) = self;
+                $( writer.write($ty); )*
+            }
+        }
+    };
+}
+
+#[cfg(feature = "extended-tuple-impls")]
+component_set!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z);
+
+#[cfg(not(feature = "extended-tuple-impls"))]
+component_set!(A, B, C, D, E, F, G, H);
+
 #[derive(Clone)]
-struct DynamicArchetype<'a> {
-    base: Arc<EntityLayout>,
-    add: &'a [ComponentTypeId],
-    add_constructors: &'a [fn() -> Box<dyn UnknownComponentStorage>],
-    remove: &'a [ComponentTypeId],
+pub(crate) struct DynamicArchetype<'a> {
+    pub(crate) base: Arc<EntityLayout>,
+    pub(crate) add: &'a [ComponentTypeId],
+    pub(crate) add_constructors: &'a [fn() -> Box<dyn UnknownComponentStorage>],
+    pub(crate) remove: &'a [ComponentTypeId],
 }
 
 impl<'a> LayoutFilter for DynamicArchetype<'a> {
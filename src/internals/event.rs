@@ -5,7 +5,14 @@ use super::storage::{
     component::ComponentTypeId,
 };
 use std::iter::Iterator;
-use std::{fmt::Debug, sync::Arc};
+use std::{
+    fmt::Debug,
+    ptr::null_mut,
+    sync::{
+        atomic::{AtomicPtr, AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 /// Events emitted by a world to subscribers. See `World.subscribe(Sender, EntityFilter)`.
 #[derive(Debug, Clone)]
@@ -16,6 +23,45 @@ pub enum Event {
     EntityInserted(Entity, ArchetypeIndex),
     /// An entity has been removed from an archetype.
     EntityRemoved(Entity, ArchetypeIndex),
+    /// An entity has been deleted from the world. This follows the corresponding `EntityRemoved`
+    /// event, which is not followed by this event when the entity only moves between archetypes.
+    EntityDespawned(Entity, ArchetypeIndex),
+    /// An entity has moved from one archetype to another because components were added to
+    /// or removed from it. This follows the corresponding `EntityRemoved` and `EntityInserted` events.
+    EntityMoved {
+        /// The entity which was moved.
+        entity: Entity,
+        /// The archetype the entity was moved out of.
+        from: ArchetypeIndex,
+        /// The archetype the entity was moved into.
+        to: ArchetypeIndex,
+    },
+    /// A component has been added to an existing entity.
+    ComponentAdded {
+        /// The entity which gained the component.
+        entity: Entity,
+        /// The type of the component which was added.
+        component: ComponentTypeId,
+    },
+    /// A component has been removed from an entity which still exists.
+    ComponentRemoved {
+        /// The entity which lost the component.
+        entity: Entity,
+        /// The type of the component which was removed.
+        component: ComponentTypeId,
+    },
+    /// The last entity has been removed from an archetype. The archetype itself still exists
+    /// and may be populated again later.
+    ArchetypeEmptied(ArchetypeIndex),
+}
+
+/// Describes why an entity was removed from an archetype.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum RemovalKind {
+    /// The entity no longer exists in the world.
+    Despawned,
+    /// The entity has moved into another archetype within the same world.
+    Migrated,
 }
 
 /// Describes a type which can send entity events.
@@ -35,6 +81,241 @@ impl EventSender for crossbeam_channel::Sender<Event> {
     }
 }
 
+/// Describes what an [EventQueue](struct.EventQueue.html) does with events sent to it while it is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OverflowPolicy {
+    /// Discards the new event. The number of discarded events is reported by `EventQueue::dropped`.
+    DropNewest,
+    /// Discards the new event and unsubscribes the queue from the world.
+    Unsubscribe,
+}
+
+/// A lock-free, multi-producer event queue which can be subscribed to a world and drained later.
+///
+/// Event queues are cheap to clone, and all clones share the same queue. This allows one handle
+/// to be given to `World::subscribe` while another is stored in `Resources` and drained by a system.
+/// The queue is unsubscribed from the world once all other handles have been dropped.
+///
+/// # Examples
+///
+/// ```
+/// # use legion::*;
+/// # use legion::world::{Event, EventQueue};
+/// let mut world = World::default();
+/// let mut resources = Resources::default();
+///
+/// let events = EventQueue::unbounded();
+/// world.subscribe(events.clone(), any());
+/// resources.insert(events);
+///
+/// world.push((1usize,));
+///
+/// let events = resources.get::<EventQueue>().unwrap();
+/// let inserted = events
+///     .drain()
+///     .filter(|event| matches!(event, Event::EntityInserted(..)))
+///     .count();
+/// assert_eq!(inserted, 1);
+/// ```
+#[derive(Clone)]
+pub struct EventQueue {
+    inner: Arc<QueueInner>,
+}
+
+struct QueueNode {
+    event: Event,
+    next: *mut QueueNode,
+}
+
+struct QueueInner {
+    // most recently pushed node, linked towards older nodes
+    head: AtomicPtr<QueueNode>,
+    len: AtomicUsize,
+    dropped: AtomicUsize,
+    capacity: Option<usize>,
+    policy: OverflowPolicy,
+}
+
+impl Drop for QueueInner {
+    fn drop(&mut self) {
+        let mut node = *self.head.get_mut();
+        while !node.is_null() {
+            let boxed = unsafe { Box::from_raw(node) };
+            node = boxed.next;
+        }
+    }
+}
+
+impl EventQueue {
+    /// Constructs a new queue which can grow without limit.
+    pub fn unbounded() -> Self {
+        Self::with_capacity(None, OverflowPolicy::DropNewest)
+    }
+
+    /// Constructs a new queue which holds at most `capacity` events. Events sent while the
+    /// queue is full are handled according to the given overflow policy.
+    pub fn bounded(capacity: usize, policy: OverflowPolicy) -> Self {
+        Self::with_capacity(Some(capacity), policy)
+    }
+
+    fn with_capacity(capacity: Option<usize>, policy: OverflowPolicy) -> Self {
+        Self {
+            inner: Arc::new(QueueInner {
+                head: AtomicPtr::new(null_mut()),
+                len: AtomicUsize::new(0),
+                dropped: AtomicUsize::new(0),
+                capacity,
+                policy,
+            }),
+        }
+    }
+
+    /// Returns the maximum number of events the queue can hold, or `None` if it is unbounded.
+    pub fn capacity(&self) -> Option<usize> {
+        self.inner.capacity
+    }
+
+    /// Returns the number of events currently held in the queue.
+    pub fn len(&self) -> usize {
+        self.inner.len.load(Ordering::Acquire)
+    }
+
+    /// Returns `true` if the queue holds no events.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the total number of events which have been discarded because the queue was full.
+    pub fn dropped(&self) -> usize {
+        self.inner.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Pushes an event onto the queue. Returns `false` if the event was discarded.
+    pub fn push(&self, event: Event) -> bool {
+        let inner = &*self.inner;
+        let len = inner.len.fetch_add(1, Ordering::AcqRel);
+        if let Some(capacity) = inner.capacity {
+            if len >= capacity {
+                inner.len.fetch_sub(1, Ordering::AcqRel);
+                inner.dropped.fetch_add(1, Ordering::Relaxed);
+                return false;
+            }
+        }
+
+        let node = Box::into_raw(Box::new(QueueNode {
+            event,
+            next: null_mut(),
+        }));
+        let mut head = inner.head.load(Ordering::Relaxed);
+        loop {
+            // safety: the node is not visible to other threads until the exchange succeeds
+            unsafe { (*node).next = head };
+            match inner
+                .head
+                .compare_exchange_weak(head, node, Ordering::Release, Ordering::Relaxed)
+            {
+                Ok(_) => return true,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    /// Removes all events currently held in the queue, returning them in the order in which they
+    /// were sent. Events sent while the returned iterator is alive remain in the queue.
+    pub fn drain(&self) -> EventDrain {
+        let mut node = self.inner.head.swap(null_mut(), Ordering::Acquire);
+
+        // reverse the taken list into send order
+        let mut head = null_mut();
+        let mut count = 0;
+        while !node.is_null() {
+            // safety: the swap above gave us exclusive ownership of every node in the list
+            unsafe {
+                let next = (*node).next;
+                (*node).next = head;
+                head = node;
+                node = next;
+            }
+            count += 1;
+        }
+        self.inner.len.fetch_sub(count, Ordering::AcqRel);
+
+        EventDrain { head, len: count }
+    }
+}
+
+impl Default for EventQueue {
+    fn default() -> Self {
+        Self::unbounded()
+    }
+}
+
+impl Debug for EventQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventQueue")
+            .field("len", &self.len())
+            .field("capacity", &self.capacity())
+            .field("dropped", &self.dropped())
+            .finish()
+    }
+}
+
+impl EventSender for EventQueue {
+    fn send(&self, event: Event) -> bool {
+        // the world's subscription holds the last handle, nobody can drain the queue
+        if Arc::strong_count(&self.inner) == 1 {
+            return false;
+        }
+
+        self.push(event) || self.inner.policy != OverflowPolicy::Unsubscribe
+    }
+}
+
+/// An iterator over the events drained from an [EventQueue](struct.EventQueue.html).
+pub struct EventDrain {
+    head: *mut QueueNode,
+    len: usize,
+}
+
+// safety: the drain has exclusive ownership of its nodes, which only contain `Event`s
+unsafe impl Send for EventDrain {}
+unsafe impl Sync for EventDrain {}
+
+impl Iterator for EventDrain {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.head.is_null() {
+            return None;
+        }
+
+        let node = unsafe { Box::from_raw(self.head) };
+        self.head = node.next;
+        self.len -= 1;
+        Some(node.event)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl ExactSizeIterator for EventDrain {}
+
+impl Drop for EventDrain {
+    fn drop(&mut self) {
+        for _ in self {}
+    }
+}
+
+impl Debug for EventDrain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventDrain")
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct Subscriber {
     filter: Arc<dyn LayoutFilter + Send + Sync>,
@@ -62,6 +343,10 @@ impl Subscriber {
     pub(crate) fn send(&self, message: Event) -> bool {
         self.sender.send(message)
     }
+
+    fn is_same(&self, other: &Subscriber) -> bool {
+        Arc::ptr_eq(&self.sender, &other.sender)
+    }
 }
 
 #[derive(Clone, Default)]
@@ -82,6 +367,21 @@ impl Subscribers {
         }
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.subscribers.is_empty()
+    }
+
+    /// Returns the subscribers present in either `self` or `other`, without duplicates.
+    pub fn union(&self, other: &Subscribers) -> Self {
+        let mut subscribers = self.subscribers.clone();
+        for subscriber in &other.subscribers {
+            if !subscribers.iter().any(|sub| sub.is_same(subscriber)) {
+                subscribers.push(subscriber.clone());
+            }
+        }
+        Self { subscribers }
+    }
+
     pub fn matches_layout(&self, components: &[ComponentTypeId]) -> Self {
         Self {
             subscribers: self
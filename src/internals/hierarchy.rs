@@ -0,0 +1,489 @@
+//! Contains types related to parent-child relationships between entities.
+
+use super::{
+    entity::Entity,
+    world::{EntityStore, World},
+};
+
+/// A component which refers to the parent of an entity.
+///
+/// `Parent` and [Children](struct.Children.html) components are maintained together by the
+/// world's hierarchy methods, such as [World::set_parent](../world/struct.World.html#method.set_parent),
+/// and so cannot be constructed directly.
+///
+/// Register `Parent` with [Duplicate::register_clone](../world/struct.Duplicate.html#method.register_clone)
+/// (not `register_copy`) so that it is remapped to the cloned parent by `World::clone_from`.
+// this type must not be `Copy`, as the derived `Clone` would then bypass entity remapping
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct Parent(Entity);
+
+impl Parent {
+    /// Gets the parent entity.
+    pub fn entity(&self) -> Entity {
+        self.0
+    }
+}
+
+/// A component which lists the children of an entity, in the order in which they were added.
+///
+/// Entities without children do not have a `Children` component.
+/// See [Parent](struct.Parent.html).
+#[derive(Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct Children(Vec<Entity>);
+
+impl Clone for Children {
+    fn clone(&self) -> Self {
+        // `Vec<Entity>::clone` copies its elements, bypassing entity remapping
+        #[allow(clippy::map_clone)]
+        Self(self.0.iter().map(Entity::clone).collect())
+    }
+}
+
+impl Children {
+    /// Gets the child entities.
+    pub fn entities(&self) -> &[Entity] {
+        &self.0
+    }
+
+    /// Returns an iterator over the child entities.
+    pub fn iter(&self) -> std::slice::Iter<'_, Entity> {
+        self.0.iter()
+    }
+
+    /// Returns the number of children.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if there are no children.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a Children {
+    type Item = &'a Entity;
+    type IntoIter = std::slice::Iter<'a, Entity>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Errors which may occur when modifying the hierarchy.
+#[derive(thiserror::Error, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum HierarchyError {
+    /// The entity does not exist.
+    #[error("entity {0:?} does not exist")]
+    EntityNotFound(Entity),
+    /// The parent is the child itself, or one of its descendants.
+    #[error(
+        "entity {parent:?} cannot be the parent of {child:?}, as it is one of its descendants"
+    )]
+    Cycle {
+        /// The entity which was to be re-parented.
+        child: Entity,
+        /// The proposed parent.
+        parent: Entity,
+    },
+}
+
+/// Returns an iterator over all descendants of an entity, in depth-first pre-order.
+///
+/// Each entity is yielded before its children, and children are visited in order. The root
+/// entity is not included.
+///
+/// The store must be able to read `Children` components. Inside a system, declare this with
+/// `read_component::<Children>()`.
+///
+/// # Examples
+///
+/// ```
+/// # use legion::*;
+/// # use legion::hierarchy::descendants;
+/// let mut world = World::default();
+/// let root = world.push((0usize,));
+/// let child = world.push((1usize,));
+/// let grandchild = world.push((2usize,));
+/// world.set_parent(child, root).unwrap();
+/// world.set_parent(grandchild, child).unwrap();
+///
+/// let entities = descendants(&world, root).collect::<Vec<_>>();
+/// assert_eq!(entities, vec![child, grandchild]);
+/// ```
+pub fn descendants<S: EntityStore>(store: &S, root: Entity) -> Descendants<'_, S> {
+    let mut iter = Descendants {
+        store,
+        stack: Vec::new(),
+    };
+    iter.push_children(root);
+    iter
+}
+
+/// A depth-first iterator over the descendants of an entity.
+///
+/// See [descendants](fn.descendants.html).
+pub struct Descendants<'a, S: EntityStore> {
+    store: &'a S,
+    stack: Vec<Entity>,
+}
+
+impl<'a, S: EntityStore> Descendants<'a, S> {
+    fn push_children(&mut self, entity: Entity) {
+        if let Ok(entry) = self.store.entry_ref(entity) {
+            if let Ok(children) = entry.get_component::<Children>() {
+                self.stack.extend(children.iter().rev());
+            }
+        }
+    }
+}
+
+impl<'a, S: EntityStore> Iterator for Descendants<'a, S> {
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Entity> {
+        let entity = self.stack.pop()?;
+        self.push_children(entity);
+        Some(entity)
+    }
+}
+
+/// Returns an iterator over the ancestors of an entity, starting with its parent.
+///
+/// The store must be able to read `Parent` components.
+pub fn ancestors<S: EntityStore>(store: &S, entity: Entity) -> Ancestors<'_, S> {
+    Ancestors {
+        store,
+        current: Some(entity),
+    }
+}
+
+/// An iterator over the ancestors of an entity.
+///
+/// See [ancestors](fn.ancestors.html).
+pub struct Ancestors<'a, S: EntityStore> {
+    store: &'a S,
+    current: Option<Entity>,
+}
+
+impl<'a, S: EntityStore> Iterator for Ancestors<'a, S> {
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Entity> {
+        let entry = self.store.entry_ref(self.current?).ok();
+        self.current =
+            entry.and_then(|entry| entry.get_component::<Parent>().ok().map(Parent::entity));
+        self.current
+    }
+}
+
+impl World {
+    /// Sets the parent of an entity, replacing its previous parent if it had one.
+    ///
+    /// The child's [Parent](../hierarchy/struct.Parent.html) component and the parent's
+    /// [Children](../hierarchy/struct.Children.html) component are updated together.
+    ///
+    /// Returns an error if either entity does not exist, or if the parent is the child itself
+    /// or one of its descendants.
+    pub fn set_parent(&mut self, child: Entity, parent: Entity) -> Result<(), HierarchyError> {
+        if !self.contains(child) {
+            return Err(HierarchyError::EntityNotFound(child));
+        }
+        if !self.contains(parent) {
+            return Err(HierarchyError::EntityNotFound(parent));
+        }
+        if child == parent || ancestors(self, parent).any(|ancestor| ancestor == child) {
+            return Err(HierarchyError::Cycle { child, parent });
+        }
+
+        match ancestors(self, child).next() {
+            Some(previous) if previous == parent => return Ok(()),
+            Some(previous) => self.remove_child(previous, child),
+            None => {}
+        }
+
+        let mut entry = self.entry(child).unwrap();
+        entry.add_component(Parent(parent));
+
+        let mut entry = self.entry(parent).unwrap();
+        match entry.get_component_mut::<Children>() {
+            Ok(children) => children.0.push(child),
+            Err(_) => entry.add_component(Children(vec![child])),
+        }
+
+        Ok(())
+    }
+
+    /// Removes the parent of an entity, making it a root. Returns the previous parent.
+    pub fn remove_parent(&mut self, child: Entity) -> Option<Entity> {
+        let mut entry = self.entry(child)?;
+        let parent = entry.get_component::<Parent>().ok()?.entity();
+        entry.remove_component::<Parent>();
+        self.remove_child(parent, child);
+        Some(parent)
+    }
+
+    /// Removes an entity and all of its descendants from the world. Returns `true` if the entity
+    /// was removed.
+    pub fn remove_recursive(&mut self, entity: Entity) -> bool {
+        if !self.contains(entity) {
+            return false;
+        }
+
+        self.remove_parent(entity);
+        let descendants = descendants(self, entity).collect::<Vec<_>>();
+        self.remove_detached(entity);
+        for descendant in descendants {
+            self.remove_detached(descendant);
+        }
+        true
+    }
+
+    /// Removes an entity from the children of its parent, and removes the parents of its
+    /// children, in preparation for the entity's removal.
+    pub(crate) fn detach(&mut self, entity: Entity) {
+        let (parent, children) = match self.entry(entity) {
+            Some(entry) => {
+                let layout = entry.archetype().layout();
+                if !layout.has_component::<Parent>() && !layout.has_component::<Children>() {
+                    return;
+                }
+                (
+                    entry.get_component::<Parent>().ok().map(Parent::entity),
+                    entry
+                        .get_component::<Children>()
+                        .ok()
+                        .map(|children| children.0.to_vec()),
+                )
+            }
+            None => return,
+        };
+
+        if let Some(parent) = parent {
+            self.remove_child(parent, entity);
+        }
+        for child in children.into_iter().flatten() {
+            if let Some(mut entry) = self.entry(child) {
+                entry.remove_component::<Parent>();
+            }
+        }
+    }
+
+    /// Links a clone of an entity into the hierarchy. The clone takes the cloned parent, while
+    /// the cloned children remain the children of the original entity only.
+    pub(crate) fn relink_hierarchy(&mut self, clone: Entity) {
+        let parent = match self.entry(clone) {
+            Some(mut entry) => {
+                let layout = entry.archetype().layout();
+                if !layout.has_component::<Parent>() && !layout.has_component::<Children>() {
+                    return;
+                }
+                let parent = entry.get_component::<Parent>().ok().map(Parent::entity);
+                entry.remove_component::<Parent>();
+                entry.remove_component::<Children>();
+                parent
+            }
+            None => return,
+        };
+
+        if let Some(parent) = parent {
+            // the clone remains a root if its parent was not found
+            let _ = self.set_parent(clone, parent);
+        }
+    }
+
+    fn remove_child(&mut self, parent: Entity, child: Entity) {
+        if let Some(mut entry) = self.entry(parent) {
+            if let Ok(children) = entry.get_component_mut::<Children>() {
+                children.0.retain(|entity| *entity != child);
+                if children.0.is_empty() {
+                    entry.remove_component::<Children>();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::internals::{
+        query::{
+            filter::filter_fns::any,
+            view::{read::Read, try_read::TryRead},
+            IntoQuery,
+        },
+        world::Duplicate,
+    };
+
+    fn children(world: &World, entity: Entity) -> Vec<Entity> {
+        world
+            .entry_ref(entity)
+            .unwrap()
+            .get_component::<Children>()
+            .map(|children| children.entities().to_vec())
+            .unwrap_or_default()
+    }
+
+    fn parent(world: &World, entity: Entity) -> Option<Entity> {
+        world
+            .entry_ref(entity)
+            .unwrap()
+            .get_component::<Parent>()
+            .ok()
+            .map(Parent::entity)
+    }
+
+    #[test]
+    fn set_parent() {
+        let mut world = World::default();
+        let a = world.push((1usize,));
+        let b = world.push((2usize,));
+        let c = world.push((3usize,));
+
+        world.set_parent(b, a).unwrap();
+        world.set_parent(c, a).unwrap();
+        assert_eq!(children(&world, a), vec![b, c]);
+        assert_eq!(parent(&world, b), Some(a));
+
+        // re-parenting removes the child from its previous parent
+        world.set_parent(c, b).unwrap();
+        assert_eq!(children(&world, a), vec![b]);
+        assert_eq!(children(&world, b), vec![c]);
+        assert_eq!(ancestors(&world, c).collect::<Vec<_>>(), vec![b, a]);
+
+        assert_eq!(
+            world.set_parent(a, c),
+            Err(HierarchyError::Cycle {
+                child: a,
+                parent: c
+            })
+        );
+        assert_eq!(
+            world.set_parent(a, a),
+            Err(HierarchyError::Cycle {
+                child: a,
+                parent: a
+            })
+        );
+
+        assert_eq!(world.remove_parent(b), Some(a));
+        assert_eq!(world.remove_parent(b), None);
+        assert!(world
+            .entry_ref(a)
+            .unwrap()
+            .get_component::<Children>()
+            .is_err());
+    }
+
+    #[test]
+    fn remove() {
+        let mut world = World::default();
+        let root = world.push((1usize,));
+        let a = world.push((2usize,));
+        let b = world.push((3usize,));
+        let c = world.push((4usize,));
+        world.set_parent(a, root).unwrap();
+        world.set_parent(b, a).unwrap();
+        world.set_parent(c, root).unwrap();
+
+        // removing an entity detaches it from its parent and orphans its children
+        assert!(world.remove(a));
+        assert_eq!(children(&world, root), vec![c]);
+        assert_eq!(parent(&world, b), None);
+
+        world.set_parent(b, c).unwrap();
+        assert!(world.remove_recursive(c));
+        assert!(!world.contains(c));
+        assert!(!world.contains(b));
+        assert!(world
+            .entry_ref(root)
+            .unwrap()
+            .get_component::<Children>()
+            .is_err());
+        assert_eq!(world.len(), 1);
+    }
+
+    #[test]
+    fn depth_first() {
+        let mut world = World::default();
+        let entities = world.extend((0..6usize).map(|i| (i,))).to_vec();
+        world.set_parent(entities[1], entities[0]).unwrap();
+        world.set_parent(entities[2], entities[1]).unwrap();
+        world.set_parent(entities[3], entities[0]).unwrap();
+        world.set_parent(entities[4], entities[3]).unwrap();
+        world.set_parent(entities[5], entities[1]).unwrap();
+
+        let order = descendants(&world, entities[0])
+            .map(|entity| {
+                *world
+                    .entry_ref(entity)
+                    .unwrap()
+                    .get_component::<usize>()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec![1, 2, 5, 3, 4]);
+    }
+
+    #[test]
+    fn clone_from() {
+        let mut world = World::default();
+        let root = world.push((1usize,));
+        let child = world.push((2usize,));
+        world.set_parent(child, root).unwrap();
+
+        let mut merger = Duplicate::default();
+        merger.register_copy::<usize>();
+        merger.register_clone::<Parent>();
+        merger.register_clone::<Children>();
+
+        let mut cloned = World::default();
+        let mappings = cloned.clone_from(&world, &any(), &mut merger);
+        let (root, child) = (mappings[&root], mappings[&child]);
+        assert_eq!(children(&cloned, root), vec![child]);
+        assert_eq!(parent(&cloned, child), Some(root));
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn serialize() {
+        use crate::internals::serialize::Registry;
+        use serde::de::DeserializeSeed;
+
+        let mut world = World::default();
+        let root = world.push((1usize,));
+        let child = world.push((2usize,));
+        world.set_parent(child, root).unwrap();
+
+        let registry = || {
+            let mut registry = Registry::<String>::default();
+            registry.register::<usize>("usize".to_string());
+            registry.register::<Parent>("parent".to_string());
+            registry.register::<Children>("children".to_string());
+            registry
+        };
+        let json = serde_json::to_value(&world.as_serializable(any(), &registry())).unwrap();
+
+        let world: World = registry().as_deserialize().deserialize(json).unwrap();
+        let mut query = <(Read<usize>, TryRead<Parent>)>::query();
+        let mut entities = query
+            .iter(&world)
+            .map(|(value, parent)| (*value, parent.map(Parent::entity)))
+            .collect::<Vec<_>>();
+        entities.sort_by_key(|(value, _)| *value);
+        let (_, parent) = entities[1];
+        let parent = parent.unwrap();
+        assert_eq!(
+            world
+                .entry_ref(parent)
+                .unwrap()
+                .get_component::<usize>()
+                .unwrap(),
+            &1
+        );
+        assert_eq!(children(&world, parent).len(), 1);
+    }
+}
@@ -3,10 +3,13 @@ pub mod entity;
 pub mod entry;
 pub mod event;
 pub mod hash;
+pub mod hierarchy;
 pub mod insert;
 pub mod iter;
 pub mod permissions;
+pub mod prefab;
 pub mod query;
+pub mod relation;
 #[cfg(feature = "serialize")]
 pub mod serialize;
 pub mod storage;
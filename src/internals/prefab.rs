@@ -0,0 +1,556 @@
+//! Contains types related to prefabs, which are templates for groups of entities.
+
+use super::{
+    entity::{Entity, EntityHasher},
+    entry::Entry,
+    hierarchy::{Children, Parent},
+    query::{filter::filter_fns::any, view::read::Read, IntoQuery},
+    relation::is_relation,
+    storage::component::{Component, ComponentTypeId},
+    world::{AssignIds, Merger, World},
+};
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Unique identifier for a [prefab](struct.Prefab.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PrefabId(u64);
+static PREFAB_ID_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+impl PrefabId {
+    fn next() -> Self {
+        PrefabId(PREFAB_ID_COUNTER.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Unique identifier for an [instance](struct.Instance.html) of a prefab.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InstanceId(u64);
+static INSTANCE_ID_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+impl InstanceId {
+    fn next() -> Self {
+        InstanceId(INSTANCE_ID_COUNTER.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// A template world, copies of whose entities can be instantiated into other worlds with
+/// [World::instantiate](../world/struct.World.html#method.instantiate).
+pub struct Prefab {
+    id: PrefabId,
+    world: World,
+}
+
+impl Prefab {
+    /// Creates a new prefab from a template world.
+    pub fn new(world: World) -> Self {
+        Self {
+            id: PrefabId::next(),
+            world,
+        }
+    }
+
+    /// Returns the prefab's unique ID.
+    pub fn id(&self) -> PrefabId {
+        self.id
+    }
+
+    /// Gets the prefab's template world.
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// Gets the prefab's template world mutably. Changes are applied to existing instances by
+    /// [World::sync_prefab](../world/struct.World.html#method.sync_prefab).
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    /// Consumes the prefab, returning its template world.
+    pub fn into_world(self) -> World {
+        self.world
+    }
+}
+
+impl From<World> for Prefab {
+    fn from(world: World) -> Self {
+        Self::new(world)
+    }
+}
+
+/// A component added to each instantiated entity, linking it to the prefab entity it was
+/// cloned from.
+///
+/// When a prefab's template contains instances of other prefabs, the links of the template's
+/// entities are kept in the template, and the instantiated entities are linked to the outer
+/// prefab.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PrefabInstance {
+    prefab: PrefabId,
+    instance: InstanceId,
+    entity: Entity,
+    overridden: Vec<ComponentTypeId>,
+}
+
+impl PrefabInstance {
+    /// Returns the ID of the prefab which the entity was instantiated from.
+    pub fn prefab(&self) -> PrefabId {
+        self.prefab
+    }
+
+    /// Returns the ID of the instance which the entity belongs to.
+    pub fn instance(&self) -> InstanceId {
+        self.instance
+    }
+
+    /// Returns the entity in the prefab's template world which the entity was cloned from.
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Returns the types of the components which were overridden when the entity was
+    /// instantiated.
+    pub fn overridden(&self) -> &[ComponentTypeId] {
+        &self.overridden
+    }
+}
+
+type Override = Box<dyn FnOnce(&mut Entry) + Send + Sync>;
+
+/// Components which are added to particular entities of an instance after it has been
+/// instantiated, replacing those cloned from the prefab.
+#[derive(Default)]
+pub struct Overrides {
+    overrides: Vec<(Entity, ComponentTypeId, Override)>,
+}
+
+impl Overrides {
+    /// Creates an empty set of overrides.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a component on the instance of the given prefab entity.
+    pub fn set<T: Component>(&mut self, entity: Entity, component: T) {
+        self.overrides.push((
+            entity,
+            ComponentTypeId::of::<T>(),
+            Box::new(move |entry: &mut Entry| entry.add_component(component)),
+        ));
+    }
+
+    /// Sets a component on the instance of the given prefab entity.
+    pub fn with<T: Component>(mut self, entity: Entity, component: T) -> Self {
+        self.set(entity, component);
+        self
+    }
+
+    /// Returns `true` if there are no overrides.
+    pub fn is_empty(&self) -> bool {
+        self.overrides.is_empty()
+    }
+}
+
+/// The entities created by instantiating a [prefab](struct.Prefab.html).
+#[derive(Debug, Clone)]
+pub struct Instance {
+    id: InstanceId,
+    prefab: PrefabId,
+    entities: HashMap<Entity, Entity, EntityHasher>,
+}
+
+impl Instance {
+    pub(crate) fn allocate(prefab: &Prefab, mut allocator: impl FnMut() -> Entity) -> Self {
+        let entities = prefab
+            .world
+            .archetypes()
+            .iter()
+            .flat_map(|arch| arch.entities())
+            .map(|entity| (*entity, allocator()))
+            .collect();
+        Self {
+            id: InstanceId::next(),
+            prefab: prefab.id,
+            entities,
+        }
+    }
+
+    /// Returns the instance's unique ID.
+    pub fn id(&self) -> InstanceId {
+        self.id
+    }
+
+    /// Returns the ID of the prefab which was instantiated.
+    pub fn prefab(&self) -> PrefabId {
+        self.prefab
+    }
+
+    /// Gets the instance of the given prefab entity.
+    pub fn get(&self, entity: Entity) -> Option<Entity> {
+        self.entities.get(&entity).copied()
+    }
+
+    /// Returns an iterator over each prefab entity and its instance.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, Entity)> + '_ {
+        self.entities
+            .iter()
+            .map(|(prefab, instance)| (*prefab, *instance))
+    }
+
+    /// Returns the number of entities in the instance.
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Returns `true` if the instance contains no entities.
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+impl World {
+    /// Instantiates a copy of each of a prefab's entities into this world.
+    ///
+    /// A [merger](struct.Merger.html), such as [Duplicate](struct.Duplicate.html), describes how
+    /// each component is cloned. References between the prefab's entities are rewritten to
+    /// refer to their instances. The `overrides` are then applied, and each instantiated entity
+    /// is given a [PrefabInstance](../prefab/struct.PrefabInstance.html) component linking it to
+    /// the prefab entity it was cloned from.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an override is given for an entity which is not in the prefab.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # use legion::prefab::{Overrides, Prefab};
+    /// # use legion::world::Duplicate;
+    /// let mut template = World::default();
+    /// let ship = template.push((100usize, false));
+    /// let prefab = Prefab::new(template);
+    ///
+    /// let mut merger = Duplicate::default();
+    /// merger.register_copy::<usize>();
+    /// merger.register_copy::<bool>();
+    ///
+    /// let mut world = World::default();
+    /// let instance = world.instantiate(&prefab, Overrides::new().with(ship, true), &mut merger);
+    ///
+    /// let entry = world.entry(instance.get(ship).unwrap()).unwrap();
+    /// assert_eq!(entry.get_component::<usize>(), Ok(&100usize));
+    /// assert_eq!(entry.get_component::<bool>(), Ok(&true));
+    /// ```
+    pub fn instantiate<M: Merger>(
+        &mut self,
+        prefab: &Prefab,
+        overrides: Overrides,
+        merger: &mut M,
+    ) -> Instance {
+        let mut allocator = self.entity_allocator();
+        let instance = Instance::allocate(prefab, || allocator.next().unwrap());
+        self.instantiate_as(prefab, &instance, overrides, merger);
+        instance
+    }
+
+    /// Instantiates a prefab, giving its entities the IDs in the given instance.
+    pub(crate) fn instantiate_as<M: Merger>(
+        &mut self,
+        prefab: &Prefab,
+        instance: &Instance,
+        overrides: Overrides,
+        merger: &mut M,
+    ) {
+        self.clone_from(
+            &prefab.world,
+            &any(),
+            &mut AssignIds {
+                merger,
+                ids: &instance.entities,
+            },
+        );
+
+        let mut overridden = HashMap::<_, Vec<_>, EntityHasher>::default();
+        for (entity, type_id, _) in &overrides.overrides {
+            overridden.entry(*entity).or_default().push(*type_id);
+        }
+        let links = instance
+            .iter()
+            .map(|(entity, clone)| {
+                let link = PrefabInstance {
+                    prefab: prefab.id,
+                    instance: instance.id,
+                    entity,
+                    overridden: overridden.remove(&entity).unwrap_or_default(),
+                };
+                (clone, link)
+            })
+            .collect();
+        self.add_components_batch(links);
+
+        for (entity, _, apply) in overrides.overrides {
+            let clone = instance.get(entity).expect("entity not found in prefab");
+            apply(&mut self.entry(clone).unwrap());
+        }
+    }
+
+    /// Applies changes made to a prefab's template world to each of its instances in this
+    /// world. Returns the number of instances updated.
+    ///
+    /// The components of each instantiated entity are replaced by clones of its template
+    /// entity's components, made by the [merger](struct.Merger.html) as in
+    /// [instantiate](#method.instantiate). Components which were overridden when the entity was
+    /// instantiated are kept, as are components which the template entity does not have. The
+    /// hierarchy and relations of instantiated entities are not changed, and entities which
+    /// have been added to or removed from the template are not added to or removed from its
+    /// instances.
+    ///
+    /// Nested prefabs are updated from the inside out: sync the instances of an inner prefab in
+    /// the outer prefab's [template world](../prefab/struct.Prefab.html#method.world_mut), and
+    /// then sync the instances of the outer prefab.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # use legion::prefab::{Overrides, Prefab};
+    /// # use legion::world::Duplicate;
+    /// let mut template = World::default();
+    /// let ship = template.push((100usize,));
+    /// let mut prefab = Prefab::new(template);
+    ///
+    /// let mut merger = Duplicate::default();
+    /// merger.register_copy::<usize>();
+    ///
+    /// let mut world = World::default();
+    /// let instance = world.instantiate(&prefab, Overrides::new(), &mut merger);
+    ///
+    /// *prefab.world_mut().entry(ship).unwrap().get_component_mut::<usize>().unwrap() = 200;
+    /// assert_eq!(world.sync_prefab(&prefab, &mut merger), 1);
+    ///
+    /// let entry = world.entry(instance.get(ship).unwrap()).unwrap();
+    /// assert_eq!(entry.get_component::<usize>(), Ok(&200usize));
+    /// ```
+    pub fn sync_prefab<M: Merger>(&mut self, prefab: &Prefab, merger: &mut M) -> usize {
+        let mut instances = HashMap::<InstanceId, HashMap<_, _, EntityHasher>>::new();
+        let mut overridden = HashMap::<_, _, EntityHasher>::default();
+        let mut query = <(Entity, Read<PrefabInstance>)>::query();
+        for (entity, link) in query
+            .iter(self)
+            .filter(|(_, link)| link.prefab == prefab.id)
+        {
+            instances
+                .entry(link.instance)
+                .or_default()
+                .insert(link.entity, *entity);
+            overridden.insert(*entity, link.overridden.clone());
+        }
+
+        let keep = |entity: Entity, type_id: ComponentTypeId| {
+            type_id == ComponentTypeId::of::<PrefabInstance>()
+                || type_id == ComponentTypeId::of::<Parent>()
+                || type_id == ComponentTypeId::of::<Children>()
+                || is_relation(type_id)
+                || matches!(overridden.get(&entity), Some(types) if types.contains(&type_id))
+        };
+
+        for ids in instances.values_mut() {
+            // template entities which are no longer part of the instance are staged under
+            // unused IDs, and are dropped with the staging world
+            let mut allocator = self.entity_allocator();
+            for arch in prefab.world.archetypes() {
+                for entity in arch.entities() {
+                    ids.entry(*entity)
+                        .or_insert_with(|| allocator.next().unwrap());
+                }
+            }
+
+            let mut staging = World::default();
+            staging.clone_from(&prefab.world, &any(), &mut AssignIds { merger, ids });
+            self.replace_components_from(&mut staging, keep);
+        }
+
+        instances.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::internals::{
+        systems::{command::CommandBuffer, resources::Resources},
+        world::{Duplicate, EntityStore},
+    };
+    use std::sync::Arc;
+
+    fn merger() -> Duplicate {
+        let mut merger = Duplicate::default();
+        merger.register_copy::<usize>();
+        merger.register_copy::<bool>();
+        merger.register_clone::<Parent>();
+        merger.register_clone::<Children>();
+        merger
+    }
+
+    #[test]
+    fn instantiate() {
+        let mut template = World::default();
+        let root = template.push((1usize,));
+        let child = template.push((2usize,));
+        template.set_parent(child, root).unwrap();
+        let prefab = Prefab::new(template);
+
+        let mut world = World::default();
+        let a = world.instantiate(&prefab, Overrides::new(), &mut merger());
+        let b = world.instantiate(&prefab, Overrides::new().with(child, 3usize), &mut merger());
+        assert_eq!(world.len(), 4);
+
+        for (instance, value) in [(&a, 2usize), (&b, 3usize)].iter() {
+            let root_clone = instance.get(root).unwrap();
+            let child_clone = instance.get(child).unwrap();
+            let entry = world.entry(child_clone).unwrap();
+            assert_eq!(entry.get_component::<usize>(), Ok(value));
+            assert_eq!(
+                entry.get_component::<Parent>().unwrap().entity(),
+                root_clone
+            );
+            assert_eq!(
+                entry.get_component::<PrefabInstance>().unwrap().entity(),
+                child
+            );
+        }
+
+        let mut query = Read::<PrefabInstance>::query();
+        assert!(query.iter(&world).all(|link| link.prefab() == prefab.id()));
+    }
+
+    #[test]
+    fn sync() {
+        let mut template = World::default();
+        let root = template.push((1usize,));
+        let child = template.push((2usize,));
+        template.set_parent(child, root).unwrap();
+        let mut prefab = Prefab::new(template);
+
+        let mut world = World::default();
+        let a = world.instantiate(&prefab, Overrides::new(), &mut merger());
+        let b = world.instantiate(&prefab, Overrides::new().with(child, 3usize), &mut merger());
+        let other = world.push((4usize,));
+        world.remove(a.get(root).unwrap());
+
+        let mut entry = prefab.world_mut().entry(child).unwrap();
+        *entry.get_component_mut::<usize>().unwrap() = 5;
+        entry.add_component(false);
+        assert_eq!(world.sync_prefab(&prefab, &mut merger()), 2);
+        assert_eq!(world.len(), 4);
+
+        let usize_of = |world: &World, entity: Entity| {
+            *world
+                .entry_ref(entity)
+                .unwrap()
+                .get_component::<usize>()
+                .unwrap()
+        };
+        assert_eq!(usize_of(&world, a.get(child).unwrap()), 5);
+        assert_eq!(usize_of(&world, b.get(child).unwrap()), 3);
+        assert_eq!(usize_of(&world, b.get(root).unwrap()), 1);
+        assert_eq!(usize_of(&world, other), 4);
+
+        // the hierarchy and links are kept, and components added to the template are cloned
+        let entry = world.entry(b.get(child).unwrap()).unwrap();
+        assert_eq!(entry.get_component::<bool>(), Ok(&false));
+        assert_eq!(
+            entry.get_component::<Parent>().map(Parent::entity),
+            Ok(b.get(root).unwrap())
+        );
+        let link = entry.get_component::<PrefabInstance>().unwrap();
+        assert_eq!(link.instance(), b.id());
+        assert_eq!(link.overridden(), &[ComponentTypeId::of::<usize>()]);
+        assert!(world
+            .entry(a.get(child).unwrap())
+            .unwrap()
+            .get_component::<Parent>()
+            .is_err());
+    }
+
+    #[test]
+    fn nested() {
+        let mut template = World::default();
+        let wheel = template.push((1usize,));
+        let mut inner = Prefab::new(template);
+
+        let mut template = World::default();
+        let wheels = template.instantiate(&inner, Overrides::new(), &mut merger());
+        let body = template.push((2usize,));
+        let mut outer = Prefab::new(template);
+        let outer_wheel = wheels.get(wheel).unwrap();
+
+        // cloned links to the inner prefab are replaced by links to the outer prefab
+        let mut links = merger();
+        links.register_clone::<PrefabInstance>();
+
+        let mut world = World::default();
+        let car = world.instantiate(&outer, Overrides::new(), &mut links);
+        assert_eq!(world.len(), 2);
+
+        // the template keeps its link to the inner prefab
+        let entry = world.entry(car.get(outer_wheel).unwrap()).unwrap();
+        let link = entry.get_component::<PrefabInstance>().unwrap();
+        assert_eq!(link.prefab(), outer.id());
+        assert_eq!(link.entity(), outer_wheel);
+        let template_link = outer
+            .world()
+            .entry_ref(outer_wheel)
+            .unwrap()
+            .into_component::<PrefabInstance>()
+            .unwrap()
+            .clone();
+        assert_eq!(template_link.prefab(), inner.id());
+        assert_eq!(template_link.entity(), wheel);
+
+        // changes to the inner prefab propagate through the outer template
+        *inner
+            .world_mut()
+            .entry(wheel)
+            .unwrap()
+            .get_component_mut::<usize>()
+            .unwrap() = 3;
+        assert_eq!(outer.world_mut().sync_prefab(&inner, &mut merger()), 1);
+        assert_eq!(world.sync_prefab(&outer, &mut links), 1);
+        let entry = world.entry(car.get(outer_wheel).unwrap()).unwrap();
+        assert_eq!(entry.get_component::<usize>(), Ok(&3usize));
+        assert_eq!(
+            entry.get_component::<PrefabInstance>().unwrap().prefab(),
+            outer.id()
+        );
+        let entry = world.entry(car.get(body).unwrap()).unwrap();
+        assert_eq!(entry.get_component::<usize>(), Ok(&2usize));
+    }
+
+    #[test]
+    fn instantiate_command() {
+        let mut template = World::default();
+        let root = template.push((1usize,));
+        let prefab = Arc::new(Prefab::new(template));
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let mut command = CommandBuffer::new(&world);
+        let instance =
+            command.instantiate(prefab.clone(), Overrides::new().with(root, false), merger);
+        let clone = instance.get(root).unwrap();
+        assert!(!world.contains(clone));
+
+        command.flush(&mut world, &mut resources);
+        let entry = world.entry(clone).unwrap();
+        assert_eq!(entry.get_component::<usize>(), Ok(&1usize));
+        assert_eq!(entry.get_component::<bool>(), Ok(&false));
+        assert_eq!(
+            entry.get_component::<PrefabInstance>().unwrap().prefab(),
+            prefab.id()
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use super::{
+    and::And, not::Not, or::Or, passthrough::Passthrough, ActiveFilter, DynamicFilter, FilterResult,
+};
+use crate::internals::{query::view::Fetch, storage::component::Component, world::WorldId};
+use std::{collections::HashMap, marker::PhantomData};
+
+/// A filter which detects components which have been newly added to their entities.
+///
+/// This filter will reject all entities which have not had the
+/// component inserted since the filter was last run. Moving an entity
+/// between archetypes does not count as an addition.
+#[derive(Debug)]
+pub struct EntityAddedFilter<T: Component> {
+    _phantom: PhantomData<T>,
+    history: HashMap<WorldId, u64>,
+    world: Option<WorldId>,
+    threshold: u64,
+    maximum: u64,
+}
+
+impl<T: Component> Default for EntityAddedFilter<T> {
+    fn default() -> Self {
+        Self {
+            _phantom: PhantomData,
+            history: Default::default(),
+            world: None,
+            threshold: 0,
+            maximum: 0,
+        }
+    }
+}
+
+impl<T: Component> Clone for EntityAddedFilter<T> {
+    fn clone(&self) -> Self {
+        Self {
+            _phantom: PhantomData,
+            history: self.history.clone(),
+            world: None,
+            threshold: 0,
+            maximum: 0,
+        }
+    }
+}
+
+impl<T: Component> ActiveFilter for EntityAddedFilter<T> {}
+
+impl<T: Component> DynamicFilter for EntityAddedFilter<T> {
+    fn prepare(&mut self, world: WorldId) {
+        if let Some(world) = self.world {
+            self.history.insert(world, self.maximum);
+        }
+
+        self.world = Some(world);
+        self.threshold = *self.history.entry(world).or_insert(0);
+        self.maximum = self.threshold;
+    }
+
+    fn matches_archetype<Fet: Fetch>(&mut self, fetch: &Fet) -> FilterResult {
+        if let Some(version) = fetch.version::<T>() {
+            if version > self.maximum {
+                self.maximum = version;
+            }
+            FilterResult::Match(version > self.threshold)
+        } else {
+            FilterResult::Defer
+        }
+    }
+
+    fn filters_entities() -> bool {
+        true
+    }
+
+    fn matches_entity<Fet: Fetch>(&mut self, fetch: &Fet, index: usize) -> FilterResult {
+        if let Some(versions) = fetch.added_versions::<T>() {
+            FilterResult::Match(versions[index] > self.threshold)
+        } else {
+            FilterResult::Defer
+        }
+    }
+}
+
+impl<T: Component> std::ops::Not for EntityAddedFilter<T> {
+    type Output = Not<Self>;
+
+    #[inline]
+    fn not(self) -> Self::Output {
+        Not { filter: self }
+    }
+}
+
+impl<T: Component, Rhs: ActiveFilter> std::ops::BitAnd<Rhs> for EntityAddedFilter<T> {
+    type Output = And<(Self, Rhs)>;
+
+    #[inline]
+    fn bitand(self, rhs: Rhs) -> Self::Output {
+        And {
+            filters: (self, rhs),
+        }
+    }
+}
+
+impl<T: Component> std::ops::BitAnd<Passthrough> for EntityAddedFilter<T> {
+    type Output = Self;
+
+    #[inline]
+    fn bitand(self, _: Passthrough) -> Self::Output {
+        self
+    }
+}
+
+impl<T: Component, Rhs: ActiveFilter> std::ops::BitOr<Rhs> for EntityAddedFilter<T> {
+    type Output = Or<(Self, Rhs)>;
+
+    #[inline]
+    fn bitor(self, rhs: Rhs) -> Self::Output {
+        Or {
+            filters: (self, rhs),
+        }
+    }
+}
+
+impl<T: Component> std::ops::BitOr<Passthrough> for EntityAddedFilter<T> {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, _: Passthrough) -> Self::Output {
+        self
+    }
+}
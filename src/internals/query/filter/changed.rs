@@ -0,0 +1,128 @@
+use super::{
+    and::And, not::Not, or::Or, passthrough::Passthrough, ActiveFilter, DynamicFilter, FilterResult,
+};
+use crate::internals::{query::view::Fetch, storage::component::Component, world::WorldId};
+use std::{collections::HashMap, marker::PhantomData};
+
+/// A filter which performs fine-grained change detection.
+///
+/// This filter will reject all components which have not been
+/// changed since the filter was last run.
+#[derive(Debug)]
+pub struct EntityChangedFilter<T: Component> {
+    _phantom: PhantomData<T>,
+    history: HashMap<WorldId, u64>,
+    world: Option<WorldId>,
+    threshold: u64,
+    maximum: u64,
+}
+
+impl<T: Component> Default for EntityChangedFilter<T> {
+    fn default() -> Self {
+        Self {
+            _phantom: PhantomData,
+            history: Default::default(),
+            world: None,
+            threshold: 0,
+            maximum: 0,
+        }
+    }
+}
+
+impl<T: Component> Clone for EntityChangedFilter<T> {
+    fn clone(&self) -> Self {
+        Self {
+            _phantom: PhantomData,
+            history: self.history.clone(),
+            world: None,
+            threshold: 0,
+            maximum: 0,
+        }
+    }
+}
+
+impl<T: Component> ActiveFilter for EntityChangedFilter<T> {}
+
+impl<T: Component> DynamicFilter for EntityChangedFilter<T> {
+    fn prepare(&mut self, world: WorldId) {
+        if let Some(world) = self.world {
+            self.history.insert(world, self.maximum);
+        }
+
+        self.world = Some(world);
+        self.threshold = *self.history.entry(world).or_insert(0);
+        self.maximum = self.threshold;
+    }
+
+    fn matches_archetype<Fet: Fetch>(&mut self, fetch: &Fet) -> FilterResult {
+        if let Some(version) = fetch.version::<T>() {
+            if version > self.maximum {
+                self.maximum = version;
+            }
+            FilterResult::Match(version > self.threshold)
+        } else {
+            FilterResult::Defer
+        }
+    }
+
+    fn filters_entities() -> bool {
+        true
+    }
+
+    fn matches_entity<Fet: Fetch>(&mut self, fetch: &Fet, index: usize) -> FilterResult {
+        if let Some(versions) = fetch.entity_versions::<T>() {
+            FilterResult::Match(versions[index] > self.threshold)
+        } else {
+            FilterResult::Defer
+        }
+    }
+}
+
+impl<T: Component> std::ops::Not for EntityChangedFilter<T> {
+    type Output = Not<Self>;
+
+    #[inline]
+    fn not(self) -> Self::Output {
+        Not { filter: self }
+    }
+}
+
+impl<T: Component, Rhs: ActiveFilter> std::ops::BitAnd<Rhs> for EntityChangedFilter<T> {
+    type Output = And<(Self, Rhs)>;
+
+    #[inline]
+    fn bitand(self, rhs: Rhs) -> Self::Output {
+        And {
+            filters: (self, rhs),
+        }
+    }
+}
+
+impl<T: Component> std::ops::BitAnd<Passthrough> for EntityChangedFilter<T> {
+    type Output = Self;
+
+    #[inline]
+    fn bitand(self, _: Passthrough) -> Self::Output {
+        self
+    }
+}
+
+impl<T: Component, Rhs: ActiveFilter> std::ops::BitOr<Rhs> for EntityChangedFilter<T> {
+    type Output = Or<(Self, Rhs)>;
+
+    #[inline]
+    fn bitor(self, rhs: Rhs) -> Self::Output {
+        Or {
+            filters: (self, rhs),
+        }
+    }
+}
+
+impl<T: Component> std::ops::BitOr<Passthrough> for EntityChangedFilter<T> {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, _: Passthrough) -> Self::Output {
+        self
+    }
+}
@@ -3,8 +3,10 @@
 use super::view::Fetch;
 use crate::internals::{storage::component::ComponentTypeId, world::WorldId};
 
+pub mod added;
 pub mod and;
 pub mod any;
+pub mod changed;
 pub mod component;
 pub mod maybe_changed;
 pub mod not;
@@ -14,7 +16,8 @@ pub mod try_component;
 
 pub mod filter_fns {
     use super::{
-        any::Any, component::ComponentFilter, maybe_changed::ComponentChangedFilter,
+        added::EntityAddedFilter, any::Any, changed::EntityChangedFilter,
+        component::ComponentFilter, maybe_changed::ComponentChangedFilter,
         passthrough::Passthrough, try_component::TryComponentFilter, EntityFilterTuple,
     };
     use crate::internals::storage::component::Component;
@@ -33,6 +36,25 @@ pub mod filter_fns {
         Default::default()
     }
 
+    /// Constructs a filter which requires that the component has been changed since the last
+    /// time the query was run.
+    ///
+    /// Unlike `maybe_changed`, this check is performed for each individual entity. Chunks yielded
+    /// by a query using this filter will be split such that they only contain changed entities.
+    pub fn changed<T: Component>(
+    ) -> EntityFilterTuple<TryComponentFilter<T>, EntityChangedFilter<T>> {
+        Default::default()
+    }
+
+    /// Constructs a filter which requires that the component has been added to the entity since
+    /// the last time the query was run.
+    ///
+    /// This check is performed for each individual entity. Entities which were moved into a new
+    /// archetype because some other component was added or removed are not considered added.
+    pub fn added<T: Component>() -> EntityFilterTuple<TryComponentFilter<T>, EntityAddedFilter<T>> {
+        Default::default()
+    }
+
     /// Constructs a filter which passes all entities.
     pub fn any() -> EntityFilterTuple<Any, Any> {
         Default::default()
@@ -115,7 +137,24 @@ pub trait DynamicFilter: Default + Send + Sync {
     fn prepare(&mut self, world: WorldId);
 
     /// Calculates the filter's result for the given archetype data.
+    ///
+    /// Filters which select individual entities should accept any archetype which may
+    /// contain matching entities.
     fn matches_archetype<F: Fetch>(&mut self, fetch: &F) -> FilterResult;
+
+    /// Returns `true` if the filter may reject individual entities within an archetype
+    /// that it has accepted.
+    fn filters_entities() -> bool {
+        false
+    }
+
+    /// Calculates the filter's result for a single entity within an accepted archetype.
+    ///
+    /// This is only called if `filters_entities` returns `true`. Filters which operate on
+    /// whole archetypes return their archetype result.
+    fn matches_entity<F: Fetch>(&mut self, fetch: &F, _index: usize) -> FilterResult {
+        self.matches_archetype(fetch)
+    }
 }
 
 /// A marker trait for filters that are not no-ops.
@@ -162,6 +201,15 @@ impl<T: EntityFilter> DynamicFilter for T {
         let (_, dynamic_filter) = self.filters();
         dynamic_filter.matches_archetype(fetch)
     }
+
+    fn filters_entities() -> bool {
+        T::Dynamic::filters_entities()
+    }
+
+    fn matches_entity<Fet: Fetch>(&mut self, fetch: &Fet, index: usize) -> FilterResult {
+        let (_, dynamic_filter) = self.filters();
+        dynamic_filter.matches_entity(fetch, index)
+    }
 }
 
 impl<T: EntityFilter> GroupMatcher for T {
@@ -68,6 +68,20 @@ macro_rules! impl_or_filter {
                 $( result = result.coalesce_or($ty.matches_archetype(fetch)); )*
                 result
             }
+
+            #[inline]
+            fn filters_entities() -> bool {
+                $( $ty::filters_entities() )||*
+            }
+
+            #[inline]
+            fn matches_entity<Fet: Fetch>(&mut self, fetch: &Fet, index: usize) -> FilterResult {
+                #![allow(non_snake_case)]
+                let ($( $ty, )*) = &mut self.filters;
+                let mut result = FilterResult::Defer;
+                $( result = result.coalesce_or($ty.matches_entity(fetch, index)); )*
+                result
+            }
         }
 
         impl<$( $ty ),*> std::ops::Not for Or<($( $ty, )*)> {
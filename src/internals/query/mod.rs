@@ -387,6 +387,7 @@ impl<V: IntoView, F: EntityFilter> Query<V, F> {
             archetypes: accessor.archetypes(),
             max_count: indices.len(),
             indices,
+            runs: None,
         }
     }
 
@@ -758,14 +759,22 @@ impl<V: IntoView, F: EntityFilter> Query<V, F> {
 /// Provides access to slices of components for entities which have the same component layout.
 ///
 /// A single index in any of the slices contained in a chunk belong to the same entity.
+///
+/// If the query uses a per-entity filter, such as `changed`, then a chunk may contain
+/// only a subset of the entities in its archetype.
 pub struct ChunkView<'a, F: Fetch> {
     archetype: &'a Archetype,
+    entities: &'a [Entity],
     fetch: F,
 }
 
 impl<'a, F: Fetch> ChunkView<'a, F> {
     fn new(archetype: &'a Archetype, fetch: F) -> Self {
-        Self { archetype, fetch }
+        Self {
+            archetype,
+            entities: archetype.entities(),
+            fetch,
+        }
     }
 
     /// Returns the archetype that all entities in the chunk belong to.
@@ -838,7 +847,7 @@ impl<'a, F: Fetch> ChunkView<'a, F> {
         <F as IntoIndexableIter>::IntoIter: 'a,
     {
         let iter = self.fetch.into_indexable_iter();
-        self.archetype.entities().iter().copied().zip(iter)
+        self.entities.iter().copied().zip(iter)
     }
 }
 
@@ -872,6 +881,7 @@ where
     filter: &'index mut D,
     archetypes: &'data [Archetype],
     max_count: usize,
+    runs: Option<EntityRuns<'data, V::Fetch>>,
 }
 
 impl<'world, 'query, V, D> Iterator for ChunkIter<'world, 'query, V, D>
@@ -882,20 +892,112 @@ where
     type Item = ChunkView<'world, V::Fetch>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(chunk) = self.runs.as_mut().and_then(|runs| runs.next()) {
+            return Some(chunk);
+        }
+
         for fetch in &mut self.inner {
             // if fetch is None here, filtering is broken
             let mut fetch = fetch.unwrap();
             let idx = self.indices.next().unwrap();
             if self.filter.matches_archetype(&fetch).is_pass() {
-                fetch.accepted();
-                return Some(ChunkView::new(&self.archetypes[*idx], fetch));
+                if D::filters_entities() {
+                    let mut runs = EntityRuns::new(&self.archetypes[*idx], fetch, self.filter);
+                    if let Some(chunk) = runs.next() {
+                        self.runs = Some(runs);
+                        return Some(chunk);
+                    }
+                } else {
+                    fetch.accepted();
+                    return Some(ChunkView::new(&self.archetypes[*idx], fetch));
+                }
             }
         }
         None
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        (0, Some(self.max_count))
+        if D::filters_entities() {
+            let max_count = EntityRuns::max_count(&self.runs, &self.indices, self.archetypes);
+            (0, Some(max_count))
+        } else {
+            (0, Some(self.max_count))
+        }
+    }
+}
+
+/// Splits an archetype's fetch into chunks for each contiguous run of
+/// entities which pass a per-entity filter.
+struct EntityRuns<'a, F: Fetch> {
+    archetype: &'a Archetype,
+    fetch: Option<F>,
+    offset: usize,
+    runs: std::vec::IntoIter<Range<usize>>,
+}
+
+impl<'a, F: Fetch> EntityRuns<'a, F> {
+    fn new<D: DynamicFilter>(archetype: &'a Archetype, mut fetch: F, filter: &mut D) -> Self {
+        let len = archetype.entities().len();
+        let mut runs = Vec::new();
+        let mut start = None;
+        for i in 0..len {
+            match (filter.matches_entity(&fetch, i).is_pass(), start) {
+                (true, None) => start = Some(i),
+                (false, Some(run_start)) => {
+                    runs.push(run_start..i);
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(run_start) = start {
+            runs.push(run_start..len);
+        }
+
+        // only trigger version increments if any entities are going to be provided to the user
+        if !runs.is_empty() {
+            fetch.accepted();
+        }
+
+        Self {
+            archetype,
+            fetch: Some(fetch),
+            offset: 0,
+            runs: runs.into_iter(),
+        }
+    }
+
+    // Returns the maximum number of chunks left to yield, given the pending runs and the
+    // archetypes which are yet to be filtered. Each archetype yields at most one run per entity.
+    fn max_count(
+        runs: &Option<Self>,
+        indices: &Iter<ArchetypeIndex>,
+        archetypes: &[Archetype],
+    ) -> usize {
+        let pending = runs.as_ref().map_or(0, |runs| runs.runs.len());
+        let remaining = indices
+            .clone()
+            .map(|index| archetypes[*index].entities().len())
+            .sum::<usize>();
+        pending + remaining
+    }
+}
+
+impl<'a, F: Fetch> Iterator for EntityRuns<'a, F> {
+    type Item = ChunkView<'a, F>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let run = self.runs.next()?;
+        let fetch = self.fetch.take()?;
+        let (_, fetch) = fetch.split_at(run.start - self.offset);
+        let (fetch, remaining) = fetch.split_at(run.end - run.start);
+        self.fetch = Some(remaining);
+        self.offset = run.end;
+        Some(ChunkView {
+            archetype: self.archetype,
+            entities: &self.archetype.entities()[run],
+            fetch,
+        })
     }
 }
 
@@ -943,6 +1045,7 @@ pub mod par_iter {
         filter: &'query Mutex<D>,
         archetypes: &'world [Archetype],
         max_count: usize,
+        runs: Option<EntityRuns<'world, V::Fetch>>,
     }
 
     impl<'world, 'query, V, D> Iterator for Iter<'world, 'query, V, D>
@@ -953,20 +1056,37 @@ pub mod par_iter {
         type Item = ChunkView<'world, V::Fetch>;
 
         fn next(&mut self) -> Option<Self::Item> {
+            if let Some(chunk) = self.runs.as_mut().and_then(|runs| runs.next()) {
+                return Some(chunk);
+            }
+
             let mut filter = self.filter.lock();
             for fetch in &mut self.inner {
                 let mut fetch = fetch.unwrap();
                 let idx = self.indices.next().unwrap();
                 if filter.matches_archetype(&fetch).is_pass() {
-                    fetch.accepted();
-                    return Some(ChunkView::new(&self.archetypes[*idx], fetch));
+                    if D::filters_entities() {
+                        let mut runs = EntityRuns::new(&self.archetypes[*idx], fetch, &mut *filter);
+                        if let Some(chunk) = runs.next() {
+                            self.runs = Some(runs);
+                            return Some(chunk);
+                        }
+                    } else {
+                        fetch.accepted();
+                        return Some(ChunkView::new(&self.archetypes[*idx], fetch));
+                    }
                 }
             }
             None
         }
 
         fn size_hint(&self) -> (usize, Option<usize>) {
-            (0, Some(self.max_count))
+            if D::filters_entities() {
+                let max_count = EntityRuns::max_count(&self.runs, &self.indices, self.archetypes);
+                (0, Some(max_count))
+            } else {
+                (0, Some(self.max_count))
+            }
         }
     }
 
@@ -1064,6 +1184,7 @@ pub mod par_iter {
                 archetypes: self.world.archetypes(),
                 max_count: indices.len(),
                 indices,
+                runs: None,
             };
             folder.consume_iter(iter)
         }
@@ -151,6 +151,25 @@ impl<'a> Fetch for EntityFetch<'a> {
         None
     }
 
+    #[inline]
+    fn entity_versions<C: Component>(&self) -> Option<&[u64]> {
+        None
+    }
+
+    #[inline]
+    fn added_versions<C: Component>(&self) -> Option<&[u64]> {
+        None
+    }
+
     #[inline]
     fn accepted(&mut self) {}
+
+    #[inline]
+    fn split_at(self, index: usize) -> (Self, Self) {
+        let (left, right) = self.entities.split_at(index);
+        (
+            EntityFetch { entities: left },
+            EntityFetch { entities: right },
+        )
+    }
 }
@@ -22,6 +22,7 @@ use std::marker::PhantomData;
 
 pub mod entity;
 pub mod read;
+pub mod sparse;
 pub mod try_read;
 pub mod try_write;
 pub mod write;
@@ -135,9 +136,25 @@ pub trait Fetch: IntoIndexableIter + Send + Sync {
     /// if this fetch contains the requested component type.
     fn version<T: Component>(&self) -> Option<u64>;
 
+    /// Tries to find the versions of each individual component in the slice,
+    /// if this fetch contains the requested component type.
+    fn entity_versions<T: Component>(&self) -> Option<&[u64]>;
+
+    /// Tries to find the versions at which each individual component in the slice was
+    /// added to its entity, if this fetch contains the requested component type.
+    fn added_versions<T: Component>(&self) -> Option<&[u64]>;
+
     /// Indicates that the archetype is going to be provided to the user.
     /// Component slice versions are incremented here.
     fn accepted(&mut self);
+
+    /// Splits the fetch into two fetches at the given entity index.
+    ///
+    /// The fetch must have already been accepted. Only the first half retains
+    /// access to the component slice versions.
+    fn split_at(self, index: usize) -> (Self, Self)
+    where
+        Self: Sized;
 }
 
 /// A fetch which only retrieves shared references to component data.
@@ -362,12 +379,52 @@ macro_rules! impl_view_tuple {
                 result
             }
 
+            #[inline]
+            fn entity_versions<Comp: Component>(&self) -> Option<&[u64]> {
+                #[allow(non_snake_case)]
+                let ($( $ty, )*) = &self.fetches;
+                let mut result = None;
+                $(
+                    result = result.or_else(|| $ty.entity_versions::<Comp>());
+                )*
+                result
+            }
+
+            #[inline]
+            fn added_versions<Comp: Component>(&self) -> Option<&[u64]> {
+                #[allow(non_snake_case)]
+                let ($( $ty, )*) = &self.fetches;
+                let mut result = None;
+                $(
+                    result = result.or_else(|| $ty.added_versions::<Comp>());
+                )*
+                result
+            }
+
             #[inline]
             fn accepted(&mut self) {
                 #[allow(non_snake_case)]
                 let ($( $ty, )*) = &mut self.fetches;
                 $( $ty.accepted(); )*
             }
+
+            paste::item! {
+                fn split_at(self, index: usize) -> (Self, Self) {
+                    #![allow(non_snake_case)]
+                    let ($( $ty, )*) = self.fetches;
+                    $( let ([<$ty _left>], [<$ty _right>]) = $ty.split_at(index); )*
+                    (
+                        MultiFetch {
+                            fetches: ($( [<$ty _left>], )*),
+                            _phantom: PhantomData,
+                        },
+                        MultiFetch {
+                            fetches: ($( [<$ty _right>], )*),
+                            _phantom: PhantomData,
+                        },
+                    )
+                }
+            }
         }
     };
 }
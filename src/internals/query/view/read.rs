@@ -137,6 +137,8 @@ impl<'a, T: Component> Iterator for ReadIter<'a, T> {
 #[doc(hidden)]
 pub struct ReadFetch<'a, T: Component> {
     version: &'a u64,
+    entity_versions: &'a [u64],
+    added_versions: &'a [u64],
     components: &'a [T],
 }
 
@@ -145,6 +147,8 @@ impl<'a, T: Component> From<ComponentSlice<'a, T>> for ReadFetch<'a, T> {
         ReadFetch {
             components: slice.components,
             version: slice.version,
+            entity_versions: slice.entity_versions,
+            added_versions: slice.added_versions,
         }
     }
 }
@@ -211,6 +215,45 @@ impl<'a, T: Component> Fetch for ReadFetch<'a, T> {
         }
     }
 
+    #[inline]
+    fn entity_versions<C: Component>(&self) -> Option<&[u64]> {
+        if TypeId::of::<C>() == TypeId::of::<T>() {
+            Some(self.entity_versions)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn added_versions<C: Component>(&self) -> Option<&[u64]> {
+        if TypeId::of::<C>() == TypeId::of::<T>() {
+            Some(self.added_versions)
+        } else {
+            None
+        }
+    }
+
     #[inline]
     fn accepted(&mut self) {}
+
+    #[inline]
+    fn split_at(self, index: usize) -> (Self, Self) {
+        let (left, right) = self.components.split_at(index);
+        let (left_versions, right_versions) = self.entity_versions.split_at(index);
+        let (left_added, right_added) = self.added_versions.split_at(index);
+        (
+            ReadFetch {
+                version: self.version,
+                entity_versions: left_versions,
+                added_versions: left_added,
+                components: left,
+            },
+            ReadFetch {
+                version: self.version,
+                entity_versions: right_versions,
+                added_versions: right_added,
+                components: right,
+            },
+        )
+    }
 }
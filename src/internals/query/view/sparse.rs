@@ -0,0 +1,424 @@
+#![doc(hidden)]
+
+use super::{DefaultFilter, Fetch, IntoIndexableIter, IntoView, ReadOnly, ReadOnlyFetch, View};
+use crate::internals::{
+    entity::Entity,
+    iter::indexed::{IndexedIter, TrustedRandomAccess},
+    permissions::Permissions,
+    query::{
+        filter::{any::Any, passthrough::Passthrough, EntityFilterTuple},
+        QueryResult,
+    },
+    storage::{
+        archetype::{Archetype, ArchetypeIndex},
+        component::{Component, ComponentTypeId},
+        sparse::SparseSet,
+        Components,
+    },
+    subworld::ComponentAccess,
+};
+use derivative::Derivative;
+use std::{any::TypeId, marker::PhantomData};
+
+/// Reads a single component type from [sparse storage](../storage/struct.SparseSet.html),
+/// yielding `None` for entities which do not have the component.
+///
+/// As sparse components are not part of an entity's layout, this view matches every archetype.
+/// Filters such as `component::<T>()` and `maybe_changed::<T>()` do not apply to sparse
+/// components; check the yielded `Option` instead.
+#[derive(Derivative, Debug, Copy, Clone)]
+#[derivative(Default(bound = ""))]
+pub struct TryReadSparse<T>(PhantomData<*const T>);
+
+unsafe impl<T> Send for TryReadSparse<T> {}
+unsafe impl<T: Sync> Sync for TryReadSparse<T> {}
+unsafe impl<T> ReadOnly for TryReadSparse<T> {}
+
+impl<T: Component> DefaultFilter for TryReadSparse<T> {
+    type Filter = EntityFilterTuple<Any, Passthrough>;
+}
+
+impl<T: Component> IntoView for TryReadSparse<T> {
+    type View = Self;
+}
+
+impl<'data, T: Component> View<'data> for TryReadSparse<T> {
+    type Element = <Self::Fetch as IntoIndexableIter>::Item;
+    type Fetch = ReadSlice<'data, T>;
+    type Iter = ReadIter<'data, T>;
+    type Read = [ComponentTypeId; 1];
+    type Write = [ComponentTypeId; 0];
+
+    #[inline]
+    fn validate() {}
+
+    #[inline]
+    fn validate_access(access: &ComponentAccess) -> bool {
+        access.allows_read(ComponentTypeId::of::<T>())
+    }
+
+    #[inline]
+    fn reads_types() -> Self::Read {
+        [ComponentTypeId::of::<T>()]
+    }
+
+    #[inline]
+    fn writes_types() -> Self::Write {
+        []
+    }
+
+    #[inline]
+    fn reads<D: Component>() -> bool {
+        TypeId::of::<T>() == TypeId::of::<D>()
+    }
+
+    #[inline]
+    fn writes<D: Component>() -> bool {
+        false
+    }
+
+    #[inline]
+    fn requires_permissions() -> Permissions<ComponentTypeId> {
+        let mut permissions = Permissions::default();
+        permissions.push_read(ComponentTypeId::of::<T>());
+        permissions
+    }
+
+    unsafe fn fetch(
+        components: &'data Components,
+        archetypes: &'data [Archetype],
+        query: QueryResult<'data>,
+    ) -> Self::Iter {
+        ReadIter {
+            set: components.get_sparse::<T>(),
+            archetypes,
+            indexes: query.index().iter(),
+        }
+    }
+}
+
+#[doc(hidden)]
+pub struct ReadIter<'a, T: Component> {
+    set: Option<&'a SparseSet<T>>,
+    archetypes: &'a [Archetype],
+    indexes: std::slice::Iter<'a, ArchetypeIndex>,
+}
+
+impl<'a, T: Component> Iterator for ReadIter<'a, T> {
+    type Item = Option<ReadSlice<'a, T>>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.indexes.next().map(|i| {
+            Some(ReadSlice {
+                set: self.set,
+                entities: self.archetypes[*i].entities(),
+            })
+        })
+    }
+}
+
+#[doc(hidden)]
+pub struct ReadSlice<'a, T: Component> {
+    set: Option<&'a SparseSet<T>>,
+    entities: &'a [Entity],
+}
+
+impl<'a, T: Component> Clone for ReadSlice<'a, T> {
+    fn clone(&self) -> Self {
+        Self {
+            set: self.set,
+            entities: self.entities,
+        }
+    }
+}
+
+unsafe impl<'a, T: Component> TrustedRandomAccess for ReadSlice<'a, T> {
+    type Item = Option<&'a T>;
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    #[inline]
+    unsafe fn get_unchecked(&mut self, i: usize) -> Self::Item {
+        let entity = *self.entities.get_unchecked(i);
+        self.set.and_then(|set| set.get(entity))
+    }
+
+    #[inline]
+    fn split_at(self, index: usize) -> (Self, Self) {
+        let (left, right) = self.entities.split_at(index);
+        (
+            Self {
+                set: self.set,
+                entities: left,
+            },
+            Self {
+                set: self.set,
+                entities: right,
+            },
+        )
+    }
+}
+
+impl<'a, T: Component> IntoIndexableIter for ReadSlice<'a, T> {
+    type Item = Option<&'a T>;
+    type IntoIter = IndexedIter<Self>;
+
+    fn into_indexable_iter(self) -> Self::IntoIter {
+        IndexedIter::new(self)
+    }
+}
+
+impl<'a, T: Component> IntoIterator for ReadSlice<'a, T> {
+    type Item = <Self as IntoIndexableIter>::Item;
+    type IntoIter = <Self as IntoIndexableIter>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_indexable_iter()
+    }
+}
+
+unsafe impl<'a, T: Component> ReadOnlyFetch for ReadSlice<'a, T> {
+    fn get_components(&self) -> Self::Data {
+        self.clone().into_indexable_iter()
+    }
+}
+
+impl<'a, T: Component> Fetch for ReadSlice<'a, T> {
+    type Data = IndexedIter<Self>;
+
+    #[inline]
+    fn into_components(self) -> Self::Data {
+        self.into_indexable_iter()
+    }
+
+    #[inline]
+    fn find<C: 'static>(&self) -> Option<&[C]> {
+        None
+    }
+
+    #[inline]
+    fn find_mut<C: 'static>(&mut self) -> Option<&mut [C]> {
+        None
+    }
+
+    #[inline]
+    fn version<C: Component>(&self) -> Option<u64> {
+        None
+    }
+
+    #[inline]
+    fn entity_versions<C: Component>(&self) -> Option<&[u64]> {
+        None
+    }
+
+    #[inline]
+    fn added_versions<C: Component>(&self) -> Option<&[u64]> {
+        None
+    }
+
+    #[inline]
+    fn accepted(&mut self) {}
+
+    #[inline]
+    fn split_at(self, index: usize) -> (Self, Self) {
+        TrustedRandomAccess::split_at(self, index)
+    }
+}
+
+/// Writes a single component type in [sparse storage](../storage/struct.SparseSet.html),
+/// yielding `None` for entities which do not have the component.
+///
+/// As sparse components are not part of an entity's layout, this view matches every archetype.
+/// Filters such as `component::<T>()` and `maybe_changed::<T>()` do not apply to sparse
+/// components; check the yielded `Option` instead.
+#[derive(Derivative, Debug, Copy, Clone)]
+#[derivative(Default(bound = ""))]
+pub struct TryWriteSparse<T>(PhantomData<*const T>);
+
+unsafe impl<T: Send> Send for TryWriteSparse<T> {}
+unsafe impl<T> Sync for TryWriteSparse<T> {}
+
+impl<T: Component> DefaultFilter for TryWriteSparse<T> {
+    type Filter = EntityFilterTuple<Any, Passthrough>;
+}
+
+impl<T: Component> IntoView for TryWriteSparse<T> {
+    type View = Self;
+}
+
+impl<'data, T: Component> View<'data> for TryWriteSparse<T> {
+    type Element = <Self::Fetch as IntoIndexableIter>::Item;
+    type Fetch = WriteSlice<'data, T>;
+    type Iter = WriteIter<'data, T>;
+    type Read = [ComponentTypeId; 1];
+    type Write = [ComponentTypeId; 1];
+
+    #[inline]
+    fn validate() {}
+
+    #[inline]
+    fn validate_access(access: &ComponentAccess) -> bool {
+        access.allows_write(ComponentTypeId::of::<T>())
+    }
+
+    #[inline]
+    fn reads_types() -> Self::Read {
+        [ComponentTypeId::of::<T>()]
+    }
+
+    #[inline]
+    fn writes_types() -> Self::Write {
+        [ComponentTypeId::of::<T>()]
+    }
+
+    #[inline]
+    fn reads<D: Component>() -> bool {
+        TypeId::of::<T>() == TypeId::of::<D>()
+    }
+
+    #[inline]
+    fn writes<D: Component>() -> bool {
+        TypeId::of::<T>() == TypeId::of::<D>()
+    }
+
+    #[inline]
+    fn requires_permissions() -> Permissions<ComponentTypeId> {
+        let mut permissions = Permissions::default();
+        permissions.push(ComponentTypeId::of::<T>());
+        permissions
+    }
+
+    unsafe fn fetch(
+        components: &'data Components,
+        archetypes: &'data [Archetype],
+        query: QueryResult<'data>,
+    ) -> Self::Iter {
+        WriteIter {
+            set: components.get_sparse::<T>(),
+            archetypes,
+            indexes: query.index().iter(),
+        }
+    }
+}
+
+#[doc(hidden)]
+pub struct WriteIter<'a, T: Component> {
+    set: Option<&'a SparseSet<T>>,
+    archetypes: &'a [Archetype],
+    indexes: std::slice::Iter<'a, ArchetypeIndex>,
+}
+
+impl<'a, T: Component> Iterator for WriteIter<'a, T> {
+    type Item = Option<WriteSlice<'a, T>>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.indexes.next().map(|i| {
+            Some(WriteSlice {
+                set: self.set,
+                entities: self.archetypes[*i].entities(),
+            })
+        })
+    }
+}
+
+#[doc(hidden)]
+pub struct WriteSlice<'a, T: Component> {
+    set: Option<&'a SparseSet<T>>,
+    entities: &'a [Entity],
+}
+
+unsafe impl<'a, T: Component> TrustedRandomAccess for WriteSlice<'a, T> {
+    type Item = Option<&'a mut T>;
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    #[inline]
+    unsafe fn get_unchecked(&mut self, i: usize) -> Self::Item {
+        // each entity occurs once in a query, so its component is only borrowed once
+        let entity = *self.entities.get_unchecked(i);
+        self.set.and_then(|set| set.get_unchecked_mut(entity))
+    }
+
+    #[inline]
+    fn split_at(self, index: usize) -> (Self, Self) {
+        let (left, right) = self.entities.split_at(index);
+        (
+            Self {
+                set: self.set,
+                entities: left,
+            },
+            Self {
+                set: self.set,
+                entities: right,
+            },
+        )
+    }
+}
+
+impl<'a, T: Component> IntoIndexableIter for WriteSlice<'a, T> {
+    type Item = Option<&'a mut T>;
+    type IntoIter = IndexedIter<Self>;
+
+    fn into_indexable_iter(self) -> Self::IntoIter {
+        IndexedIter::new(self)
+    }
+}
+
+impl<'a, T: Component> IntoIterator for WriteSlice<'a, T> {
+    type Item = <Self as IntoIndexableIter>::Item;
+    type IntoIter = <Self as IntoIndexableIter>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_indexable_iter()
+    }
+}
+
+impl<'a, T: Component> Fetch for WriteSlice<'a, T> {
+    type Data = IndexedIter<Self>;
+
+    #[inline]
+    fn into_components(self) -> Self::Data {
+        self.into_indexable_iter()
+    }
+
+    #[inline]
+    fn find<C: 'static>(&self) -> Option<&[C]> {
+        None
+    }
+
+    #[inline]
+    fn find_mut<C: 'static>(&mut self) -> Option<&mut [C]> {
+        None
+    }
+
+    #[inline]
+    fn version<C: Component>(&self) -> Option<u64> {
+        None
+    }
+
+    #[inline]
+    fn entity_versions<C: Component>(&self) -> Option<&[u64]> {
+        None
+    }
+
+    #[inline]
+    fn added_versions<C: Component>(&self) -> Option<&[u64]> {
+        None
+    }
+
+    #[inline]
+    fn accepted(&mut self) {}
+
+    #[inline]
+    fn split_at(self, index: usize) -> (Self, Self) {
+        TrustedRandomAccess::split_at(self, index)
+    }
+}
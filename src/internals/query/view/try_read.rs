@@ -121,6 +121,8 @@ impl<'a, T: Component> Iterator for TryReadIter<'a, T> {
 pub enum Slice<'a, T: Component> {
     Occupied {
         version: &'a u64,
+        entity_versions: &'a [u64],
+        added_versions: &'a [u64],
         components: &'a [T],
     },
     Empty(usize),
@@ -131,6 +133,8 @@ impl<'a, T: Component> From<ComponentSlice<'a, T>> for Slice<'a, T> {
         Slice::Occupied {
             components: slice.components,
             version: slice.version,
+            entity_versions: slice.entity_versions,
+            added_versions: slice.added_versions,
         }
     }
 }
@@ -209,8 +213,65 @@ impl<'a, T: Component> Fetch for Slice<'a, T> {
         }
     }
 
+    #[inline]
+    fn entity_versions<C: Component>(&self) -> Option<&[u64]> {
+        if TypeId::of::<C>() == TypeId::of::<T>() {
+            match self {
+                Self::Occupied {
+                    entity_versions, ..
+                } => Some(entity_versions),
+                Self::Empty(_) => None,
+            }
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn added_versions<C: Component>(&self) -> Option<&[u64]> {
+        if TypeId::of::<C>() == TypeId::of::<T>() {
+            match self {
+                Self::Occupied { added_versions, .. } => Some(added_versions),
+                Self::Empty(_) => None,
+            }
+        } else {
+            None
+        }
+    }
+
     #[inline]
     fn accepted(&mut self) {}
+
+    #[inline]
+    fn split_at(self, index: usize) -> (Self, Self) {
+        match self {
+            Self::Occupied {
+                version,
+                entity_versions,
+                added_versions,
+                components,
+            } => {
+                let (left, right) = components.split_at(index);
+                let (left_versions, right_versions) = entity_versions.split_at(index);
+                let (left_added, right_added) = added_versions.split_at(index);
+                (
+                    Self::Occupied {
+                        version,
+                        entity_versions: left_versions,
+                        added_versions: left_added,
+                        components: left,
+                    },
+                    Self::Occupied {
+                        version,
+                        entity_versions: right_versions,
+                        added_versions: right_added,
+                        components: right,
+                    },
+                )
+            }
+            Self::Empty(count) => (Self::Empty(index), Self::Empty(count - index)),
+        }
+    }
 }
 
 #[doc(hidden)]
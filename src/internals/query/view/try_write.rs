@@ -119,18 +119,41 @@ impl<'a, T: Component> Iterator for TryWriteIter<'a, T> {
 #[doc(hidden)]
 pub enum Slice<'a, T: Component> {
     Occupied {
-        version: &'a mut u64,
+        // the chunk version is only updated through the first slice when split, but all parts
+        // report it
+        version: u64,
+        version_mut: Option<&'a mut u64>,
+        entity_versions: &'a mut [u64],
+        added_versions: &'a [u64],
         components: &'a mut [T],
         next_version: u64,
     },
     Empty(usize),
 }
 
+impl<'a, T: Component> Slice<'a, T> {
+    fn touch_all(&mut self) {
+        if let Self::Occupied {
+            entity_versions,
+            next_version,
+            ..
+        } = self
+        {
+            for entity_version in entity_versions.iter_mut() {
+                *entity_version = *next_version;
+            }
+        }
+    }
+}
+
 impl<'a, T: Component> From<ComponentSliceMut<'a, T>> for Slice<'a, T> {
     fn from(slice: ComponentSliceMut<'a, T>) -> Self {
         Slice::Occupied {
             components: slice.components,
-            version: slice.version,
+            version: *slice.version,
+            version_mut: Some(slice.version),
+            entity_versions: slice.entity_versions,
+            added_versions: slice.added_versions,
             next_version: next_component_version(),
         }
     }
@@ -142,7 +165,16 @@ impl<'a, T: Component> IntoIndexableIter for Slice<'a, T> {
 
     fn into_indexable_iter(self) -> Self::IntoIter {
         let data = match self {
-            Self::Occupied { components, .. } => Data::Occupied(components),
+            Self::Occupied {
+                components,
+                entity_versions,
+                next_version,
+                ..
+            } => Data::Occupied {
+                components,
+                entity_versions,
+                version: next_version,
+            },
             Self::Empty(count) => Data::Empty(count),
         };
         IndexedIter::new(data)
@@ -162,7 +194,8 @@ impl<'a, T: Component> Fetch for Slice<'a, T> {
     type Data = Option<&'a mut [T]>;
 
     #[inline]
-    fn into_components(self) -> Self::Data {
+    fn into_components(mut self) -> Self::Data {
+        self.touch_all();
         match self {
             Self::Occupied { components, .. } => Some(components),
             Self::Empty(_) => None,
@@ -187,6 +220,7 @@ impl<'a, T: Component> Fetch for Slice<'a, T> {
     #[inline]
     fn find_mut<C: 'static>(&mut self) -> Option<&mut [C]> {
         if TypeId::of::<C>() == TypeId::of::<T>() {
+            self.touch_all();
             // safety: C and T are the same type
             match self {
                 Self::Occupied { components, .. } => Some(unsafe {
@@ -206,7 +240,33 @@ impl<'a, T: Component> Fetch for Slice<'a, T> {
     fn version<C: Component>(&self) -> Option<u64> {
         if TypeId::of::<C>() == TypeId::of::<T>() {
             match self {
-                Self::Occupied { version, .. } => Some(**version),
+                Self::Occupied { version, .. } => Some(*version),
+                Self::Empty(_) => None,
+            }
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn entity_versions<C: Component>(&self) -> Option<&[u64]> {
+        if TypeId::of::<C>() == TypeId::of::<T>() {
+            match self {
+                Self::Occupied {
+                    entity_versions, ..
+                } => Some(entity_versions),
+                Self::Empty(_) => None,
+            }
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn added_versions<C: Component>(&self) -> Option<&[u64]> {
+        if TypeId::of::<C>() == TypeId::of::<T>() {
+            match self {
+                Self::Occupied { added_versions, .. } => Some(added_versions),
                 Self::Empty(_) => None,
             }
         } else {
@@ -218,18 +278,63 @@ impl<'a, T: Component> Fetch for Slice<'a, T> {
     fn accepted(&mut self) {
         if let Self::Occupied {
             version,
+            version_mut,
             next_version,
             ..
         } = self
         {
-            **version = *next_version
+            *version = *next_version;
+            if let Some(version) = version_mut {
+                **version = *next_version;
+            }
+        }
+    }
+
+    #[inline]
+    fn split_at(self, index: usize) -> (Self, Self) {
+        match self {
+            Self::Occupied {
+                version,
+                version_mut,
+                entity_versions,
+                added_versions,
+                components,
+                next_version,
+            } => {
+                let (left, right) = components.split_at_mut(index);
+                let (left_versions, right_versions) = entity_versions.split_at_mut(index);
+                let (left_added, right_added) = added_versions.split_at(index);
+                (
+                    Self::Occupied {
+                        version,
+                        version_mut,
+                        entity_versions: left_versions,
+                        added_versions: left_added,
+                        components: left,
+                        next_version,
+                    },
+                    Self::Occupied {
+                        version,
+                        version_mut: None,
+                        entity_versions: right_versions,
+                        added_versions: right_added,
+                        components: right,
+                        next_version,
+                    },
+                )
+            }
+            Self::Empty(count) => (Self::Empty(index), Self::Empty(count - index)),
         }
     }
 }
 
 #[doc(hidden)]
 pub enum Data<'a, T: Component> {
-    Occupied(&'a mut [T]),
+    Occupied {
+        components: &'a mut [T],
+        entity_versions: &'a mut [u64],
+        version: u64,
+    },
     Empty(usize),
 }
 
@@ -239,7 +344,7 @@ unsafe impl<'a, T: Component> TrustedRandomAccess for Data<'a, T> {
     #[inline]
     fn len(&self) -> usize {
         match self {
-            Self::Occupied(slice) => slice.len(),
+            Self::Occupied { components, .. } => components.len(),
             Self::Empty(len) => *len,
         }
     }
@@ -247,7 +352,14 @@ unsafe impl<'a, T: Component> TrustedRandomAccess for Data<'a, T> {
     #[inline]
     unsafe fn get_unchecked(&mut self, i: usize) -> Self::Item {
         match self {
-            Self::Occupied(slice) => Some(slice.get_unchecked(i)),
+            Self::Occupied {
+                components,
+                entity_versions,
+                version,
+            } => {
+                *entity_versions.get_unchecked_mut(i) = *version;
+                Some(&mut *components.as_mut_ptr().add(i))
+            }
             Self::Empty(_) => None,
         }
     }
@@ -255,9 +367,25 @@ unsafe impl<'a, T: Component> TrustedRandomAccess for Data<'a, T> {
     #[inline]
     fn split_at(self, index: usize) -> (Self, Self) {
         match self {
-            Self::Occupied(slice) => {
-                let (left, right) = slice.split_at_mut(index);
-                (Self::Occupied(left), Self::Occupied(right))
+            Self::Occupied {
+                components,
+                entity_versions,
+                version,
+            } => {
+                let (left, right) = components.split_at_mut(index);
+                let (left_versions, right_versions) = entity_versions.split_at_mut(index);
+                (
+                    Self::Occupied {
+                        components: left,
+                        entity_versions: left_versions,
+                        version,
+                    },
+                    Self::Occupied {
+                        components: right,
+                        entity_versions: right_versions,
+                        version,
+                    },
+                )
             }
             Self::Empty(count) => (Self::Empty(index), Self::Empty(count - index)),
         }
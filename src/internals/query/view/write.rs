@@ -2,7 +2,7 @@
 
 use super::{DefaultFilter, Fetch, IntoIndexableIter, IntoView, View};
 use crate::internals::{
-    iter::indexed::IndexedIter,
+    iter::indexed::{IndexedIter, TrustedRandomAccess},
     permissions::Permissions,
     query::{
         filter::{component::ComponentFilter, passthrough::Passthrough, EntityFilterTuple},
@@ -135,16 +135,31 @@ impl<'a, T: Component> Iterator for WriteIter<'a, T> {
 
 #[doc(hidden)]
 pub struct WriteFetch<'a, T: Component> {
-    version: &'a mut u64,
+    // the chunk version is only updated through the first fetch when split, but all parts report it
+    version: u64,
+    version_mut: Option<&'a mut u64>,
+    entity_versions: &'a mut [u64],
+    added_versions: &'a [u64],
     components: &'a mut [T],
     next_version: u64,
 }
 
+impl<'a, T: Component> WriteFetch<'a, T> {
+    fn touch_all(&mut self) {
+        for entity_version in self.entity_versions.iter_mut() {
+            *entity_version = self.next_version;
+        }
+    }
+}
+
 impl<'a, T: Component> From<ComponentSliceMut<'a, T>> for WriteFetch<'a, T> {
     fn from(slice: ComponentSliceMut<'a, T>) -> Self {
         WriteFetch {
             components: slice.components,
-            version: slice.version,
+            version: *slice.version,
+            version_mut: Some(slice.version),
+            entity_versions: slice.entity_versions,
+            added_versions: slice.added_versions,
             next_version: next_component_version(),
         }
     }
@@ -152,10 +167,14 @@ impl<'a, T: Component> From<ComponentSliceMut<'a, T>> for WriteFetch<'a, T> {
 
 impl<'a, T: Component> IntoIndexableIter for WriteFetch<'a, T> {
     type Item = &'a mut T;
-    type IntoIter = IndexedIter<&'a mut [T]>;
+    type IntoIter = IndexedIter<Data<'a, T>>;
 
     fn into_indexable_iter(self) -> Self::IntoIter {
-        IndexedIter::new(self.components)
+        IndexedIter::new(Data {
+            components: self.components,
+            entity_versions: self.entity_versions,
+            version: self.next_version,
+        })
     }
 }
 
@@ -172,7 +191,8 @@ impl<'a, T: Component> Fetch for WriteFetch<'a, T> {
     type Data = &'a mut [T];
 
     #[inline]
-    fn into_components(self) -> Self::Data {
+    fn into_components(mut self) -> Self::Data {
+        self.touch_all();
         self.components
     }
 
@@ -194,6 +214,7 @@ impl<'a, T: Component> Fetch for WriteFetch<'a, T> {
     #[inline]
     fn find_mut<C: 'static>(&mut self) -> Option<&mut [C]> {
         if TypeId::of::<C>() == TypeId::of::<T>() {
+            self.touch_all();
             // safety: C and T are the same type
             Some(unsafe {
                 std::slice::from_raw_parts_mut(
@@ -209,7 +230,25 @@ impl<'a, T: Component> Fetch for WriteFetch<'a, T> {
     #[inline]
     fn version<C: Component>(&self) -> Option<u64> {
         if TypeId::of::<C>() == TypeId::of::<T>() {
-            Some(*self.version)
+            Some(self.version)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn entity_versions<C: Component>(&self) -> Option<&[u64]> {
+        if TypeId::of::<C>() == TypeId::of::<T>() {
+            Some(self.entity_versions)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn added_versions<C: Component>(&self) -> Option<&[u64]> {
+        if TypeId::of::<C>() == TypeId::of::<T>() {
+            Some(self.added_versions)
         } else {
             None
         }
@@ -217,6 +256,75 @@ impl<'a, T: Component> Fetch for WriteFetch<'a, T> {
 
     #[inline]
     fn accepted(&mut self) {
-        *self.version = self.next_version;
+        self.version = self.next_version;
+        if let Some(version) = &mut self.version_mut {
+            **version = self.next_version;
+        }
+    }
+
+    #[inline]
+    fn split_at(self, index: usize) -> (Self, Self) {
+        let (left, right) = self.components.split_at_mut(index);
+        let (left_versions, right_versions) = self.entity_versions.split_at_mut(index);
+        let (left_added, right_added) = self.added_versions.split_at(index);
+        (
+            WriteFetch {
+                version: self.version,
+                version_mut: self.version_mut,
+                entity_versions: left_versions,
+                added_versions: left_added,
+                components: left,
+                next_version: self.next_version,
+            },
+            WriteFetch {
+                version: self.version,
+                version_mut: None,
+                entity_versions: right_versions,
+                added_versions: right_added,
+                components: right,
+                next_version: self.next_version,
+            },
+        )
+    }
+}
+
+/// A mutable component slice which records the version of each component as it is accessed.
+#[doc(hidden)]
+pub struct Data<'a, T: Component> {
+    components: &'a mut [T],
+    entity_versions: &'a mut [u64],
+    version: u64,
+}
+
+unsafe impl<'a, T: Component> TrustedRandomAccess for Data<'a, T> {
+    type Item = &'a mut T;
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    #[inline]
+    unsafe fn get_unchecked(&mut self, i: usize) -> Self::Item {
+        *self.entity_versions.get_unchecked_mut(i) = self.version;
+        &mut *self.components.as_mut_ptr().add(i)
+    }
+
+    #[inline]
+    fn split_at(self, index: usize) -> (Self, Self) {
+        let (left, right) = self.components.split_at_mut(index);
+        let (left_versions, right_versions) = self.entity_versions.split_at_mut(index);
+        (
+            Data {
+                components: left,
+                entity_versions: left_versions,
+                version: self.version,
+            },
+            Data {
+                components: right,
+                entity_versions: right_versions,
+                version: self.version,
+            },
+        )
     }
 }
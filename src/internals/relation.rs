@@ -0,0 +1,610 @@
+//! Contains types related to typed relations between entities.
+
+use super::{
+    entity::Entity,
+    storage::{
+        archetype::{Archetype, EntityLayout},
+        component::ComponentTypeId,
+    },
+    world::{EntityStore, World},
+};
+use derivative::Derivative;
+use parking_lot::RwLock;
+use smallvec::SmallVec;
+use std::{
+    marker::PhantomData,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// A component which lists the targets of an entity's relations of kind `K`, in the order in
+/// which they were related.
+///
+/// `K` is usually an empty marker type, such as `struct Targets;`. Relations are many-to-many,
+/// and are indexed in both directions: each target has a [RelatedBy](struct.RelatedBy.html)
+/// component listing its sources. Both components are maintained together by
+/// [World::relate](../world/struct.World.html#method.relate) and
+/// [World::unrelate](../world/struct.World.html#method.unrelate), and so cannot be constructed
+/// directly.
+///
+/// Register `Relation<K>` with
+/// [Duplicate::register_clone](../world/struct.Duplicate.html#method.register_clone) so that its
+/// targets are remapped by `World::clone_from`.
+#[derive(Derivative)]
+#[derivative(
+    Debug(bound = ""),
+    PartialEq(bound = ""),
+    Eq(bound = ""),
+    Hash(bound = "")
+)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize), serde(bound = ""))]
+pub struct Relation<K: 'static> {
+    targets: Vec<Entity>,
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    #[derivative(Debug = "ignore")]
+    _kind: PhantomData<fn() -> K>,
+}
+
+impl<K: 'static> Relation<K> {
+    fn new(target: Entity) -> Self {
+        register::<K>();
+        Self {
+            targets: vec![target],
+            _kind: PhantomData,
+        }
+    }
+
+    /// Gets the entities which this entity is related to.
+    pub fn targets(&self) -> &[Entity] {
+        &self.targets
+    }
+
+    /// Returns `true` if this entity is related to `target`.
+    pub fn contains(&self, target: Entity) -> bool {
+        self.targets.contains(&target)
+    }
+}
+
+impl<K: 'static> Clone for Relation<K> {
+    fn clone(&self) -> Self {
+        // `Vec<Entity>::clone` copies its elements, bypassing entity remapping
+        #[allow(clippy::map_clone)]
+        Self {
+            targets: self.targets.iter().map(Entity::clone).collect(),
+            _kind: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl<'de, K: 'static> serde::Deserialize<'de> for Relation<K> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "Relation")]
+        struct Targets {
+            targets: Vec<Entity>,
+        }
+
+        let Targets { targets } = Targets::deserialize(deserializer)?;
+        register::<K>();
+        Ok(Self {
+            targets,
+            _kind: PhantomData,
+        })
+    }
+}
+
+/// A component which lists the entities which are related to an entity by relations of kind
+/// `K`, in the order in which they were related.
+///
+/// This is the reverse index of [Relation](struct.Relation.html).
+#[derive(Derivative)]
+#[derivative(
+    Debug(bound = ""),
+    PartialEq(bound = ""),
+    Eq(bound = ""),
+    Hash(bound = "")
+)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize), serde(bound = ""))]
+pub struct RelatedBy<K: 'static> {
+    sources: Vec<Entity>,
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    #[derivative(Debug = "ignore")]
+    _kind: PhantomData<fn() -> K>,
+}
+
+impl<K: 'static> RelatedBy<K> {
+    fn new(source: Entity) -> Self {
+        register::<K>();
+        Self {
+            sources: vec![source],
+            _kind: PhantomData,
+        }
+    }
+
+    /// Gets the entities which are related to this entity.
+    pub fn sources(&self) -> &[Entity] {
+        &self.sources
+    }
+
+    /// Returns `true` if `source` is related to this entity.
+    pub fn contains(&self, source: Entity) -> bool {
+        self.sources.contains(&source)
+    }
+}
+
+impl<K: 'static> Clone for RelatedBy<K> {
+    fn clone(&self) -> Self {
+        // `Vec<Entity>::clone` copies its elements, bypassing entity remapping
+        #[allow(clippy::map_clone)]
+        Self {
+            sources: self.sources.iter().map(Entity::clone).collect(),
+            _kind: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl<'de, K: 'static> serde::Deserialize<'de> for RelatedBy<K> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "RelatedBy")]
+        struct Sources {
+            sources: Vec<Entity>,
+        }
+
+        let Sources { sources } = Sources::deserialize(deserializer)?;
+        register::<K>();
+        Ok(Self {
+            sources,
+            _kind: PhantomData,
+        })
+    }
+}
+
+/// Returns the entities which `source` is related to by relations of kind `K`.
+///
+/// The store must be able to read `Relation<K>` components.
+pub fn targets<K: 'static, S: EntityStore>(store: &S, source: Entity) -> Vec<Entity> {
+    store
+        .entry_ref(source)
+        .ok()
+        .and_then(|entry| {
+            entry
+                .get_component::<Relation<K>>()
+                .ok()
+                .map(|relation| relation.targets.to_vec())
+        })
+        .unwrap_or_default()
+}
+
+/// Returns the entities which are related to `target` by relations of kind `K`.
+///
+/// The store must be able to read `RelatedBy<K>` components.
+///
+/// # Examples
+///
+/// ```
+/// # use legion::*;
+/// # use legion::relation::sources;
+/// struct Targets;
+///
+/// let mut world = World::default();
+/// let player = world.push((0usize,));
+/// let enemy = world.push((1usize,));
+/// world.relate::<Targets>(enemy, player);
+///
+/// assert_eq!(sources::<Targets, _>(&world, player), vec![enemy]);
+/// ```
+pub fn sources<K: 'static, S: EntityStore>(store: &S, target: Entity) -> Vec<Entity> {
+    store
+        .entry_ref(target)
+        .ok()
+        .and_then(|entry| {
+            entry
+                .get_component::<RelatedBy<K>>()
+                .ok()
+                .map(|related| related.sources.to_vec())
+        })
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RelationKind {
+    forward: ComponentTypeId,
+    reverse: ComponentTypeId,
+    remove: fn(&mut World, Entity),
+    relink: fn(&mut World, Entity),
+}
+
+impl RelationKind {
+    fn participates(&self, layout: &EntityLayout) -> bool {
+        layout.component_types().contains(&self.forward)
+            || layout.component_types().contains(&self.reverse)
+    }
+}
+
+// Each kind of relation whose components have been constructed, including by deserialization,
+// so that relations can be cleaned up in any world which contains them.
+static RELATION_KINDS: RwLock<Vec<RelationKind>> = parking_lot::const_rwlock(Vec::new());
+
+// The number of registered kinds, which can be read without taking the registry lock.
+static RELATION_KIND_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+fn register<K: 'static>() {
+    let forward = ComponentTypeId::of::<Relation<K>>();
+    if RELATION_KINDS
+        .read()
+        .iter()
+        .any(|kind| kind.forward == forward)
+    {
+        return;
+    }
+
+    let mut kinds = RELATION_KINDS.write();
+    if !kinds.iter().any(|kind| kind.forward == forward) {
+        kinds.push(RelationKind {
+            forward,
+            reverse: ComponentTypeId::of::<RelatedBy<K>>(),
+            remove: remove_relations::<K>,
+            relink: relink_relations::<K>,
+        });
+        RELATION_KIND_COUNT.store(kinds.len(), Ordering::Release);
+    }
+}
+
+type RelationKinds = SmallVec<[RelationKind; 2]>;
+
+/// Returns the kinds of relation which an entity with the given layout may participate in.
+fn participating(layout: &EntityLayout) -> RelationKinds {
+    RELATION_KINDS
+        .read()
+        .iter()
+        .filter(|kind| kind.participates(layout))
+        .copied()
+        .collect()
+}
+
+/// Caches the kinds of relation which the entities in each of a world's archetypes may
+/// participate in, so that removing entities does not need to consult the registry.
+#[derive(Debug, Default)]
+pub(crate) struct RelationCache {
+    registered: usize,
+    archetypes: Vec<Option<RelationKinds>>,
+}
+
+impl RelationCache {
+    fn participating(&mut self, archetype: &Archetype) -> RelationKinds {
+        let registered = RELATION_KIND_COUNT.load(Ordering::Acquire);
+        if registered == 0 {
+            return RelationKinds::new();
+        }
+
+        // registering a kind may add relations to existing archetypes
+        if registered != self.registered {
+            self.archetypes.clear();
+            self.registered = registered;
+        }
+
+        let index = archetype.index().0 as usize;
+        if self.archetypes.len() <= index {
+            self.archetypes.resize(index + 1, None);
+        }
+        self.archetypes[index]
+            .get_or_insert_with(|| participating(archetype.layout()))
+            .clone()
+    }
+}
+
+/// Returns `true` if an entity with the given layout may participate in a relation.
+pub(crate) fn has_relations(layout: &EntityLayout) -> bool {
+    RELATION_KINDS
+        .read()
+        .iter()
+        .any(|kind| kind.participates(layout))
+}
+
+/// Returns `true` if the given component type is a relation component of any kind.
+pub(crate) fn is_relation(type_id: ComponentTypeId) -> bool {
+    RELATION_KINDS
+        .read()
+        .iter()
+        .any(|kind| kind.forward == type_id || kind.reverse == type_id)
+}
+
+// Removes an entity from the relations of kind `K` of all entities it is related to in either
+// direction.
+fn remove_relations<K: 'static>(world: &mut World, entity: Entity) {
+    let targets = targets::<K, _>(world, entity);
+    let sources = sources::<K, _>(world, entity);
+    for target in targets {
+        remove_source::<K>(world, entity, target);
+    }
+    for source in sources {
+        remove_target::<K>(world, source, entity);
+    }
+}
+
+// Relates a clone of an entity to the cloned targets of its relations of kind `K`. Entities
+// related to the original are not related to the clone.
+fn relink_relations<K: 'static>(world: &mut World, clone: Entity) {
+    let targets = match world.entry(clone) {
+        Some(mut entry) => {
+            let targets = entry
+                .get_component::<Relation<K>>()
+                .map(|relation| relation.targets.clone())
+                .unwrap_or_default();
+            entry.remove_component::<Relation<K>>();
+            entry.remove_component::<RelatedBy<K>>();
+            targets
+        }
+        None => return,
+    };
+    for target in targets {
+        world.relate::<K>(clone, target);
+    }
+}
+
+fn remove_target<K: 'static>(world: &mut World, source: Entity, target: Entity) -> bool {
+    if let Some(mut entry) = world.entry(source) {
+        if let Ok(relation) = entry.get_component_mut::<Relation<K>>() {
+            let len = relation.targets.len();
+            relation.targets.retain(|entity| *entity != target);
+            let removed = relation.targets.len() < len;
+            if relation.targets.is_empty() {
+                entry.remove_component::<Relation<K>>();
+            }
+            return removed;
+        }
+    }
+    false
+}
+
+fn remove_source<K: 'static>(world: &mut World, source: Entity, target: Entity) {
+    if let Some(mut entry) = world.entry(target) {
+        if let Ok(related) = entry.get_component_mut::<RelatedBy<K>>() {
+            related.sources.retain(|entity| *entity != source);
+            if related.sources.is_empty() {
+                entry.remove_component::<RelatedBy<K>>();
+            }
+        }
+    }
+}
+
+impl World {
+    /// Relates `source` to `target` by a relation of kind `K`.
+    ///
+    /// The source's [Relation](../relation/struct.Relation.html) component and the target's
+    /// [RelatedBy](../relation/struct.RelatedBy.html) component are updated together. The
+    /// relation is removed from both entities when either is removed from the world.
+    ///
+    /// Returns `true` if the relation was added, or `false` if either entity does not exist or
+    /// the entities were already related.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # use legion::relation::Relation;
+    /// struct Targets;
+    ///
+    /// let mut world = World::default();
+    /// let turret = world.push((0usize,));
+    /// let enemy = world.push((1usize,));
+    /// assert!(world.relate::<Targets>(turret, enemy));
+    ///
+    /// let entry = world.entry(turret).unwrap();
+    /// let relation = entry.get_component::<Relation<Targets>>().unwrap();
+    /// assert_eq!(relation.targets(), &[enemy]);
+    /// ```
+    pub fn relate<K: 'static>(&mut self, source: Entity, target: Entity) -> bool {
+        if !self.contains(source) || !self.contains(target) {
+            return false;
+        }
+
+        let mut entry = self.entry(source).unwrap();
+        match entry.get_component_mut::<Relation<K>>() {
+            Ok(relation) if relation.targets.contains(&target) => return false,
+            Ok(relation) => relation.targets.push(target),
+            Err(_) => entry.add_component(Relation::<K>::new(target)),
+        }
+
+        let mut entry = self.entry(target).unwrap();
+        match entry.get_component_mut::<RelatedBy<K>>() {
+            Ok(related) => related.sources.push(source),
+            Err(_) => entry.add_component(RelatedBy::<K>::new(source)),
+        }
+
+        true
+    }
+
+    /// Removes the relation of kind `K` from `source` to `target`. Returns `true` if the
+    /// entities were related.
+    pub fn unrelate<K: 'static>(&mut self, source: Entity, target: Entity) -> bool {
+        if !remove_target::<K>(self, source, target) {
+            return false;
+        }
+        remove_source::<K>(self, source, target);
+        true
+    }
+
+    /// Removes an entity from the relations of all entities it is related to, in preparation for
+    /// the entity's removal.
+    pub(crate) fn remove_relations(&mut self, entity: Entity) {
+        let kinds = match self.relation_cache(entity) {
+            Some((cache, archetype)) => cache.participating(archetype),
+            None => return,
+        };
+        for kind in kinds {
+            (kind.remove)(self, entity);
+        }
+    }
+
+    /// Links a clone of an entity into the relations it was cloned with. The clone is related
+    /// to the cloned targets, but not to the cloned sources.
+    pub(crate) fn relink_relations(&mut self, clone: Entity) {
+        let kinds = match self.relation_cache(clone) {
+            Some((cache, archetype)) => cache.participating(archetype),
+            None => return,
+        };
+        for kind in kinds {
+            (kind.relink)(self, clone);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::internals::{
+        query::{filter::filter_fns::any, view::read::Read, IntoQuery},
+        systems::{command::CommandBuffer, resources::Resources},
+        world::Duplicate,
+    };
+
+    struct Likes;
+    struct Owns;
+
+    #[test]
+    fn relate() {
+        let mut world = World::default();
+        let a = world.push((1usize,));
+        let b = world.push((2usize,));
+        let c = world.push((3usize,));
+
+        assert!(world.relate::<Likes>(a, b));
+        assert!(world.relate::<Likes>(a, c));
+        assert!(world.relate::<Likes>(c, b));
+        assert!(world.relate::<Owns>(a, b));
+        assert!(!world.relate::<Likes>(a, b));
+
+        assert_eq!(targets::<Likes, _>(&world, a), vec![b, c]);
+        assert_eq!(sources::<Likes, _>(&world, b), vec![a, c]);
+        assert_eq!(sources::<Owns, _>(&world, b), vec![a]);
+
+        // query for the entities which like `b`
+        let mut query = <(Entity, Read<Relation<Likes>>)>::query();
+        let likes_b = query
+            .iter(&world)
+            .filter(|(_, relation)| relation.contains(b))
+            .map(|(entity, _)| *entity)
+            .collect::<Vec<_>>();
+        assert_eq!(likes_b.len(), 2);
+        assert!(likes_b.contains(&a) && likes_b.contains(&c));
+
+        assert!(world.unrelate::<Likes>(a, b));
+        assert!(!world.unrelate::<Likes>(a, b));
+        assert_eq!(targets::<Likes, _>(&world, a), vec![c]);
+        assert_eq!(sources::<Likes, _>(&world, b), vec![c]);
+        assert_eq!(targets::<Owns, _>(&world, a), vec![b]);
+
+        assert!(world.unrelate::<Likes>(c, b));
+        assert!(world
+            .entry(b)
+            .unwrap()
+            .get_component::<RelatedBy<Likes>>()
+            .is_err());
+    }
+
+    #[test]
+    fn remove() {
+        let mut world = World::default();
+        let a = world.push((1usize,));
+        let b = world.push((2usize,));
+        let c = world.push((3usize,));
+        world.relate::<Likes>(a, b);
+        world.relate::<Likes>(b, c);
+        world.relate::<Owns>(c, b);
+
+        // removing an entity removes it from both ends of its relations
+        assert!(world.remove(b));
+        assert!(targets::<Likes, _>(&world, a).is_empty());
+        assert!(sources::<Likes, _>(&world, c).is_empty());
+        assert!(targets::<Owns, _>(&world, c).is_empty());
+        assert!(world
+            .entry(a)
+            .unwrap()
+            .get_component::<Relation<Likes>>()
+            .is_err());
+
+        let mut resources = Resources::default();
+        let mut cmd = CommandBuffer::new(&world);
+        cmd.relate::<Likes>(a, c);
+        cmd.flush(&mut world, &mut resources);
+        assert_eq!(targets::<Likes, _>(&world, a), vec![c]);
+
+        cmd.remove(c);
+        cmd.flush(&mut world, &mut resources);
+        assert!(targets::<Likes, _>(&world, a).is_empty());
+    }
+
+    #[test]
+    fn clone_from() {
+        let mut world = World::default();
+        let a = world.push((1usize,));
+        let b = world.push((2usize,));
+        world.relate::<Likes>(a, b);
+
+        let mut merger = Duplicate::default();
+        merger.register_copy::<usize>();
+        merger.register_clone::<Relation<Likes>>();
+        merger.register_clone::<RelatedBy<Likes>>();
+
+        let mut cloned = World::default();
+        let mappings = cloned.clone_from(&world, &any(), &mut merger);
+        let (a, b) = (mappings[&a], mappings[&b]);
+        assert_eq!(targets::<Likes, _>(&cloned, a), vec![b]);
+        assert_eq!(sources::<Likes, _>(&cloned, b), vec![a]);
+
+        // the cloned world cleans up relations of the kinds used in the source world
+        cloned.remove(b);
+        assert!(targets::<Likes, _>(&cloned, a).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn serialize() {
+        use crate::internals::serialize::Registry;
+        use serde::de::DeserializeSeed;
+
+        // only ever constructed by deserialization, so that the relation kind is first seen
+        // when the world is deserialized
+        struct Deserialized;
+
+        let mut world = World::default();
+        let a = world.push((1usize,));
+        let b = world.push((2usize,));
+        world.relate::<Likes>(a, b);
+
+        let mut registry = Registry::<String>::default();
+        registry.register::<usize>("usize".to_string());
+        registry.register::<Relation<Likes>>("relation".to_string());
+        registry.register::<RelatedBy<Likes>>("related_by".to_string());
+        let json = serde_json::to_value(&world.as_serializable(any(), &registry)).unwrap();
+
+        let mut registry = Registry::<String>::default();
+        registry.register::<usize>("usize".to_string());
+        registry.register::<Relation<Deserialized>>("relation".to_string());
+        registry.register::<RelatedBy<Deserialized>>("related_by".to_string());
+        let mut world: World = registry.as_deserialize().deserialize(json).unwrap();
+
+        let mut query = <(Entity, Read<usize>)>::query();
+        let mut find = |world: &World, value: usize| {
+            query
+                .iter(world)
+                .find(|(_, v)| **v == value)
+                .map(|(entity, _)| *entity)
+                .unwrap()
+        };
+        let a = find(&world, 1);
+        let b = find(&world, 2);
+        assert_eq!(targets::<Deserialized, _>(&world, a), vec![b]);
+
+        world.remove(b);
+        assert!(targets::<Deserialized, _>(&world, a).is_empty());
+        assert!(world
+            .entry(a)
+            .unwrap()
+            .get_component::<Relation<Deserialized>>()
+            .is_err());
+    }
+}
@@ -14,7 +14,7 @@ use super::{
 };
 use crate::internals::{
     entity::Entity,
-    event::{Event, Subscriber, Subscribers},
+    event::{Event, RemovalKind, Subscriber, Subscribers},
     query::filter::{FilterResult, LayoutFilter},
 };
 use std::{
@@ -107,19 +107,32 @@ impl Archetype {
         self.entities.reserve(additional)
     }
 
-    pub(crate) fn swap_remove(&mut self, entity_index: usize) -> Entity {
+    pub(crate) fn subscribers(&self) -> &Subscribers {
+        &self.subscribers
+    }
+
+    pub(crate) fn swap_remove(&mut self, entity_index: usize, kind: RemovalKind) -> Entity {
         let removed = self.entities.swap_remove(entity_index);
         self.subscribers
             .send(Event::EntityRemoved(removed, self.index));
+        if kind == RemovalKind::Despawned {
+            self.subscribers
+                .send(Event::EntityDespawned(removed, self.index));
+        }
+        if self.entities.is_empty() {
+            self.subscribers.send(Event::ArchetypeEmptied(self.index));
+        }
         removed
     }
 
     pub(crate) fn subscribe(&mut self, subscriber: Subscriber) {
-        subscriber.send(Event::ArchetypeCreated(self.index));
+        let mut alive = subscriber.send(Event::ArchetypeCreated(self.index));
         for entity in &self.entities {
-            subscriber.send(Event::EntityInserted(*entity, self.index));
+            alive = alive && subscriber.send(Event::EntityInserted(*entity, self.index));
+        }
+        if alive {
+            self.subscribers.push(subscriber);
         }
-        self.subscribers.push(subscriber);
     }
 
     pub(crate) fn drain(&mut self) -> Vec<Entity> {
@@ -128,6 +141,11 @@ impl Archetype {
         for entity in &entities {
             self.subscribers
                 .send(Event::EntityRemoved(*entity, self.index));
+            self.subscribers
+                .send(Event::EntityDespawned(*entity, self.index));
+        }
+        if !entities.is_empty() {
+            self.subscribers.send(Event::ArchetypeEmptied(self.index));
         }
         entities
     }
@@ -138,6 +156,8 @@ impl Drop for Archetype {
         for entity in &self.entities {
             self.subscribers
                 .send(Event::EntityRemoved(*entity, self.index));
+            self.subscribers
+                .send(Event::EntityDespawned(*entity, self.index));
         }
     }
 }
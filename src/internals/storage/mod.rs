@@ -2,6 +2,7 @@ use crate::internals::hash::ComponentTypeIdHasher;
 use archetype::ArchetypeIndex;
 use component::{Component, ComponentTypeId};
 use downcast_rs::{impl_downcast, Downcast};
+use sparse::{SparseSet, UnknownSparseSet};
 use std::{
     collections::{HashMap, HashSet},
     hash::BuildHasherDefault,
@@ -15,6 +16,7 @@ pub mod group;
 pub mod index;
 pub mod packed;
 pub mod slicevec;
+pub mod sparse;
 
 /// Contains information about the type of a component.
 #[derive(Copy, Clone, PartialEq)]
@@ -70,9 +72,13 @@ pub type Epoch = u64;
 
 /// The version of a component slice. Versions are incremented when the sliace is
 /// accessed mutably.
+///
+/// Each component within a slice also tracks the version at which it was last accessed mutably,
+/// and the version at which it was added to its entity.
 pub type Version = u64;
 
-static COMPONENT_VERSION: AtomicU64 = AtomicU64::new(0);
+// version 0 is reserved for components which have never been written
+static COMPONENT_VERSION: AtomicU64 = AtomicU64::new(1);
 pub(crate) fn next_component_version() -> u64 {
     COMPONENT_VERSION.fetch_add(1, Ordering::SeqCst)
 }
@@ -111,9 +117,22 @@ pub trait UnknownComponentStorage: Downcast + Send + Sync {
         dst: ArchetypeIndex,
     );
 
+    /// Moves a batch of components from one archetype to another. The indices must be sorted in
+    /// descending order. Each component is swap removed from the source archetype, and the
+    /// components are appended to the destination archetype in the order of their indices.
+    fn move_components(
+        &mut self,
+        source: ArchetypeIndex,
+        indices: &[ComponentIndex],
+        dst: ArchetypeIndex,
+    );
+
     /// Removes a component from an archetype slice, swapping it with the last component in the slice.
     fn swap_remove(&mut self, archetype: ArchetypeIndex, index: ComponentIndex);
 
+    /// Drops all components in an archetype slice.
+    fn clear_archetype(&mut self, archetype: ArchetypeIndex);
+
     /// Packs archetype slices.
     fn pack(&mut self, epoch_threshold: Epoch) -> usize;
 
@@ -152,13 +171,22 @@ impl_downcast!(UnknownComponentStorage);
 pub struct ComponentSlice<'a, T: Component> {
     pub(crate) components: &'a [T],
     pub(crate) version: &'a Version,
+    pub(crate) entity_versions: &'a [Version],
+    pub(crate) added_versions: &'a [Version],
 }
 
 impl<'a, T: Component> ComponentSlice<'a, T> {
-    pub(crate) fn new(components: &'a [T], version: &'a Version) -> Self {
+    pub(crate) fn new(
+        components: &'a [T],
+        version: &'a Version,
+        entity_versions: &'a [Version],
+        added_versions: &'a [Version],
+    ) -> Self {
         Self {
             components,
             version,
+            entity_versions,
+            added_versions,
         }
     }
 
@@ -194,22 +222,45 @@ pub struct ComponentSliceMut<'a, T: Component> {
     // todo would be better if these were private and we controlled version increments more centrally
     pub(crate) components: &'a mut [T],
     pub(crate) version: &'a mut Version,
+    pub(crate) entity_versions: &'a mut [Version],
+    pub(crate) added_versions: &'a [Version],
 }
 
 impl<'a, T: Component> ComponentSliceMut<'a, T> {
-    pub(crate) fn new(components: &'a mut [T], version: &'a mut Version) -> Self {
+    pub(crate) fn new(
+        components: &'a mut [T],
+        version: &'a mut Version,
+        entity_versions: &'a mut [Version],
+        added_versions: &'a [Version],
+    ) -> Self {
         Self {
             components,
             version,
+            entity_versions,
+            added_versions,
         }
     }
 
     /// Converts this slice into its inner value.
-    /// This increments the slice's version.
+    /// This increments the slice's version, and the versions of all components in the slice.
     pub fn into_slice(self) -> &'a mut [T] {
-        *self.version = next_component_version();
+        let version = next_component_version();
+        *self.version = version;
+        for entity_version in self.entity_versions.iter_mut() {
+            *entity_version = version;
+        }
         self.components
     }
+
+    /// Converts this slice into a reference to a single component.
+    /// This increments the slice's version, and the version of the returned component.
+    pub fn into_component(self, ComponentIndex(index): ComponentIndex) -> Option<&'a mut T> {
+        let component = self.components.get_mut(index)?;
+        let version = next_component_version();
+        *self.version = version;
+        self.entity_versions[index] = version;
+        Some(component)
+    }
 }
 
 impl<'a, T: Component> Deref for ComponentSliceMut<'a, T> {
@@ -286,6 +337,11 @@ pub struct Components {
         Box<dyn UnknownComponentStorage>,
         BuildHasherDefault<ComponentTypeIdHasher>,
     >,
+    sparse: HashMap<
+        ComponentTypeId,
+        Box<dyn UnknownSparseSet>,
+        BuildHasherDefault<ComponentTypeIdHasher>,
+    >,
 }
 
 impl Components {
@@ -328,6 +384,38 @@ impl Components {
             .and_then(|storage| storage.downcast_mut())
     }
 
+    /// Returns the sparse set storing components of the given type.
+    pub fn get_sparse<T: Component>(&self) -> Option<&SparseSet<T>> {
+        self.sparse
+            .get(&ComponentTypeId::of::<T>())
+            .and_then(|set| set.downcast_ref())
+    }
+
+    /// Returns the sparse set storing components of the given type.
+    pub fn get_sparse_mut<T: Component>(&mut self) -> Option<&mut SparseSet<T>> {
+        self.sparse
+            .get_mut(&ComponentTypeId::of::<T>())
+            .and_then(|set| set.downcast_mut())
+    }
+
+    /// Gets or inserts the sparse set storing components of the given type.
+    pub(crate) fn get_sparse_or_insert<T: Component>(&mut self) -> &mut SparseSet<T> {
+        self.sparse
+            .entry(ComponentTypeId::of::<T>())
+            .or_insert_with(|| Box::new(SparseSet::<T>::default()))
+            .downcast_mut()
+            .unwrap()
+    }
+
+    /// Returns an iterator over each sparse set, along with the type of component it stores.
+    pub(crate) fn iter_sparse_mut(
+        &mut self,
+    ) -> impl Iterator<Item = (ComponentTypeId, &mut dyn UnknownSparseSet)> {
+        self.sparse
+            .iter_mut()
+            .map(|(type_id, set)| (*type_id, set.deref_mut()))
+    }
+
     /// Returns a writer for writing to multiple component storages.
     pub fn get_multi_mut(&mut self) -> MultiMut {
         MultiMut::new(self)
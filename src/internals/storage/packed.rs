@@ -274,6 +274,47 @@ impl<T> Drop for ComponentVec<T> {
     }
 }
 
+/// The versions of each component in an archetype slice.
+#[derive(Debug, Default)]
+struct EntityVersions {
+    // The version at which each component was last accessed mutably
+    changed: Vec<u64>,
+    // The version at which each component was added to its entity
+    added: Vec<u64>,
+}
+
+impl EntityVersions {
+    fn len(&self) -> usize {
+        self.changed.len()
+    }
+
+    fn push(&mut self, (changed, added): (u64, u64)) {
+        self.changed.push(changed);
+        self.added.push(added);
+    }
+
+    fn extend(&mut self, count: usize, version: u64) {
+        self.changed.resize(self.changed.len() + count, version);
+        self.added.resize(self.added.len() + count, version);
+    }
+
+    fn get(&self, index: usize) -> (u64, u64) {
+        (self.changed[index], self.added[index])
+    }
+
+    fn set(&mut self, index: usize, (changed, added): (u64, u64)) {
+        self.changed[index] = changed;
+        self.added[index] = added;
+    }
+
+    fn swap_remove(&mut self, index: usize) -> (u64, u64) {
+        (
+            self.changed.swap_remove(index),
+            self.added.swap_remove(index),
+        )
+    }
+}
+
 /// Stores a slice of components of type `T` for each archetype.
 /// Archetype slices are sorted according to the group that component `T` belongs to.
 /// Each slice _may_ be packed into a single allocation to optimise for group-based access.
@@ -289,11 +330,13 @@ pub struct PackedStorage<T: Component> {
     epoch: Epoch,
     // Ordered archetype versions
     versions: Vec<UnsafeCell<u64>>,
+    // Ordered per-entity versions
+    entity_versions: Vec<UnsafeCell<EntityVersions>>,
     // Ordered allocation metadata
     allocations: Vec<ComponentVec<T>>,
 }
 
-// these are needed because of the UnsafeCell in versions
+// these are needed because of the UnsafeCell in versions and entity_versions
 // but we write protect that ourselves
 unsafe impl<T: Component> Send for PackedStorage<T> {}
 unsafe impl<T: Component> Sync for PackedStorage<T> {}
@@ -307,6 +350,9 @@ impl<T: Component> PackedStorage<T> {
         let slice_index = self.index[archetype as usize];
         let allocation = &mut self.allocations[slice_index];
         let component = allocation.swap_remove(self.epoch, index as usize);
+        self.entity_versions[slice_index]
+            .get_mut()
+            .swap_remove(index);
         self.update_slice(slice_index);
         self.entity_len -= 1;
         component
@@ -320,6 +366,17 @@ impl<T: Component> PackedStorage<T> {
     fn index(&self, ArchetypeIndex(archetype): ArchetypeIndex) -> usize {
         self.index[archetype as usize]
     }
+
+    fn set_last_entity_versions(
+        &mut self,
+        ArchetypeIndex(archetype): ArchetypeIndex,
+        versions: (u64, u64),
+    ) {
+        let slice_index = self.index[archetype as usize];
+        let entity_versions = self.entity_versions[slice_index].get_mut();
+        let last = entity_versions.len() - 1;
+        entity_versions.set(last, versions);
+    }
 }
 
 impl<T: Component> UnknownComponentStorage for PackedStorage<T> {
@@ -336,13 +393,20 @@ impl<T: Component> UnknownComponentStorage for PackedStorage<T> {
         // remove component from source slice
         let src_allocation = &mut self.allocations[src_slice_index];
         let value = src_allocation.swap_remove(self.epoch, index.0 as usize);
+        let entity_versions = self.entity_versions[src_slice_index]
+            .get_mut()
+            .swap_remove(index.0);
 
         // insert component into destination slice
+        // the component itself has not changed, so it retains its own version
         let dst_allocation = &mut self.allocations[dst_slice_index];
         unsafe {
             dst_allocation.extend_memcopy(self.epoch, &value as *const _, 1);
             *self.versions[dst_slice_index].get() = next_component_version();
         }
+        self.entity_versions[dst_slice_index]
+            .get_mut()
+            .push(entity_versions);
 
         // update slice pointers
         self.update_slice(src_slice_index);
@@ -362,6 +426,8 @@ impl<T: Component> UnknownComponentStorage for PackedStorage<T> {
         // insert archetype into collections
         self.slices.insert(index, allocation.as_raw_slice());
         self.versions.insert(index, UnsafeCell::new(0));
+        self.entity_versions
+            .insert(index, UnsafeCell::new(EntityVersions::default()));
         self.allocations.insert(index, allocation);
 
         // update index
@@ -399,6 +465,12 @@ impl<T: Component> UnknownComponentStorage for PackedStorage<T> {
 
             // bump destination version
             unsafe { *dst.versions[dst_index].get() = next_component_version() };
+
+            // move entity versions
+            std::mem::swap(
+                self.entity_versions[src_index].get_mut(),
+                dst.entity_versions[dst_index].get_mut(),
+            );
         } else {
             // memcopy components into the destination
             let (ptr, len) = self.get_raw(src_archetype).unwrap();
@@ -408,6 +480,13 @@ impl<T: Component> UnknownComponentStorage for PackedStorage<T> {
             let mut swapped = ComponentVec::<T>::new();
             std::mem::swap(&mut self.allocations[src_index], &mut swapped);
             std::mem::forget(swapped);
+
+            // move entity versions, replacing those written by the memcopy
+            let src_versions = std::mem::take(self.entity_versions[src_index].get_mut());
+            let dst_versions = dst.entity_versions[dst_index].get_mut();
+            let start = dst_versions.len() - src_versions.len();
+            dst_versions.changed[start..].copy_from_slice(&src_versions.changed);
+            dst_versions.added[start..].copy_from_slice(&src_versions.added);
         }
 
         // update slice pointers
@@ -416,6 +495,50 @@ impl<T: Component> UnknownComponentStorage for PackedStorage<T> {
     }
 
     /// Moves a component to a new storage.
+    fn move_components(
+        &mut self,
+        source: ArchetypeIndex,
+        indices: &[ComponentIndex],
+        dst: ArchetypeIndex,
+    ) {
+        // find archetype locations
+        let src_slice_index = self.index(source);
+        let dst_slice_index = self.index(dst);
+
+        // remove components from source slice
+        let mut values = Vec::with_capacity(indices.len());
+        let mut versions = Vec::with_capacity(indices.len());
+        for index in indices {
+            values.push(self.allocations[src_slice_index].swap_remove(self.epoch, index.0));
+            versions.push(
+                self.entity_versions[src_slice_index]
+                    .get_mut()
+                    .swap_remove(index.0),
+            );
+        }
+
+        // insert components into destination slice
+        // the components themselves have not changed, so they retain their own versions
+        unsafe {
+            self.allocations[dst_slice_index].extend_memcopy(
+                self.epoch,
+                values.as_ptr(),
+                values.len(),
+            );
+            // the components have been copied into the destination, so must not be dropped
+            values.set_len(0);
+            *self.versions[dst_slice_index].get() = next_component_version();
+        }
+        let entity_versions = self.entity_versions[dst_slice_index].get_mut();
+        for version in versions {
+            entity_versions.push(version);
+        }
+
+        // update slice pointers
+        self.update_slice(src_slice_index);
+        self.update_slice(dst_slice_index);
+    }
+
     fn transfer_component(
         &mut self,
         src_archetype: ArchetypeIndex,
@@ -423,15 +546,32 @@ impl<T: Component> UnknownComponentStorage for PackedStorage<T> {
         dst_archetype: ArchetypeIndex,
         dst: &mut dyn UnknownComponentStorage,
     ) {
+        let slice_index = self.index(src_archetype);
+        let entity_versions = self.entity_versions[slice_index]
+            .get_mut()
+            .get(src_component.0);
         let component = self.swap_remove_internal(src_archetype, src_component);
         unsafe { dst.extend_memcopy_raw(dst_archetype, &component as *const T as *const u8, 1) };
         std::mem::forget(component);
+
+        // the component itself has not changed, so it retains its own version
+        let dst = dst.downcast_mut::<Self>().unwrap();
+        dst.set_last_entity_versions(dst_archetype, entity_versions);
     }
 
     fn swap_remove(&mut self, archetype: ArchetypeIndex, index: ComponentIndex) {
         self.swap_remove_internal(archetype, index);
     }
 
+    fn clear_archetype(&mut self, archetype: ArchetypeIndex) {
+        let slice_index = self.index(archetype);
+        self.entity_len -= self.allocations[slice_index].len();
+        // dropping the allocation drops its components
+        self.allocations[slice_index] = ComponentVec::new();
+        *self.entity_versions[slice_index].get_mut() = EntityVersions::default();
+        self.update_slice(slice_index);
+    }
+
     fn pack(&mut self, age_threshold: Epoch) -> usize {
         if size_of::<T>() == 0 {
             return 0;
@@ -489,7 +629,14 @@ impl<T: Component> UnknownComponentStorage for PackedStorage<T> {
     ) -> Option<(*mut u8, usize)> {
         let slice_index = *self.index.get(archetype as usize)?;
         let (ptr, len) = self.slices.get(slice_index)?;
-        *self.versions.get_unchecked(slice_index).get() = next_component_version();
+        let version = next_component_version();
+        *self.versions.get_unchecked(slice_index).get() = version;
+        for entity_version in (*self.entity_versions.get_unchecked(slice_index).get())
+            .changed
+            .iter_mut()
+        {
+            *entity_version = version;
+        }
         Some((ptr.as_ptr() as *mut u8, *len))
     }
 
@@ -504,7 +651,11 @@ impl<T: Component> UnknownComponentStorage for PackedStorage<T> {
         allocation.extend_memcopy(self.epoch, ptr as *const T, count);
         self.slices[slice_index] = allocation.as_raw_slice();
         self.entity_len += count;
-        *self.versions[slice_index].get() = next_component_version();
+        let version = next_component_version();
+        *self.versions[slice_index].get() = version;
+        self.entity_versions[slice_index]
+            .get_mut()
+            .extend(count, version);
     }
 
     fn increment_epoch(&mut self) {
@@ -524,6 +675,7 @@ impl<T: Component> Default for PackedStorage<T> {
             index: Vec::new(),
             slices: Vec::new(),
             versions: Vec::new(),
+            entity_versions: Vec::new(),
             allocations: Vec::new(),
             entity_len: 0,
             epoch: 0,
@@ -544,7 +696,13 @@ impl<'a, T: Component> ComponentStorage<'a, T> for PackedStorage<T> {
         let (ptr, len) = self.slices.get(slice_index)?;
         let slice = unsafe { std::slice::from_raw_parts(ptr.as_ptr(), *len as usize) };
         let version = unsafe { &*self.versions.get_unchecked(slice_index).get() };
-        Some(ComponentSlice::new(slice, version))
+        let entity_versions = unsafe { &*self.entity_versions.get_unchecked(slice_index).get() };
+        Some(ComponentSlice::new(
+            slice,
+            version,
+            &entity_versions.changed,
+            &entity_versions.added,
+        ))
     }
 
     unsafe fn get_mut(
@@ -555,14 +713,21 @@ impl<'a, T: Component> ComponentStorage<'a, T> for PackedStorage<T> {
         let (ptr, len) = self.slices.get(slice_index)?;
         let slice = std::slice::from_raw_parts_mut(ptr.as_ptr(), *len as usize);
         let version = &mut *self.versions.get_unchecked(slice_index).get();
-        Some(ComponentSliceMut::new(slice, version))
+        let entity_versions = &mut *self.entity_versions.get_unchecked(slice_index).get();
+        Some(ComponentSliceMut::new(
+            slice,
+            version,
+            &mut entity_versions.changed,
+            &entity_versions.added,
+        ))
     }
 
     fn iter(&'a self, start_inclusive: usize, end_exclusive: usize) -> Self::Iter {
         ComponentIter {
             slices: self.slices[start_inclusive..end_exclusive]
                 .iter()
-                .zip(self.versions[start_inclusive..end_exclusive].iter()),
+                .zip(self.versions[start_inclusive..end_exclusive].iter())
+                .zip(self.entity_versions[start_inclusive..end_exclusive].iter()),
         }
     }
 
@@ -570,7 +735,8 @@ impl<'a, T: Component> ComponentStorage<'a, T> for PackedStorage<T> {
         ComponentIterMut {
             slices: self.slices[start_inclusive..end_exclusive]
                 .iter()
-                .zip(self.versions[start_inclusive..end_exclusive].iter()),
+                .zip(self.versions[start_inclusive..end_exclusive].iter())
+                .zip(self.entity_versions[start_inclusive..end_exclusive].iter()),
         }
     }
 
@@ -579,9 +745,14 @@ impl<'a, T: Component> ComponentStorage<'a, T> for PackedStorage<T> {
     }
 }
 
+type SliceIter<'a, T> = Zip<
+    Zip<Iter<'a, (NonNull<T>, usize)>, Iter<'a, UnsafeCell<u64>>>,
+    Iter<'a, UnsafeCell<EntityVersions>>,
+>;
+
 #[doc(hidden)]
 pub struct ComponentIter<'a, T> {
-    slices: Zip<Iter<'a, (NonNull<T>, usize)>, Iter<'a, UnsafeCell<u64>>>,
+    slices: SliceIter<'a, T>,
 }
 
 impl<'a, T: Component> Iterator for ComponentIter<'a, T> {
@@ -589,17 +760,25 @@ impl<'a, T: Component> Iterator for ComponentIter<'a, T> {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        self.slices.next().map(|((ptr, len), version)| {
-            let slice = unsafe { std::slice::from_raw_parts(ptr.as_ptr(), *len as usize) };
-            let version = unsafe { &*version.get() };
-            ComponentSlice::new(slice, version)
-        })
+        self.slices
+            .next()
+            .map(|(((ptr, len), version), entity_versions)| {
+                let slice = unsafe { std::slice::from_raw_parts(ptr.as_ptr(), *len as usize) };
+                let version = unsafe { &*version.get() };
+                let entity_versions = unsafe { &*entity_versions.get() };
+                ComponentSlice::new(
+                    slice,
+                    version,
+                    &entity_versions.changed,
+                    &entity_versions.added,
+                )
+            })
     }
 }
 
 #[doc(hidden)]
 pub struct ComponentIterMut<'a, T> {
-    slices: Zip<Iter<'a, (NonNull<T>, usize)>, Iter<'a, UnsafeCell<u64>>>,
+    slices: SliceIter<'a, T>,
 }
 
 impl<'a, T: Component> Iterator for ComponentIterMut<'a, T> {
@@ -607,12 +786,20 @@ impl<'a, T: Component> Iterator for ComponentIterMut<'a, T> {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        self.slices.next().map(|((ptr, len), version)| {
-            // safety: we know each slice is disjoint
-            let slice = unsafe { std::slice::from_raw_parts_mut(ptr.as_ptr(), *len as usize) };
-            let version = unsafe { &mut *version.get() };
-            ComponentSliceMut::new(slice, version)
-        })
+        self.slices
+            .next()
+            .map(|(((ptr, len), version), entity_versions)| {
+                // safety: we know each slice is disjoint
+                let slice = unsafe { std::slice::from_raw_parts_mut(ptr.as_ptr(), *len as usize) };
+                let version = unsafe { &mut *version.get() };
+                let entity_versions = unsafe { &mut *entity_versions.get() };
+                ComponentSliceMut::new(
+                    slice,
+                    version,
+                    &mut entity_versions.changed,
+                    &entity_versions.added,
+                )
+            })
     }
 }
 
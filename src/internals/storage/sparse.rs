@@ -0,0 +1,194 @@
+//! Component storage for components which are not part of an entity's archetype.
+
+use super::{component::Component, Components};
+use crate::internals::entity::{Entity, EntityHasher};
+use downcast_rs::{impl_downcast, Downcast};
+use std::{cell::UnsafeCell, collections::HashMap};
+
+/// A sparse set of components of type `T`, indexed by entity.
+///
+/// Components stored in a sparse set do not form part of an entity's layout, so adding or
+/// removing them does not move the entity between archetypes. This makes sparse storage
+/// suitable for components which are frequently added and removed, such as markers.
+/// Components are kept in a dense array, so they can still be iterated efficiently.
+///
+/// Sparse storage is opted into per insertion with
+/// [World::insert_sparse](../world/struct.World.html#method.insert_sparse), rather than selected
+/// by `Component::Storage`, and sparse components are only visible to the sparse APIs. Layout
+/// and change filters do not see them, and they are not cloned by `clone_from`, serialized, or
+/// reported by archetype events.
+pub struct SparseSet<T> {
+    indices: HashMap<Entity, usize, EntityHasher>,
+    entities: Vec<Entity>,
+    components: Vec<UnsafeCell<T>>,
+}
+
+// the set only hands out mutable references to its components through `&self` via unsafe
+// methods, whose callers must guarantee that no reference is aliased
+unsafe impl<T: Component> Sync for SparseSet<T> {}
+
+impl<T> Default for SparseSet<T> {
+    fn default() -> Self {
+        Self {
+            indices: HashMap::default(),
+            entities: Vec::new(),
+            components: Vec::new(),
+        }
+    }
+}
+
+impl<T> SparseSet<T> {
+    /// Returns the number of components in the set.
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Returns `true` if the set contains no components.
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Returns `true` if the given entity has a component in the set.
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.indices.contains_key(&entity)
+    }
+
+    /// Returns the entities which have a component in the set, in the same order as
+    /// [iter](#method.iter).
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    /// Returns the component of the given entity.
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        self.indices
+            .get(&entity)
+            .map(|i| unsafe { &*self.components[*i].get() })
+    }
+
+    /// Returns the component of the given entity mutably.
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        let index = *self.indices.get(&entity)?;
+        Some(self.components[index].get_mut())
+    }
+
+    /// Returns the component of the given entity mutably.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that no other reference to the entity's component is alive.
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) unsafe fn get_unchecked_mut(&self, entity: Entity) -> Option<&mut T> {
+        self.indices
+            .get(&entity)
+            .map(|i| &mut *self.components[*i].get())
+    }
+
+    /// Inserts a component for the given entity, returning the entity's previous component.
+    pub fn insert(&mut self, entity: Entity, component: T) -> Option<T> {
+        if let Some(index) = self.indices.get(&entity) {
+            let previous = std::mem::replace(&mut self.components[*index], component.into());
+            Some(previous.into_inner())
+        } else {
+            self.indices.insert(entity, self.entities.len());
+            self.entities.push(entity);
+            self.components.push(component.into());
+            None
+        }
+    }
+
+    /// Removes and returns the component of the given entity.
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        let index = self.indices.remove(&entity)?;
+        self.entities.swap_remove(index);
+        let component = self.components.swap_remove(index);
+        if let Some(swapped) = self.entities.get(index) {
+            self.indices.insert(*swapped, index);
+        }
+        Some(component.into_inner())
+    }
+
+    /// Returns an iterator over each entity in the set and its component.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> + '_ {
+        self.entities
+            .iter()
+            .zip(self.components.iter())
+            .map(|(entity, component)| (*entity, unsafe { &*component.get() }))
+    }
+
+    /// Returns an iterator over each entity in the set and its component, mutably.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> + '_ {
+        self.entities
+            .iter()
+            .zip(self.components.iter_mut())
+            .map(|(entity, component)| (*entity, component.get_mut()))
+    }
+}
+
+impl<T> std::fmt::Debug for SparseSet<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SparseSet")
+            .field("entities", &self.entities)
+            .finish()
+    }
+}
+
+/// A sparse set whose component type is not known statically.
+pub trait UnknownSparseSet: Downcast + Send + Sync {
+    /// Returns `true` if the given entity has a component in the set.
+    fn contains(&self, entity: Entity) -> bool;
+
+    /// Removes and drops the component of the given entity. Returns `true` if the entity had
+    /// a component in the set.
+    fn remove(&mut self, entity: Entity) -> bool;
+
+    /// Moves the component of the given entity, if it has one, into the component storage of
+    /// another world.
+    fn transfer(&mut self, entity: Entity, dst: &mut Components);
+}
+impl_downcast!(UnknownSparseSet);
+
+impl<T: Component> UnknownSparseSet for SparseSet<T> {
+    fn contains(&self, entity: Entity) -> bool {
+        SparseSet::contains(self, entity)
+    }
+
+    fn remove(&mut self, entity: Entity) -> bool {
+        SparseSet::remove(self, entity).is_some()
+    }
+
+    fn transfer(&mut self, entity: Entity, dst: &mut Components) {
+        if let Some(component) = SparseSet::remove(self, entity) {
+            dst.get_sparse_or_insert::<T>().insert(entity, component);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::internals::world::World;
+
+    #[test]
+    fn insert_remove() {
+        let mut world = World::default();
+        let entities = world.extend(vec![(1usize,), (2usize,), (3usize,)]).to_vec();
+
+        let mut set = SparseSet::<bool>::default();
+        for entity in &entities {
+            assert_eq!(set.insert(*entity, false), None);
+        }
+        assert_eq!(set.insert(entities[1], true), Some(false));
+        assert_eq!(set.len(), 3);
+
+        assert_eq!(set.remove(entities[0]), Some(false));
+        assert_eq!(set.remove(entities[0]), None);
+        assert!(!set.contains(entities[0]));
+        assert_eq!(set.get(entities[1]), Some(&true));
+        assert_eq!(set.get(entities[2]), Some(&false));
+
+        *set.get_mut(entities[2]).unwrap() = true;
+        assert!(set.iter().all(|(_, value)| *value));
+        assert_eq!(set.entities().len(), 2);
+    }
+}
@@ -10,7 +10,10 @@ use super::{
         view::{IntoView, View},
         Query,
     },
-    storage::{archetype::ArchetypeIndex, component::ComponentTypeId},
+    storage::{
+        archetype::ArchetypeIndex,
+        component::{Component, ComponentTypeId},
+    },
     world::{EntityAccessError, EntityStore, StorageAccessor, World, WorldId},
 };
 use bit_set::BitSet;
@@ -235,6 +238,18 @@ impl<'a> SubWorld<'a> {
         self.split::<V>()
     }
 
+    /// Returns the entities which have lost a component of type `T` since the world's removal log
+    /// was last cleared. See [World::removed](struct.World.html#method.removed).
+    ///
+    /// Returns an `AccessDenied` error if the subworld does not have read access to `T`.
+    pub fn removed<T: Component>(&self) -> Result<&'a [Entity], EntityAccessError> {
+        if self.components.allows_read(ComponentTypeId::of::<T>()) {
+            Ok(self.world.removed::<T>())
+        } else {
+            Err(EntityAccessError::AccessDenied)
+        }
+    }
+
     fn validate_archetype_access(&self, ArchetypeIndex(arch_index): ArchetypeIndex) -> bool {
         if let Some(archetypes) = self.archetypes {
             archetypes.contains(arch_index as usize)
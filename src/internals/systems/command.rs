@@ -8,19 +8,22 @@
 use crate::{
     internals::{
         entity::Entity,
+        entry::ComponentSet,
         insert::{
             ArchetypeSource, ArchetypeWriter, ComponentSource, IntoComponentSource, KnownLength,
         },
+        prefab::{Instance, Overrides, Prefab},
         storage::{archetype::EntityLayout, component::Component},
         systems::resources::Resources,
-        world::{World, WorldId},
+        world::{Merger, World, WorldId},
     },
-    world::Allocate,
+    world::{Allocate, EntityRange},
 };
 use derivative::Derivative;
 use smallvec::SmallVec;
 use std::ops::Range;
 use std::{
+    any::Any,
     collections::VecDeque,
     iter::{Fuse, FusedIterator},
     marker::PhantomData,
@@ -154,42 +157,248 @@ impl WorldWritable for DeleteEntityCommand {
 
 #[derive(Derivative)]
 #[derivative(Debug(bound = ""))]
-struct AddComponentCommand<C> {
-    #[derivative(Debug = "ignore")]
+struct AddComponentsCommand<T> {
     entity: Entity,
     #[derivative(Debug = "ignore")]
-    component: C,
+    components: T,
 }
 
-impl<C> WorldWritable for AddComponentCommand<C>
+impl<T> WorldWritable for AddComponentsCommand<T>
 where
-    C: Component,
+    T: ComponentSet + Send + Sync,
 {
     fn write(self: Arc<Self>, world: &mut World, _: &CommandBuffer) {
         let consumed = Arc::try_unwrap(self).unwrap();
         world
             .entry(consumed.entity)
             .expect("entity not found")
-            .add_component(consumed.component);
+            .add_components(consumed.components);
     }
 }
 
 #[derive(Derivative)]
 #[derivative(Debug(bound = ""))]
-struct RemoveComponentCommand<C> {
+struct CloneEntityCommand<F> {
     entity: Entity,
-    _marker: PhantomData<C>,
+    clone: Entity,
+    #[derivative(Debug = "ignore")]
+    merger: F,
 }
 
-impl<C> WorldWritable for RemoveComponentCommand<C>
+impl<M, F> WorldWritable for CloneEntityCommand<F>
 where
-    C: Component,
+    M: Merger,
+    F: FnOnce() -> M + Send + Sync,
+{
+    fn write(self: Arc<Self>, world: &mut World, _: &CommandBuffer) {
+        let consumed = Arc::try_unwrap(self).unwrap();
+        let mut merger = (consumed.merger)();
+        world.clone_entity_as(consumed.entity, consumed.clone, &mut merger);
+    }
+}
+
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+struct InstantiateCommand<F> {
+    #[derivative(Debug = "ignore")]
+    prefab: Arc<Prefab>,
+    instance: Instance,
+    #[derivative(Debug = "ignore")]
+    overrides: Overrides,
+    #[derivative(Debug = "ignore")]
+    merger: F,
+}
+
+impl<M, F> WorldWritable for InstantiateCommand<F>
+where
+    M: Merger,
+    F: FnOnce() -> M + Send + Sync,
+{
+    fn write(self: Arc<Self>, world: &mut World, _: &CommandBuffer) {
+        let consumed = Arc::try_unwrap(self).unwrap();
+        let mut merger = (consumed.merger)();
+        world.instantiate_as(
+            &consumed.prefab,
+            &consumed.instance,
+            consumed.overrides,
+            &mut merger,
+        );
+    }
+}
+
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+struct RemoveComponentsCommand<T> {
+    entity: Entity,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> WorldWritable for RemoveComponentsCommand<T>
+where
+    T: ComponentSet,
 {
     fn write(self: Arc<Self>, world: &mut World, _: &CommandBuffer) {
         world
             .entry(self.entity)
             .expect("entity not found")
-            .remove_component::<C>();
+            .remove_components::<T>();
+    }
+}
+
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+struct InsertSparseCommand<T> {
+    entity: Entity,
+    #[derivative(Debug = "ignore")]
+    component: T,
+}
+
+impl<T: Component> WorldWritable for InsertSparseCommand<T> {
+    fn write(self: Arc<Self>, world: &mut World, _: &CommandBuffer) {
+        let consumed = Arc::try_unwrap(self).unwrap();
+        world
+            .insert_sparse(consumed.entity, consumed.component)
+            .expect("entity not found");
+    }
+}
+
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+struct RemoveSparseCommand<T> {
+    entity: Entity,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Component> WorldWritable for RemoveSparseCommand<T> {
+    fn write(self: Arc<Self>, world: &mut World, _: &CommandBuffer) {
+        world.remove_sparse::<T>(self.entity);
+    }
+}
+
+// A command which absorbs subsequent commands of the same kind, so that they can be applied to
+// the world together.
+trait BatchCommand: Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    fn write(self: Box<Self>, world: &mut World);
+}
+
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""), Default(bound = ""))]
+struct AddComponentBatch<C> {
+    #[derivative(Debug = "ignore")]
+    components: Vec<(Entity, C)>,
+}
+
+impl<C> BatchCommand for AddComponentBatch<C>
+where
+    C: Component,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn write(self: Box<Self>, world: &mut World) {
+        for (entity, _) in &self.components {
+            assert!(world.contains(*entity), "entity not found");
+        }
+        world.add_components_batch(self.components);
+    }
+}
+
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""), Default(bound = ""))]
+struct RemoveComponentBatch<C> {
+    entities: Vec<Entity>,
+    _marker: PhantomData<C>,
+}
+
+impl<C> BatchCommand for RemoveComponentBatch<C>
+where
+    C: Component,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn write(self: Box<Self>, world: &mut World) {
+        for entity in &self.entities {
+            assert!(world.contains(*entity), "entity not found");
+        }
+        world.remove_component_batch::<C>(&self.entities);
+    }
+}
+
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+struct SetParentCommand {
+    child: Entity,
+    parent: Entity,
+}
+
+impl WorldWritable for SetParentCommand {
+    fn write(self: Arc<Self>, world: &mut World, _: &CommandBuffer) {
+        if let Err(err) = world.set_parent(self.child, self.parent) {
+            panic!("failed to set parent: {}", err);
+        }
+    }
+}
+
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+struct RemoveParentCommand(Entity);
+
+impl WorldWritable for RemoveParentCommand {
+    fn write(self: Arc<Self>, world: &mut World, _: &CommandBuffer) {
+        world.remove_parent(self.0);
+    }
+}
+
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+struct DeleteRecursiveCommand(Entity);
+
+impl WorldWritable for DeleteRecursiveCommand {
+    fn write(self: Arc<Self>, world: &mut World, _: &CommandBuffer) {
+        world.remove_recursive(self.0);
+    }
+}
+
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+struct RelateCommand<K> {
+    source: Entity,
+    target: Entity,
+    _marker: PhantomData<fn() -> K>,
+}
+
+impl<K: 'static> WorldWritable for RelateCommand<K> {
+    fn write(self: Arc<Self>, world: &mut World, _: &CommandBuffer) {
+        world.relate::<K>(self.source, self.target);
+    }
+}
+
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+struct UnrelateCommand<K> {
+    source: Entity,
+    target: Entity,
+    _marker: PhantomData<fn() -> K>,
+}
+
+impl<K: 'static> WorldWritable for UnrelateCommand<K> {
+    fn write(self: Arc<Self>, world: &mut World, _: &CommandBuffer) {
+        world.unrelate::<K>(self.source, self.target);
     }
 }
 
@@ -197,6 +406,7 @@ where
 enum Command {
     WriteWorld(Arc<dyn WorldWritable>),
     ExecMutWorld(Arc<dyn Fn(&mut World, &mut Resources) + Send + Sync>),
+    WriteBatch(Box<dyn BatchCommand>),
 }
 
 /// A command buffer used to queue mutable changes to the world from a system. This buffer is automatically
@@ -227,7 +437,10 @@ enum Command {
 pub struct CommandBuffer {
     world_id: WorldId,
     commands: VecDeque<Command>,
+    // the number of commands queued, counting each command absorbed into a batch
+    queued: usize,
     entity_allocator: Allocate,
+    reserved: EntityRange,
     pending_insertion: SmallVec<[Entity; 64]>,
 }
 
@@ -237,8 +450,10 @@ impl CommandBuffer {
         Self {
             world_id: world.id(),
             commands: Default::default(),
+            queued: 0,
             pending_insertion: SmallVec::new(),
-            entity_allocator: Allocate::new(),
+            entity_allocator: world.command_allocator(),
+            reserved: EntityRange::default(),
         }
     }
 
@@ -260,8 +475,10 @@ impl CommandBuffer {
             match command {
                 Command::WriteWorld(ptr) => ptr.write(world, self),
                 Command::ExecMutWorld(closure) => closure(world, resources),
+                Command::WriteBatch(batch) => batch.write(world),
             }
         }
+        self.queued = 0;
 
         self.pending_insertion.clear();
     }
@@ -273,6 +490,7 @@ impl CommandBuffer {
         F: 'static + Fn(&mut World, &mut Resources) + Send + Sync,
     {
         self.commands.push_front(Command::ExecMutWorld(Arc::new(f)));
+        self.queued += 1;
     }
 
     /// Inserts an arbitrary implementor of the `WorldWritable` trait into the command queue.
@@ -284,6 +502,15 @@ impl CommandBuffer {
     {
         self.commands
             .push_front(Command::WriteWorld(Arc::new(writer)));
+        self.queued += 1;
+    }
+
+    /// Causes entities pushed into the command buffer to take their IDs from the given range of
+    /// [reserved](../world/struct.World.html#method.reserve_entities) IDs, replacing any
+    /// previously given range. Once the range is exhausted, IDs are allocated by the world's
+    /// allocator.
+    pub fn allocate_from(&mut self, range: EntityRange) {
+        self.reserved = range;
     }
 
     /// Queues the insertion of a single entity into the world.
@@ -295,6 +522,14 @@ impl CommandBuffer {
         self.extend(Some(components))[0]
     }
 
+    // Allocates an ID for a new entity, preferring reserved IDs.
+    fn next_entity(&mut self) -> Entity {
+        self.reserved
+            .next()
+            .or_else(|| self.entity_allocator.next())
+            .unwrap()
+    }
+
     /// Queues the insertion of new entities into the world.
     pub fn extend<T>(&mut self, components: T) -> &[Entity]
     where
@@ -307,8 +542,8 @@ impl CommandBuffer {
 
         self.pending_insertion.reserve(count);
         for _ in 0..count {
-            self.pending_insertion
-                .push(self.entity_allocator.next().unwrap());
+            let entity = self.next_entity();
+            self.pending_insertion.push(entity);
         }
 
         let range = start..self.pending_insertion.len();
@@ -318,32 +553,183 @@ impl CommandBuffer {
                 components,
                 entities: range.clone(),
             })));
+        self.queued += 1;
 
         &self.pending_insertion[range]
     }
 
+    /// Queues the cloning of an entity within the world, returning the ID of the clone.
+    ///
+    /// The merger is constructed when the command buffer is flushed, as mergers such as
+    /// [Duplicate](../world/struct.Duplicate.html) cannot be sent between threads.
+    /// See [World::clone_entity](../world/struct.World.html#method.clone_entity).
+    pub fn clone_entity<M, F>(&mut self, entity: Entity, merger: F) -> Entity
+    where
+        M: Merger,
+        F: 'static + FnOnce() -> M + Send + Sync,
+    {
+        let clone = self.next_entity();
+        self.insert_writer(CloneEntityCommand {
+            entity,
+            clone,
+            merger,
+        });
+        clone
+    }
+
+    /// Queues the instantiation of a prefab into the world, returning the IDs of the entities
+    /// which will be created.
+    ///
+    /// As with [clone_entity](#method.clone_entity), the merger is constructed when the command
+    /// buffer is flushed.
+    /// See [World::instantiate](../world/struct.World.html#method.instantiate).
+    pub fn instantiate<M, F>(
+        &mut self,
+        prefab: Arc<Prefab>,
+        overrides: Overrides,
+        merger: F,
+    ) -> Instance
+    where
+        M: Merger,
+        F: 'static + FnOnce() -> M + Send + Sync,
+    {
+        let instance = Instance::allocate(&prefab, || self.next_entity());
+        self.insert_writer(InstantiateCommand {
+            prefab,
+            instance: instance.clone(),
+            overrides,
+            merger,
+        });
+        instance
+    }
+
     /// Queues the deletion of an entity in the command buffer.
     pub fn remove(&mut self, entity: Entity) {
         self.insert_writer(DeleteEntityCommand(entity));
     }
 
+    /// Queues the deletion of an entity and all of its descendants in the command buffer.
+    ///
+    /// See [World::remove_recursive](../world/struct.World.html#method.remove_recursive).
+    pub fn remove_recursive(&mut self, entity: Entity) {
+        self.insert_writer(DeleteRecursiveCommand(entity));
+    }
+
+    /// Queues setting the parent of an entity in the command buffer.
+    ///
+    /// See [World::set_parent](../world/struct.World.html#method.set_parent). The flush will
+    /// panic if the parent cannot be set.
+    pub fn set_parent(&mut self, child: Entity, parent: Entity) {
+        self.insert_writer(SetParentCommand { child, parent });
+    }
+
+    /// Queues the removal of an entity's parent in the command buffer.
+    pub fn remove_parent(&mut self, child: Entity) {
+        self.insert_writer(RemoveParentCommand(child));
+    }
+
+    /// Queues relating `source` to `target` by a relation of kind `K` in the command buffer.
+    ///
+    /// See [World::relate](../world/struct.World.html#method.relate).
+    pub fn relate<K: 'static>(&mut self, source: Entity, target: Entity) {
+        self.insert_writer(RelateCommand::<K> {
+            source,
+            target,
+            _marker: PhantomData,
+        });
+    }
+
+    /// Queues the removal of the relation of kind `K` from `source` to `target` in the command
+    /// buffer.
+    pub fn unrelate<K: 'static>(&mut self, source: Entity, target: Entity) {
+        self.insert_writer(UnrelateCommand::<K> {
+            source,
+            target,
+            _marker: PhantomData,
+        });
+    }
+
     /// Queues the addition of a component from an entity in the command buffer.
+    ///
+    /// Consecutive additions of the same component type are applied to the world as a
+    /// [batch](../world/struct.World.html#method.add_component_batch).
     pub fn add_component<C: Component>(&mut self, entity: Entity, component: C) {
-        self.insert_writer(AddComponentCommand { entity, component });
+        self.batch_writer::<AddComponentBatch<C>>()
+            .components
+            .push((entity, component));
     }
 
     /// Queues the removal of a component from an entity in the command buffer.
+    ///
+    /// Consecutive removals of the same component type are applied to the world as a
+    /// [batch](../world/struct.World.html#method.remove_component_batch).
     pub fn remove_component<C: Component>(&mut self, entity: Entity) {
-        self.insert_writer(RemoveComponentCommand {
+        self.batch_writer::<RemoveComponentBatch<C>>()
+            .entities
+            .push(entity);
+    }
+
+    /// Queues the addition of a set of components to an entity in the command buffer.
+    ///
+    /// See [Entry::add_components](../world/struct.Entry.html#method.add_components).
+    pub fn add_components<T>(&mut self, entity: Entity, components: T)
+    where
+        T: ComponentSet + Send + Sync,
+    {
+        self.insert_writer(AddComponentsCommand { entity, components });
+    }
+
+    /// Queues the removal of a set of components from an entity in the command buffer.
+    ///
+    /// See [Entry::remove_components](../world/struct.Entry.html#method.remove_components).
+    pub fn remove_components<T: ComponentSet>(&mut self, entity: Entity) {
+        self.insert_writer(RemoveComponentsCommand::<T> {
             entity,
-            _marker: PhantomData::<C>::default(),
+            _marker: PhantomData,
         });
     }
 
+    /// Queues the addition of a component to an entity in sparse storage in the command buffer.
+    ///
+    /// See [World::insert_sparse](../world/struct.World.html#method.insert_sparse). The flush
+    /// will panic if the entity does not exist.
+    pub fn insert_sparse<C: Component>(&mut self, entity: Entity, component: C) {
+        self.insert_writer(InsertSparseCommand { entity, component });
+    }
+
+    /// Queues the removal of a component from an entity in sparse storage in the command buffer.
+    pub fn remove_sparse<C: Component>(&mut self, entity: Entity) {
+        self.insert_writer(RemoveSparseCommand::<C> {
+            entity,
+            _marker: PhantomData,
+        });
+    }
+
+    // Returns the most recently queued command if it is a batch of type `B`, or else queues a
+    // new batch.
+    fn batch_writer<B: 'static + BatchCommand + Default>(&mut self) -> &mut B {
+        self.queued += 1;
+        let extend = matches!(
+            self.commands.front(),
+            Some(Command::WriteBatch(batch)) if batch.as_any().is::<B>()
+        );
+        if !extend {
+            self.commands
+                .push_front(Command::WriteBatch(Box::new(B::default())));
+        }
+        match self.commands.front_mut() {
+            Some(Command::WriteBatch(batch)) => batch.as_any_mut().downcast_mut::<B>().unwrap(),
+            _ => unreachable!(),
+        }
+    }
+
     /// Returns the current number of commands already queued in this `CommandBuffer` instance.
+    ///
+    /// Component additions and removals which are applied together as a batch are each counted
+    /// as a separate command.
     #[inline]
     pub fn len(&self) -> usize {
-        self.commands.len()
+        self.queued
     }
 
     /// Returns true if this `CommandBuffer` is currently empty and contains no writers.
@@ -397,4 +783,131 @@ mod tests {
 
         assert_eq!(components_len, count);
     }
+
+    #[test]
+    fn coalesce_component_commands() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let entities = world
+            .extend(vec![(Pos(1., 2., 3.),), (Pos(4., 5., 6.),)])
+            .to_vec();
+
+        let mut command = CommandBuffer::new(&world);
+        command.add_component(entities[0], Vel(1., 1., 1.));
+        command.add_component(entities[1], Vel(2., 2., 2.));
+        command.add_component(entities[0], Vel(3., 3., 3.));
+        command.remove_component::<Pos>(entities[1]);
+        assert_eq!(command.len(), 4);
+        command.flush(&mut world, &mut resources);
+        assert!(command.is_empty());
+
+        let entry = world.entry(entities[0]).unwrap();
+        assert_eq!(entry.get_component::<Vel>(), Ok(&Vel(3., 3., 3.)));
+        let entry = world.entry(entities[1]).unwrap();
+        assert_eq!(entry.get_component::<Vel>(), Ok(&Vel(2., 2., 2.)));
+        assert!(entry.get_component::<Pos>().is_err());
+    }
+
+    #[test]
+    fn component_sets() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let entity = world.push((Pos(1., 2., 3.),));
+
+        let mut command = CommandBuffer::new(&world);
+        command.add_components(entity, (Vel(1., 1., 1.), 5usize));
+        command.flush(&mut world, &mut resources);
+        let entry = world.entry(entity).unwrap();
+        assert_eq!(entry.get_component::<Vel>(), Ok(&Vel(1., 1., 1.)));
+        assert_eq!(entry.get_component::<usize>(), Ok(&5usize));
+
+        command.remove_components::<(Pos, usize)>(entity);
+        command.flush(&mut world, &mut resources);
+        let entry = world.entry(entity).unwrap();
+        assert_eq!(entry.archetype().layout().component_types().len(), 1);
+        assert_eq!(entry.get_component::<Vel>(), Ok(&Vel(1., 1., 1.)));
+    }
+
+    #[test]
+    fn clone_entity() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let entity = world.push((Pos(1., 2., 3.), Vel(1., 1., 1.)));
+
+        let mut command = CommandBuffer::new(&world);
+        let clone = command.clone_entity(entity, || {
+            let mut merger = crate::internals::world::Duplicate::default();
+            merger.register_copy::<Pos>();
+            merger
+        });
+        assert!(!world.contains(clone));
+        command.flush(&mut world, &mut resources);
+
+        let entry = world.entry(clone).unwrap();
+        assert_eq!(entry.get_component::<Pos>(), Ok(&Pos(1., 2., 3.)));
+        assert!(entry.get_component::<Vel>().is_err());
+        assert!(world.contains(entity));
+    }
+
+    #[test]
+    fn allocate_from() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let reserved = world.reserve_entities(2);
+
+        let mut command = CommandBuffer::new(&world);
+        command.allocate_from(reserved.clone());
+        let entities = command.extend(vec![(Pos(1., 2., 3.),); 3]).to_vec();
+        command.flush(&mut world, &mut resources);
+
+        assert_eq!(&entities[..2], &reserved.collect::<Vec<_>>()[..]);
+        assert!(entities.iter().all(|entity| world.contains(*entity)));
+    }
+
+    #[test]
+    fn hierarchy() {
+        use crate::internals::hierarchy::descendants;
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let root = world.push((Pos(1., 2., 3.),));
+
+        let mut command = CommandBuffer::new(&world);
+        let child = command.push((Pos(4., 5., 6.),));
+        let grandchild = command.push((Pos(7., 8., 9.),));
+        command.set_parent(child, root);
+        command.set_parent(grandchild, child);
+        command.flush(&mut world, &mut resources);
+
+        assert_eq!(
+            descendants(&world, root).collect::<Vec<_>>(),
+            vec![child, grandchild]
+        );
+
+        command.remove_recursive(child);
+        command.flush(&mut world, &mut resources);
+        assert_eq!(world.len(), 1);
+        assert_eq!(descendants(&world, root).count(), 0);
+    }
+
+    #[test]
+    fn sparse_components() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let entity = world.push((Pos(1., 2., 3.),));
+        let archetype = world.entry(entity).unwrap().location().archetype();
+
+        let mut command = CommandBuffer::new(&world);
+        command.insert_sparse(entity, 5usize);
+        command.flush(&mut world, &mut resources);
+        assert_eq!(world.get_sparse::<usize>(entity), Some(&5usize));
+        assert_eq!(
+            world.entry(entity).unwrap().location().archetype(),
+            archetype
+        );
+
+        command.remove_sparse::<usize>(entity);
+        command.flush(&mut world, &mut resources);
+        assert_eq!(world.get_sparse::<usize>(entity), None);
+    }
 }
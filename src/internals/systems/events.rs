@@ -0,0 +1,302 @@
+//! Contains types related to typed event channels which can be shared between systems.
+//!
+//! Events are stored in an [Events](struct.Events.html) resource. Systems send events with an
+//! [EventWriter](struct.EventWriter.html) and receive them with an [EventReader](struct.EventReader.html),
+//! which tracks which events it has already seen with an [EventCursor](struct.EventCursor.html).
+
+use super::{schedule::ParallelRunnable, system::SystemBuilder};
+use std::{iter::FusedIterator, marker::PhantomData, slice::Iter};
+
+/// A double-buffered channel of events of type `T`, which can be stored as a resource.
+///
+/// Events remain readable for the frame in which they were sent and the frame after, and are
+/// then discarded. Frames are advanced by calling [update](#method.update), typically once per
+/// schedule execution with the system returned by [update_system](#method.update_system).
+/// Readers which do not read at least once every two updates will miss events.
+///
+/// # Examples
+///
+/// ```
+/// # use legion::*;
+/// # use legion::systems::{EventCursor, Events};
+/// struct Collision(u32);
+///
+/// let mut events = Events::<Collision>::default();
+/// let mut cursor = EventCursor::default();
+///
+/// events.send(Collision(1));
+/// assert_eq!(events.read(&mut cursor).count(), 1);
+///
+/// events.update();
+/// events.send(Collision(2));
+/// assert_eq!(events.read(&mut cursor).map(|c| c.0).collect::<Vec<_>>(), vec![2]);
+/// ```
+#[derive(Debug)]
+pub struct Events<T> {
+    // the index of the first event in `previous`
+    start: usize,
+    previous: Vec<T>,
+    current: Vec<T>,
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Self {
+            start: 0,
+            previous: Vec::new(),
+            current: Vec::new(),
+        }
+    }
+}
+
+impl<T> Events<T> {
+    /// Sends an event to all readers.
+    pub fn send(&mut self, event: T) {
+        self.current.push(event);
+    }
+
+    /// Returns an iterator over all events which the given cursor has not yet seen, and moves
+    /// the cursor past them.
+    pub fn read<'a>(&'a self, cursor: &mut EventCursor<T>) -> EventIter<'a, T> {
+        let skip = cursor.index.saturating_sub(self.start);
+        let (previous, current) = if skip < self.previous.len() {
+            (&self.previous[skip..], &self.current[..])
+        } else {
+            let skip = (skip - self.previous.len()).min(self.current.len());
+            (&self.previous[..0], &self.current[skip..])
+        };
+
+        cursor.index = self.end();
+        EventIter {
+            previous: previous.iter(),
+            current: current.iter(),
+        }
+    }
+
+    /// Constructs a cursor which will only see events sent after this call.
+    pub fn cursor_at_end(&self) -> EventCursor<T> {
+        EventCursor {
+            index: self.end(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Advances to the next frame, discarding all events sent before the previous update.
+    pub fn update(&mut self) {
+        self.start += self.previous.len();
+        std::mem::swap(&mut self.previous, &mut self.current);
+        self.current.clear();
+    }
+
+    /// Discards all buffered events.
+    pub fn clear(&mut self) {
+        self.start = self.end();
+        self.previous.clear();
+        self.current.clear();
+    }
+
+    /// Returns the number of buffered events.
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    /// Returns `true` if no events are buffered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn end(&self) -> usize {
+        self.start + self.len()
+    }
+}
+
+impl<T: Send + Sync + 'static> Events<T> {
+    /// Constructs a system which calls [update](#method.update) on the `Events<T>` resource.
+    ///
+    /// The system should be added to a schedule once, usually at its beginning or end.
+    pub fn update_system() -> impl ParallelRunnable {
+        SystemBuilder::new(format!("update_events<{}>", std::any::type_name::<T>()))
+            .write_resource::<Events<T>>()
+            .build(|_, _, events, _| events.update())
+    }
+}
+
+/// Records which events of type `T` a reader has already seen.
+///
+/// Each reader should own its own cursor, usually as part of its system's state.
+#[derive(Debug)]
+pub struct EventCursor<T> {
+    index: usize,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for EventCursor<T> {
+    fn default() -> Self {
+        Self {
+            index: 0,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for EventCursor<T> {
+    fn clone(&self) -> Self {
+        Self {
+            index: self.index,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Provides access to the events of type `T` which a reader has not yet seen.
+///
+/// Inside `#[system]` functions, an `EventReader<T>` parameter declares read access to the
+/// `Events<T>` resource and stores its cursor in the system.
+///
+/// ```
+/// # use legion::*;
+/// # use legion::systems::EventReader;
+/// struct Collision(u32);
+///
+/// #[system]
+/// fn print_collisions(mut collisions: EventReader<Collision>) {
+///     for collision in collisions.iter() {
+///         println!("collision {}", collision.0);
+///     }
+/// }
+/// ```
+pub struct EventReader<'a, T> {
+    events: &'a Events<T>,
+    cursor: &'a mut EventCursor<T>,
+}
+
+impl<'a, T> EventReader<'a, T> {
+    /// Constructs a new reader.
+    pub fn new(events: &'a Events<T>, cursor: &'a mut EventCursor<T>) -> Self {
+        Self { events, cursor }
+    }
+
+    /// Returns an iterator over all events which have not yet been read by this reader.
+    pub fn iter(&mut self) -> EventIter<'a, T> {
+        self.events.read(self.cursor)
+    }
+}
+
+/// Allows sending events of type `T`.
+///
+/// Inside `#[system]` functions, an `EventWriter<T>` parameter declares write access to the
+/// `Events<T>` resource.
+///
+/// ```
+/// # use legion::*;
+/// # use legion::systems::EventWriter;
+/// struct Collision(u32);
+///
+/// #[system]
+/// fn detect_collisions(mut collisions: EventWriter<Collision>) {
+///     collisions.send(Collision(5));
+/// }
+/// ```
+pub struct EventWriter<'a, T> {
+    events: &'a mut Events<T>,
+}
+
+impl<'a, T> EventWriter<'a, T> {
+    /// Constructs a new writer.
+    pub fn new(events: &'a mut Events<T>) -> Self {
+        Self { events }
+    }
+
+    /// Sends an event to all readers.
+    pub fn send(&mut self, event: T) {
+        self.events.send(event);
+    }
+}
+
+/// An iterator over unread events.
+pub struct EventIter<'a, T> {
+    previous: Iter<'a, T>,
+    current: Iter<'a, T>,
+}
+
+impl<'a, T> Iterator for EventIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.previous.next().or_else(|| self.current.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.previous.len() + self.current.len();
+        (len, Some(len))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for EventIter<'a, T> {}
+
+impl<'a, T> FusedIterator for EventIter<'a, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn double_buffered() {
+        let mut events = Events::<usize>::default();
+        let mut early = EventCursor::default();
+        let mut late = EventCursor::default();
+
+        events.send(1);
+        events.send(2);
+        assert_eq!(
+            events.read(&mut early).copied().collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(events.read(&mut early).count(), 0);
+
+        events.update();
+        events.send(3);
+        assert_eq!(
+            events.read(&mut early).copied().collect::<Vec<_>>(),
+            vec![3]
+        );
+
+        // events survive one update
+        assert_eq!(
+            events.read(&mut late).copied().collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+
+        // readers which fall behind miss events
+        let mut missed = EventCursor::default();
+        events.update();
+        events.update();
+        events.send(4);
+        assert_eq!(
+            events.read(&mut missed).copied().collect::<Vec<_>>(),
+            vec![4]
+        );
+    }
+
+    #[test]
+    fn cursor_at_end() {
+        let mut events = Events::<usize>::default();
+        events.send(1);
+
+        let mut cursor = events.cursor_at_end();
+        assert_eq!(events.read(&mut cursor).count(), 0);
+
+        events.send(2);
+        assert_eq!(
+            events.read(&mut cursor).copied().collect::<Vec<_>>(),
+            vec![2]
+        );
+
+        events.clear();
+        events.send(3);
+        assert_eq!(
+            events.read(&mut cursor).copied().collect::<Vec<_>>(),
+            vec![3]
+        );
+    }
+}
@@ -0,0 +1,345 @@
+//! Contains types related to systems with asynchronous bodies.
+//!
+//! An asynchronous system is built with
+//! [SystemBuilder::build_async](../struct.SystemBuilder.html#method.build_async). Its body
+//! returns a future which is polled across many executions of the schedule. The future accesses
+//! the world and resources through an [AsyncContext](struct.AsyncContext.html), which only
+//! permits access while the future is being polled.
+
+use super::{
+    command::CommandBuffer,
+    resources::ResourceSet,
+    system::{QuerySet, SystemFn},
+};
+use crate::internals::subworld::SubWorld;
+use parking_lot::Mutex;
+use std::{
+    future::Future,
+    mem::ManuallyDrop,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+    thread::ThreadId,
+};
+
+type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Pointers to the data provided to the system for the duration of a single poll.
+struct Borrows<R: ResourceSet<'static>, Q> {
+    thread: ThreadId,
+    commands: *mut CommandBuffer,
+    world: *mut SubWorld<'static>,
+    resources: *mut R::Result,
+    queries: *mut Q,
+}
+
+// safety: the pointers are only dereferenced by `AsyncContext::with`, which checks that it is
+// called on the thread which is polling the future
+unsafe impl<R: ResourceSet<'static>, Q> Send for Borrows<R, Q> {}
+
+type SharedBorrows<R, Q> = Arc<Mutex<Option<Borrows<R, Q>>>>;
+
+/// Provides an asynchronous system's future with access to the system's command buffer,
+/// world, resources and queries.
+///
+/// Access is only granted inside [with](#method.with), which may only be called while the future
+/// is being polled by its system, and so borrows can never be held across an `.await`.
+pub struct AsyncContext<R: ResourceSet<'static>, Q> {
+    borrows: SharedBorrows<R, Q>,
+}
+
+impl<R: ResourceSet<'static>, Q> Clone for AsyncContext<R, Q> {
+    fn clone(&self) -> Self {
+        Self {
+            borrows: self.borrows.clone(),
+        }
+    }
+}
+
+impl<R: ResourceSet<'static>, Q> AsyncContext<R, Q> {
+    /// Calls `f` with the system's command buffer, world, resources and queries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called while the future is not being polled by its system, from a thread other
+    /// than the one polling the future, or from within another call to `with`.
+    pub fn with<T, F>(&self, f: F) -> T
+    where
+        F: FnOnce(&mut CommandBuffer, &mut SubWorld, &mut R::Result, &mut Q) -> T,
+    {
+        let borrows = {
+            let mut shared = self.borrows.lock();
+            let borrows = shared
+                .as_ref()
+                .expect("async systems can only access data while being polled by their system");
+            assert_eq!(
+                borrows.thread,
+                std::thread::current().id(),
+                "async systems can only access data from the thread polling them"
+            );
+            shared.take()
+        };
+        let restore = RestoreBorrows {
+            shared: &self.borrows,
+            borrows,
+        };
+        let borrows = restore.borrows.as_ref().unwrap();
+
+        // safety: the pointers are valid for the duration of the poll, which we are within as
+        // the borrows have not been cleared, and are exclusive as we have taken them
+        unsafe {
+            f(
+                &mut *borrows.commands,
+                &mut *borrows.world,
+                &mut *borrows.resources,
+                &mut *borrows.queries,
+            )
+        }
+    }
+}
+
+// Returns taken borrows when a call to `with` completes.
+struct RestoreBorrows<'a, R: ResourceSet<'static>, Q> {
+    shared: &'a Mutex<Option<Borrows<R, Q>>>,
+    borrows: Option<Borrows<R, Q>>,
+}
+
+impl<'a, R: ResourceSet<'static>, Q> Drop for RestoreBorrows<'a, R, Q> {
+    fn drop(&mut self) {
+        *self.shared.lock() = self.borrows.take();
+    }
+}
+
+// Clears the borrows when a poll completes, including by panicking.
+struct ClearBorrows<'a, R: ResourceSet<'static>, Q>(&'a Mutex<Option<Borrows<R, Q>>>);
+
+impl<'a, R: ResourceSet<'static>, Q> Drop for ClearBorrows<'a, R, Q> {
+    fn drop(&mut self) {
+        *self.0.lock() = None;
+    }
+}
+
+// A flag which is set when a future's waker is woken.
+//
+// The waker is built from a raw vtable over an `Arc<AtomicBool>`, rather than with
+// `std::task::Wake`, which is not available on older compilers.
+#[derive(Clone, Default)]
+struct WakeFlag(Arc<AtomicBool>);
+
+impl WakeFlag {
+    fn set(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    fn take(&self) -> bool {
+        self.0.swap(false, Ordering::SeqCst)
+    }
+
+    fn waker(&self) -> Waker {
+        let raw = RawWaker::new(Arc::into_raw(self.0.clone()).cast(), &WAKE_FLAG_VTABLE);
+        // safety: the vtable functions uphold the `RawWaker` contract for pointers created
+        // by `Arc::into_raw`
+        unsafe { Waker::from_raw(raw) }
+    }
+}
+
+static WAKE_FLAG_VTABLE: RawWakerVTable = RawWakerVTable::new(
+    wake_flag_clone,
+    wake_flag_wake,
+    wake_flag_wake_by_ref,
+    wake_flag_drop,
+);
+
+unsafe fn wake_flag_clone(data: *const ()) -> RawWaker {
+    let flag = ManuallyDrop::new(Arc::from_raw(data.cast::<AtomicBool>()));
+    let clone = Arc::clone(&flag);
+    RawWaker::new(Arc::into_raw(clone).cast(), &WAKE_FLAG_VTABLE)
+}
+
+unsafe fn wake_flag_wake(data: *const ()) {
+    let flag = Arc::from_raw(data.cast::<AtomicBool>());
+    flag.store(true, Ordering::SeqCst);
+}
+
+unsafe fn wake_flag_wake_by_ref(data: *const ()) {
+    (*data.cast::<AtomicBool>()).store(true, Ordering::SeqCst);
+}
+
+unsafe fn wake_flag_drop(data: *const ()) {
+    drop(Arc::from_raw(data.cast::<AtomicBool>()));
+}
+
+/// The body of an asynchronous system.
+///
+/// Each time the system runs, it starts a new future if it does not have one in progress, and
+/// then polls the future if it has been woken since it was last polled. A future is therefore
+/// polled at most once per execution of its schedule. The waker passed to the future may be
+/// woken from any thread, and causes the future to be polled the next time the system runs.
+pub struct AsyncSystemFn<R: ResourceSet<'static>, Q> {
+    body: Box<dyn FnMut(AsyncContext<R, Q>) -> BoxFuture + Send>,
+    borrows: SharedBorrows<R, Q>,
+    pending: Option<BoxFuture>,
+    woken: WakeFlag,
+}
+
+// safety: the system function is only ever accessed through `&mut self`
+unsafe impl<R: ResourceSet<'static>, Q> Sync for AsyncSystemFn<R, Q> {}
+
+impl<R: ResourceSet<'static>, Q> AsyncSystemFn<R, Q> {
+    pub(crate) fn new<F, Fut>(mut body: F) -> Self
+    where
+        F: FnMut(AsyncContext<R, Q>) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        Self {
+            body: Box::new(move |context| Box::pin(body(context))),
+            borrows: Arc::new(Mutex::new(None)),
+            pending: None,
+            woken: WakeFlag::default(),
+        }
+    }
+}
+
+impl<R: ResourceSet<'static>, Q: QuerySet> SystemFn<R, Q> for AsyncSystemFn<R, Q> {
+    fn run(
+        &mut self,
+        commands: &mut CommandBuffer,
+        world: &mut SubWorld,
+        resources: &mut R::Result,
+        queries: &mut Q,
+    ) {
+        if self.pending.is_none() {
+            let context = AsyncContext {
+                borrows: self.borrows.clone(),
+            };
+            self.pending = Some((self.body)(context));
+            self.woken.set();
+        }
+
+        if !self.woken.take() {
+            return;
+        }
+
+        *self.borrows.lock() = Some(Borrows {
+            thread: std::thread::current().id(),
+            commands,
+            world: (world as *mut SubWorld).cast(),
+            resources,
+            queries,
+        });
+        let _clear = ClearBorrows(&self.borrows);
+
+        let waker = self.woken.waker();
+        let mut context = Context::from_waker(&waker);
+        if let Some(future) = &mut self.pending {
+            if let Poll::Ready(()) = future.as_mut().poll(&mut context) {
+                self.pending = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internals::{
+        systems::{
+            resources::{ResourceTypeId, Resources},
+            schedule::{Runnable, Schedule},
+            system::SystemBuilder,
+        },
+        world::World,
+    };
+
+    // A future which completes once it has been signalled.
+    #[derive(Clone, Default)]
+    struct Signal(Arc<Mutex<(bool, Option<Waker>)>>);
+
+    impl Signal {
+        fn set(&self) {
+            let mut state = self.0.lock();
+            state.0 = true;
+            if let Some(waker) = state.1.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    impl Future for Signal {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            let mut state = self.0.lock();
+            if state.0 {
+                Poll::Ready(())
+            } else {
+                state.1 = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    #[test]
+    fn polled_across_executions() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(0usize);
+
+        let signal = Signal::default();
+        let loaded = signal.clone();
+        let system = SystemBuilder::new("load")
+            .write_resource::<usize>()
+            .build_async(move |context| {
+                let loaded = loaded.clone();
+                async move {
+                    context.with(|_, _, count, _| **count += 1);
+                    loaded.await;
+                    context.with(|cmd, _, count, _| {
+                        **count += 10;
+                        cmd.push((5usize,));
+                    });
+                }
+            });
+
+        assert_eq!(system.writes().0, &[ResourceTypeId::of::<usize>()]);
+
+        let mut schedule = Schedule::builder().add_system(system).build();
+        schedule.execute(&mut world, &mut resources);
+        assert_eq!(*resources.get::<usize>().unwrap(), 1);
+
+        // the future is not polled again until it has been woken
+        schedule.execute(&mut world, &mut resources);
+        assert_eq!(*resources.get::<usize>().unwrap(), 1);
+
+        std::thread::spawn(move || signal.set()).join().unwrap();
+        schedule.execute(&mut world, &mut resources);
+        assert_eq!(*resources.get::<usize>().unwrap(), 11);
+        assert_eq!(world.len(), 1);
+
+        // a new future is started once the previous one has completed, and as the signal has
+        // already been set it completes within a single poll
+        schedule.execute(&mut world, &mut resources);
+        assert_eq!(*resources.get::<usize>().unwrap(), 22);
+    }
+
+    #[test]
+    #[should_panic(expected = "while being polled")]
+    fn access_outside_poll() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+
+        let escaped = Arc::new(Mutex::new(None));
+        let escape = escaped.clone();
+        let mut system = SystemBuilder::new("escape").build_async(move |context| {
+            *escape.lock() = Some(context);
+            async {}
+        });
+        system.run(&mut world, &mut resources);
+
+        let context = escaped.lock().take().unwrap();
+        context.with(|_, _, _, _| {});
+    }
+}
@@ -0,0 +1,639 @@
+//! Contains types related to inspecting the dependencies between systems in a schedule.
+//!
+//! A [DependencyGraph](struct.DependencyGraph.html) describes which systems must wait for which
+//! other systems, and why. It can be exported to Graphviz DOT or JSON for visualization.
+
+use super::{
+    profile::write_json_string,
+    resources::ResourceTypeId,
+    schedule::{display_name, must_run_before, Runnable},
+    system::SystemLabel,
+};
+use crate::internals::storage::component::ComponentTypeId;
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter, Write},
+};
+
+/// Tracks the systems which have accessed a type, so that later systems can find the systems
+/// they must wait for.
+#[derive(Default)]
+pub(crate) struct PreviousAccess {
+    readers: Vec<usize>,
+    last_writer: Option<usize>,
+}
+
+impl PreviousAccess {
+    pub(crate) fn add_read(&mut self, idx: usize) -> Option<usize> {
+        self.readers.push(idx);
+        self.last_writer
+    }
+
+    pub(crate) fn add_write(&mut self, idx: usize) -> Vec<usize> {
+        let mut dependencies = Vec::new();
+        std::mem::swap(&mut self.readers, &mut dependencies);
+        if let Some(writer) = self.last_writer.replace(idx) {
+            dependencies.push(writer)
+        }
+        dependencies
+    }
+}
+
+/// How two systems' accesses to the same type conflict, as (earlier, later) access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccessConflict {
+    /// The earlier system writes, and the later system reads.
+    WriteRead,
+    /// The earlier system reads, and the later system writes.
+    ReadWrite,
+    /// Both systems write.
+    WriteWrite,
+}
+
+impl Display for AccessConflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccessConflict::WriteRead => write!(f, "write/read"),
+            AccessConflict::ReadWrite => write!(f, "read/write"),
+            AccessConflict::WriteWrite => write!(f, "write/write"),
+        }
+    }
+}
+
+/// The cause of a dependency between two systems.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EdgeReason {
+    /// Both systems access a resource, and at least one writes to it.
+    Resource(ResourceTypeId, AccessConflict),
+    /// Both systems access a component, and at least one writes to it. The dependency is only
+    /// enforced during an execution if the systems access any of the same archetypes.
+    Component(ComponentTypeId, AccessConflict),
+    /// The systems' labels require that they run in order.
+    Ordering,
+}
+
+impl Display for EdgeReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EdgeReason::Resource(ty, conflict) => write!(f, "resource {} ({})", ty, conflict),
+            EdgeReason::Component(ty, conflict) => write!(f, "component {} ({})", ty, conflict),
+            EdgeReason::Ordering => write!(f, "ordering"),
+        }
+    }
+}
+
+/// A system within a dependency graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphNode {
+    /// The name of the system.
+    pub name: String,
+    /// The index of the schedule step which contains the system. Systems in different steps
+    /// never run concurrently.
+    pub step: usize,
+}
+
+/// A dependency between two systems in the same step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphEdge {
+    /// The index of the node which runs first.
+    pub from: usize,
+    /// The index of the node which waits for `from` to complete.
+    pub to: usize,
+    /// Why the dependency exists.
+    pub reasons: Vec<EdgeReason>,
+}
+
+impl GraphEdge {
+    /// Returns `true` if the dependency is only enforced when both systems access the same
+    /// archetypes during an execution.
+    pub fn is_dynamic(&self) -> bool {
+        self.reasons
+            .iter()
+            .all(|reason| matches!(reason, EdgeReason::Component(..)))
+    }
+}
+
+/// The dependencies between the systems in a schedule or executor.
+///
+/// # Examples
+///
+/// ```
+/// # use legion::*;
+/// struct Time;
+///
+/// let update_time = SystemBuilder::new("update_time")
+///     .write_resource::<Time>()
+///     .build(|_, _, _, _| {});
+/// let update_positions = SystemBuilder::new("update_positions")
+///     .read_resource::<Time>()
+///     .build(|_, _, _, _| {});
+///
+/// let schedule = Schedule::builder()
+///     .add_system(update_time)
+///     .add_system(update_positions)
+///     .build();
+///
+/// let graph = schedule.dependency_graph();
+/// let edge = graph.edge("update_time", "update_positions").unwrap();
+/// println!("{}", edge.reasons[0]);
+/// println!("{}", graph.to_dot());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencyGraph {
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+}
+
+impl DependencyGraph {
+    /// Gets the systems in the graph, in execution order.
+    pub fn nodes(&self) -> &[GraphNode] {
+        &self.nodes
+    }
+
+    /// Gets the dependencies between systems.
+    pub fn edges(&self) -> &[GraphEdge] {
+        &self.edges
+    }
+
+    /// Finds the dependency of the system named `to` upon the system named `from`, if any.
+    pub fn edge(&self, from: &str, to: &str) -> Option<&GraphEdge> {
+        self.edges
+            .iter()
+            .find(|edge| self.nodes[edge.from].name == from && self.nodes[edge.to].name == to)
+    }
+
+    /// Adds the systems of an executor, which will all be run in the given step.
+    pub(crate) fn add_systems<'a, I>(&mut self, step: usize, systems: I)
+    where
+        I: IntoIterator<Item = &'a dyn Runnable>,
+    {
+        let systems = systems.into_iter().collect::<Vec<_>>();
+        let base = self.nodes.len();
+        self.nodes.extend(systems.iter().map(|system| GraphNode {
+            name: display_name(*system),
+            step,
+        }));
+
+        let mut resource_accesses = HashMap::<ResourceTypeId, PreviousAccess>::default();
+        let mut component_accesses = HashMap::<ComponentTypeId, PreviousAccess>::default();
+        for (i, system) in systems.iter().enumerate() {
+            let mut reasons = HashMap::<usize, Vec<EdgeReason>>::default();
+            for (j, other) in systems[..i].iter().enumerate() {
+                if must_run_before(*other, *system) {
+                    reasons.entry(j).or_default().push(EdgeReason::Ordering);
+                }
+            }
+
+            let (read_res, read_comp) = system.reads();
+            let (write_res, write_comp) = system.writes();
+            for res in read_res {
+                let access = resource_accesses.entry(*res).or_default();
+                if let Some(dep) = access.add_read(i) {
+                    let reason = EdgeReason::Resource(*res, AccessConflict::WriteRead);
+                    reasons.entry(dep).or_default().push(reason);
+                }
+            }
+            for res in write_res {
+                let access = resource_accesses.entry(*res).or_default();
+                let writer = access.last_writer;
+                for dep in access.add_write(i) {
+                    let conflict = if Some(dep) == writer {
+                        AccessConflict::WriteWrite
+                    } else {
+                        AccessConflict::ReadWrite
+                    };
+                    let reason = EdgeReason::Resource(*res, conflict);
+                    reasons.entry(dep).or_default().push(reason);
+                }
+            }
+            for comp in read_comp {
+                let access = component_accesses.entry(*comp).or_default();
+                if let Some(dep) = access.add_read(i) {
+                    let reason = EdgeReason::Component(*comp, AccessConflict::WriteRead);
+                    reasons.entry(dep).or_default().push(reason);
+                }
+            }
+            for comp in write_comp {
+                let access = component_accesses.entry(*comp).or_default();
+                let writer = access.last_writer;
+                for dep in access.add_write(i) {
+                    let conflict = if Some(dep) == writer {
+                        AccessConflict::WriteWrite
+                    } else {
+                        AccessConflict::ReadWrite
+                    };
+                    let reason = EdgeReason::Component(*comp, conflict);
+                    reasons.entry(dep).or_default().push(reason);
+                }
+            }
+
+            // a system which reads and writes the same type depends upon itself
+            reasons.remove(&i);
+
+            let mut reasons = reasons.into_iter().collect::<Vec<_>>();
+            reasons.sort_by_key(|(dep, _)| *dep);
+            self.edges
+                .extend(reasons.into_iter().map(|(dep, reasons)| GraphEdge {
+                    from: base + dep,
+                    to: base + i,
+                    reasons,
+                }));
+        }
+    }
+
+    /// Formats the graph as a Graphviz DOT document.
+    ///
+    /// Systems are grouped into clusters by step. Dynamic dependencies are drawn dashed.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph schedule {\n");
+        let mut step = None;
+        for (i, node) in self.nodes.iter().enumerate() {
+            if step != Some(node.step) {
+                if step.is_some() {
+                    dot.push_str("    }\n");
+                }
+                step = Some(node.step);
+                writeln!(dot, "    subgraph cluster_{} {{", node.step).unwrap();
+                writeln!(dot, "        label = \"step {}\";", node.step).unwrap();
+            }
+            write!(dot, "        n{} [label = ", i).unwrap();
+            write_json_string(&mut dot, &node.name);
+            dot.push_str("];\n");
+        }
+        if step.is_some() {
+            dot.push_str("    }\n");
+        }
+
+        for edge in &self.edges {
+            write!(dot, "    n{} -> n{} [label = ", edge.from, edge.to).unwrap();
+            let reasons = edge
+                .reasons
+                .iter()
+                .map(|reason| reason.to_string())
+                .collect::<Vec<_>>();
+            write_json_string(&mut dot, &reasons.join(", "));
+            if edge.is_dynamic() {
+                dot.push_str(", style = dashed");
+            }
+            dot.push_str("];\n");
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Formats the graph as a JSON document with `nodes` and `edges` arrays.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\"nodes\":[");
+        for (i, node) in self.nodes.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str("{\"name\":");
+            write_json_string(&mut json, &node.name);
+            write!(json, ",\"step\":{}}}", node.step).unwrap();
+        }
+        json.push_str("],\"edges\":[");
+        for (i, edge) in self.edges.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            write!(
+                json,
+                "{{\"from\":{},\"to\":{},\"dynamic\":{},\"reasons\":[",
+                edge.from,
+                edge.to,
+                edge.is_dynamic()
+            )
+            .unwrap();
+            for (j, reason) in edge.reasons.iter().enumerate() {
+                if j > 0 {
+                    json.push(',');
+                }
+                write_json_string(&mut json, &reason.to_string());
+            }
+            json.push_str("]}");
+        }
+        json.push_str("]}");
+        json
+    }
+}
+
+/// A pair of systems in the same step whose accesses conflict, but whose relative order was not
+/// declared with labels.
+///
+/// The systems will run in the order in which they were added to the schedule, which may not
+/// have been intended. See `Schedule::ambiguities`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ambiguity {
+    /// The name of the system which was added first.
+    pub first: String,
+    /// The name of the system which was added second.
+    pub second: String,
+    /// The index of the schedule step which contains the systems.
+    pub step: usize,
+    /// The conflicting accesses.
+    pub conflicts: Vec<EdgeReason>,
+}
+
+impl Display for Ambiguity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let conflicts = self
+            .conflicts
+            .iter()
+            .map(|conflict| conflict.to_string())
+            .collect::<Vec<_>>();
+        write!(
+            f,
+            "`{}` and `{}` ({})",
+            self.first,
+            self.second,
+            conflicts.join(", ")
+        )
+    }
+}
+
+fn conflicts<T: Copy + PartialEq>(
+    (first_reads, first_writes): (&[T], &[T]),
+    (second_reads, second_writes): (&[T], &[T]),
+    reason: impl Fn(T, AccessConflict) -> EdgeReason,
+    conflicts: &mut Vec<EdgeReason>,
+) {
+    for ty in first_writes {
+        if second_writes.contains(ty) {
+            conflicts.push(reason(*ty, AccessConflict::WriteWrite));
+        } else if second_reads.contains(ty) {
+            conflicts.push(reason(*ty, AccessConflict::WriteRead));
+        }
+    }
+    for ty in first_reads {
+        if second_writes.contains(ty) {
+            conflicts.push(reason(*ty, AccessConflict::ReadWrite));
+        }
+    }
+}
+
+fn matches_label(system: &dyn Runnable, label: &SystemLabel) -> bool {
+    system.labels().contains(label)
+        || system.name().map(|name| name.to_string()) == Some(label.to_string())
+}
+
+/// Finds the ambiguously ordered pairs of systems within a step.
+pub(crate) fn find_ambiguities(
+    step: usize,
+    systems: &[&dyn Runnable],
+    allowed: &[&(SystemLabel, SystemLabel)],
+) -> Vec<Ambiguity> {
+    // find which systems are transitively ordered by their labels
+    let mut ordered = vec![vec![false; systems.len()]; systems.len()];
+    for (i, first) in systems.iter().enumerate() {
+        for (j, second) in systems.iter().enumerate() {
+            ordered[i][j] = must_run_before(*first, *second);
+        }
+    }
+    for k in 0..systems.len() {
+        for i in 0..systems.len() {
+            for j in 0..systems.len() {
+                ordered[i][j] = ordered[i][j] || (ordered[i][k] && ordered[k][j]);
+            }
+        }
+    }
+
+    let mut ambiguities = Vec::new();
+    for (i, first) in systems.iter().enumerate() {
+        for (j, second) in systems.iter().enumerate().skip(i + 1) {
+            if ordered[i][j] || ordered[j][i] {
+                continue;
+            }
+
+            let is_allowed = allowed.iter().any(|(a, b)| {
+                (matches_label(*first, a) && matches_label(*second, b))
+                    || (matches_label(*first, b) && matches_label(*second, a))
+            });
+            if is_allowed {
+                continue;
+            }
+
+            let (first_read_res, first_read_comp) = first.reads();
+            let (first_write_res, first_write_comp) = first.writes();
+            let (second_read_res, second_read_comp) = second.reads();
+            let (second_write_res, second_write_comp) = second.writes();
+
+            let mut reasons = Vec::new();
+            conflicts(
+                (first_read_res, first_write_res),
+                (second_read_res, second_write_res),
+                EdgeReason::Resource,
+                &mut reasons,
+            );
+            conflicts(
+                (first_read_comp, first_write_comp),
+                (second_read_comp, second_write_comp),
+                EdgeReason::Component,
+                &mut reasons,
+            );
+
+            if !reasons.is_empty() {
+                ambiguities.push(Ambiguity {
+                    first: display_name(*first),
+                    second: display_name(*second),
+                    step,
+                    conflicts: reasons,
+                });
+            }
+        }
+    }
+
+    ambiguities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internals::{
+        query::{
+            view::{read::Read, write::Write},
+            IntoQuery,
+        },
+        systems::{
+            schedule::{Schedule, ScheduleError},
+            system::SystemBuilder,
+        },
+    };
+
+    struct Time;
+    struct Position;
+    struct Velocity;
+
+    #[test]
+    fn edge_reasons() {
+        let update_time = SystemBuilder::new("update_time")
+            .write_resource::<Time>()
+            .build(|_, _, _, _| {});
+        let integrate = SystemBuilder::new("integrate")
+            .read_resource::<Time>()
+            .with_query(<(Write<Position>, Read<Velocity>)>::query())
+            .build(|_, _, _, _| {});
+        let accelerate = SystemBuilder::new("accelerate")
+            .label("accelerate")
+            .before("render")
+            .with_query(Write::<Velocity>::query())
+            .build(|_, _, _, _| {});
+        let render = SystemBuilder::new("render")
+            .label("render")
+            .build(|_, _, _, _| {});
+
+        let schedule = Schedule::builder()
+            .add_system(update_time)
+            .add_system(integrate)
+            .add_system(accelerate)
+            .flush()
+            .add_thread_local(render)
+            .build();
+
+        let graph = schedule.dependency_graph();
+        assert_eq!(
+            graph
+                .nodes()
+                .iter()
+                .map(|node| (node.name.as_str(), node.step))
+                .collect::<Vec<_>>(),
+            vec![
+                ("update_time", 0),
+                ("integrate", 0),
+                ("accelerate", 0),
+                ("render", 2)
+            ]
+        );
+
+        let time = graph.edge("update_time", "integrate").unwrap();
+        assert_eq!(
+            time.reasons,
+            vec![EdgeReason::Resource(
+                ResourceTypeId::of::<Time>(),
+                AccessConflict::WriteRead
+            )]
+        );
+        assert!(!time.is_dynamic());
+
+        let velocity = graph.edge("integrate", "accelerate").unwrap();
+        assert_eq!(
+            velocity.reasons,
+            vec![EdgeReason::Component(
+                ComponentTypeId::of::<Velocity>(),
+                AccessConflict::ReadWrite
+            )]
+        );
+        assert!(velocity.is_dynamic());
+
+        // systems in different steps never run concurrently, so have no edges
+        assert_eq!(graph.edges().len(), 2);
+        assert!(graph.edge("accelerate", "render").is_none());
+
+        let dot = graph.to_dot();
+        assert!(dot.contains("subgraph cluster_2"));
+        assert!(dot.contains("n1 -> n2"));
+        assert!(dot.contains("style = dashed"));
+
+        let json = graph.to_json();
+        assert!(json.starts_with("{\"nodes\":[{\"name\":\"update_time\",\"step\":0}"));
+        assert!(json.contains("{\"from\":1,\"to\":2,\"dynamic\":true,\"reasons\":[\"component "));
+    }
+
+    #[test]
+    fn ordering_edges() {
+        let first = SystemBuilder::new("first")
+            .label("first")
+            .build(|_, _, _, _| {});
+        let second = SystemBuilder::new("second")
+            .after("first")
+            .build(|_, _, _, _| {});
+
+        let schedule = Schedule::builder()
+            .add_system(second)
+            .add_system(first)
+            .build();
+
+        let graph = schedule.dependency_graph();
+        let edge = graph.edge("first", "second").unwrap();
+        assert_eq!(edge.reasons, vec![EdgeReason::Ordering]);
+        assert!(!edge.is_dynamic());
+    }
+
+    #[test]
+    fn ambiguities() {
+        let build = |allow: bool, deny: bool| {
+            let first = SystemBuilder::new("first")
+                .write_resource::<Time>()
+                .with_query(Read::<Position>::query())
+                .build(|_, _, _, _| {});
+            let second = SystemBuilder::new("second")
+                .label("movement")
+                .write_resource::<Time>()
+                .with_query(Write::<Position>::query())
+                .build(|_, _, _, _| {});
+            let third = SystemBuilder::new("third")
+                .after("movement")
+                .read_resource::<Time>()
+                .build(|_, _, _, _| {});
+            let fourth = SystemBuilder::new("fourth")
+                .read_resource::<Time>()
+                .build(|_, _, _, _| {});
+
+            let mut builder = Schedule::builder();
+            builder
+                .add_system(first)
+                .add_system(second)
+                .add_system(third)
+                .flush()
+                .add_system(fourth);
+            if allow {
+                builder.allow_ambiguity("first", "movement");
+            }
+            if deny {
+                builder.deny_ambiguities();
+            }
+            builder.try_build()
+        };
+
+        let ambiguities = build(false, false).unwrap().ambiguities();
+        assert_eq!(
+            ambiguities,
+            vec![
+                Ambiguity {
+                    first: "first".to_string(),
+                    second: "second".to_string(),
+                    step: 0,
+                    conflicts: vec![
+                        EdgeReason::Resource(
+                            ResourceTypeId::of::<Time>(),
+                            AccessConflict::WriteWrite
+                        ),
+                        EdgeReason::Component(
+                            ComponentTypeId::of::<Position>(),
+                            AccessConflict::ReadWrite
+                        ),
+                    ],
+                },
+                Ambiguity {
+                    first: "first".to_string(),
+                    second: "third".to_string(),
+                    step: 0,
+                    conflicts: vec![EdgeReason::Resource(
+                        ResourceTypeId::of::<Time>(),
+                        AccessConflict::WriteRead
+                    )],
+                },
+            ]
+        );
+
+        let ambiguities = build(true, false).unwrap().ambiguities();
+        assert_eq!(ambiguities.len(), 1);
+        assert_eq!(ambiguities[0].second, "third");
+
+        match build(true, true) {
+            Err(ScheduleError::Ambiguous(ambiguities)) => assert_eq!(ambiguities.len(), 1),
+            _ => panic!("expected ambiguity error"),
+        }
+    }
+}
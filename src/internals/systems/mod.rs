@@ -1,4 +1,9 @@
 pub mod command;
+pub mod events;
+pub mod future;
+pub mod graph;
+pub mod profile;
 pub mod resources;
 pub mod schedule;
 pub mod system;
+pub mod timestep;
@@ -0,0 +1,244 @@
+//! Contains types related to profiling schedule execution.
+//!
+//! Enable profiling with `Schedule::set_profiling`. Each execution of the schedule then inserts
+//! a [ScheduleProfile](struct.ScheduleProfile.html) resource describing how long each system and
+//! command buffer flush took, and on which thread it ran.
+
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    thread::ThreadId,
+    time::{Duration, Instant},
+};
+
+/// The kind of work recorded by a [ProfileSpan](struct.ProfileSpan.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpanKind {
+    /// A system run by an `Executor`.
+    System,
+    /// A thread local system or function.
+    ThreadLocal,
+    /// The flush of a system's command buffer.
+    Flush,
+}
+
+impl SpanKind {
+    fn category(self) -> &'static str {
+        match self {
+            SpanKind::System => "system",
+            SpanKind::ThreadLocal => "thread_local",
+            SpanKind::Flush => "flush",
+        }
+    }
+}
+
+/// A timed unit of work within a schedule execution.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileSpan {
+    /// The name of the system.
+    pub name: String,
+    /// The kind of work performed.
+    pub kind: SpanKind,
+    /// The thread on which the work was performed.
+    pub thread: ThreadId,
+    /// The time at which the work started, relative to the start of the execution.
+    pub start: Duration,
+    /// The time at which the work ended, relative to the start of the execution.
+    pub end: Duration,
+}
+
+impl ProfileSpan {
+    /// Gets the wall time taken by the work.
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+}
+
+/// A resource containing timings for the most recent execution of a profiled `Schedule`.
+///
+/// # Examples
+///
+/// ```
+/// # use legion::*;
+/// # use legion::systems::ScheduleProfile;
+/// # let update_positions = SystemBuilder::new("update_positions").build(|_,_,_,_| {});
+/// let mut world = World::default();
+/// let mut resources = Resources::default();
+/// let mut schedule = Schedule::builder().add_system(update_positions).build();
+/// schedule.set_profiling(true);
+///
+/// schedule.execute(&mut world, &mut resources);
+///
+/// let profile = resources.get::<ScheduleProfile>().unwrap();
+/// for span in profile.systems() {
+///     println!("{} took {:?}", span.name, span.duration());
+/// }
+/// let trace = profile.to_chrome_trace();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScheduleProfile {
+    duration: Duration,
+    spans: Vec<ProfileSpan>,
+}
+
+impl ScheduleProfile {
+    /// Gets the wall time taken by the whole execution.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Gets all recorded spans, ordered by start time.
+    pub fn spans(&self) -> &[ProfileSpan] {
+        &self.spans
+    }
+
+    /// Returns an iterator over the spans of all systems, including thread local systems.
+    pub fn systems(&self) -> impl Iterator<Item = &ProfileSpan> {
+        self.spans
+            .iter()
+            .filter(|span| span.kind != SpanKind::Flush)
+    }
+
+    /// Returns an iterator over the spans of all command buffer flushes.
+    pub fn flushes(&self) -> impl Iterator<Item = &ProfileSpan> {
+        self.spans
+            .iter()
+            .filter(|span| span.kind == SpanKind::Flush)
+    }
+
+    /// Gets the total time spent flushing command buffers.
+    pub fn flush_duration(&self) -> Duration {
+        self.flushes().map(|span| span.duration()).sum()
+    }
+
+    /// Formats the profile as a Chrome trace event JSON document, which can be loaded into
+    /// `chrome://tracing` or compatible viewers.
+    ///
+    /// Threads are numbered in order of their first appearance.
+    pub fn to_chrome_trace(&self) -> String {
+        let mut threads = HashMap::new();
+        let mut json = String::from("{\"traceEvents\":[");
+        for (i, span) in self.spans.iter().enumerate() {
+            let next_thread = threads.len();
+            let thread = *threads.entry(span.thread).or_insert(next_thread);
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str("{\"name\":");
+            write_json_string(&mut json, &span.name);
+            write!(
+                json,
+                ",\"cat\":\"{}\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":0,\"tid\":{}}}",
+                span.kind.category(),
+                span.start.as_secs_f64() * 1_000_000.0,
+                span.duration().as_secs_f64() * 1_000_000.0,
+                thread
+            )
+            .unwrap();
+        }
+        json.push_str("]}");
+        json
+    }
+}
+
+pub(crate) fn write_json_string(json: &mut String, value: &str) {
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+/// Records spans during a single schedule execution.
+pub(crate) struct Profiler {
+    start: Instant,
+    spans: Mutex<Vec<ProfileSpan>>,
+}
+
+impl Profiler {
+    pub(crate) fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            spans: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Starts timing a span, which is recorded when the returned timer is ended.
+    pub(crate) fn begin(&self) -> SpanTimer<'_> {
+        SpanTimer {
+            profiler: self,
+            start: self.start.elapsed(),
+        }
+    }
+
+    pub(crate) fn finish(self) -> ScheduleProfile {
+        let duration = self.start.elapsed();
+        let mut spans = self.spans.into_inner();
+        spans.sort_by_key(|span| span.start);
+        ScheduleProfile { duration, spans }
+    }
+}
+
+/// A span which is being timed.
+pub(crate) struct SpanTimer<'a> {
+    profiler: &'a Profiler,
+    start: Duration,
+}
+
+impl<'a> SpanTimer<'a> {
+    /// Stops timing and records the span.
+    pub(crate) fn end(self, kind: SpanKind, name: String) {
+        let end = self.profiler.start.elapsed();
+        self.profiler.spans.lock().push(ProfileSpan {
+            name,
+            kind,
+            thread: std::thread::current().id(),
+            start: self.start,
+            end,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chrome_trace() {
+        let thread = std::thread::current().id();
+        let profile = ScheduleProfile {
+            duration: Duration::from_micros(30),
+            spans: vec![
+                ProfileSpan {
+                    name: "mov\"e".to_string(),
+                    kind: SpanKind::System,
+                    thread,
+                    start: Duration::from_micros(0),
+                    end: Duration::from_micros(10),
+                },
+                ProfileSpan {
+                    name: "move".to_string(),
+                    kind: SpanKind::Flush,
+                    thread,
+                    start: Duration::from_micros(10),
+                    end: Duration::from_micros(15),
+                },
+            ],
+        };
+
+        assert_eq!(profile.flush_duration(), Duration::from_micros(5));
+        assert_eq!(
+            profile.to_chrome_trace(),
+            "{\"traceEvents\":[\
+            {\"name\":\"mov\\\"e\",\"cat\":\"system\",\"ph\":\"X\",\"ts\":0.000,\"dur\":10.000,\"pid\":0,\"tid\":0},\
+            {\"name\":\"move\",\"cat\":\"flush\",\"ph\":\"X\",\"ts\":10.000,\"dur\":5.000,\"pid\":0,\"tid\":0}\
+            ]}"
+        );
+    }
+}
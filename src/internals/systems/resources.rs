@@ -7,6 +7,7 @@ use crate::internals::{
     hash::ComponentTypeIdHasher,
     query::view::{read::Read, write::Write, ReadOnly},
 };
+use derivative::Derivative;
 use downcast_rs::{impl_downcast, Downcast};
 use std::{
     any::TypeId,
@@ -368,6 +369,10 @@ impl UnsafeResources {
         self.map.get(type_id)
     }
 
+    pub(crate) fn sync(&self) -> SyncResources<'_> {
+        SyncResources { internal: self }
+    }
+
     /// # Safety
     /// Resources which are `!Sync` must be retrieved or inserted only on the main thread.
     unsafe fn merge(&mut self, mut other: Self) {
@@ -378,6 +383,49 @@ impl UnsafeResources {
     }
 }
 
+/// Accesses a resource which is not `Send` or `Sync` (such as a window handle or graphics
+/// context) for reading.
+///
+/// Systems which access non-send resources are always run on the thread which owns the
+/// `Resources`, i.e. the thread which executes the schedule.
+/// See [SystemBuilder::read_non_send_resource](../struct.SystemBuilder.html#method.read_non_send_resource).
+#[derive(Derivative, Debug, Copy, Clone)]
+#[derivative(Default(bound = ""))]
+pub struct NonSend<T>(PhantomData<*const T>);
+
+// safety: the marker only contains type information; the executor ensures that the
+// resource itself is only accessed from the thread which owns the resources
+unsafe impl<T> Send for NonSend<T> {}
+unsafe impl<T> Sync for NonSend<T> {}
+unsafe impl<T> ReadOnly for NonSend<T> {}
+
+impl<'a, T: Resource> ResourceSet<'a> for NonSend<T> {
+    type Result = Fetch<'a, T>;
+
+    unsafe fn fetch_unchecked(resources: &'a UnsafeResources) -> Self::Result {
+        Read::<T>::fetch_unchecked(resources)
+    }
+}
+
+/// Accesses a resource which is not `Send` or `Sync` for writing.
+///
+/// See [NonSend](struct.NonSend.html).
+#[derive(Derivative, Debug, Copy, Clone)]
+#[derivative(Default(bound = ""))]
+pub struct NonSendMut<T>(PhantomData<*const T>);
+
+// safety: see `NonSend`
+unsafe impl<T> Send for NonSendMut<T> {}
+unsafe impl<T> Sync for NonSendMut<T> {}
+
+impl<'a, T: Resource> ResourceSet<'a> for NonSendMut<T> {
+    type Result = FetchMut<'a, T>;
+
+    unsafe fn fetch_unchecked(resources: &'a UnsafeResources) -> Self::Result {
+        Write::<T>::fetch_unchecked(resources)
+    }
+}
+
 /// Resources container. Shared resources stored here can be retrieved in systems.
 #[derive(Default)]
 pub struct Resources {
@@ -2,10 +2,13 @@
 
 use std::cell::UnsafeCell;
 
+#[cfg(feature = "parallel")]
+use parking_lot::{Condvar, Mutex};
+
 #[cfg(feature = "parallel")]
 use std::{
     collections::{HashMap, HashSet},
-    sync::atomic::{AtomicUsize, Ordering},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
 };
 
 #[cfg(feature = "parallel")]
@@ -14,19 +17,30 @@ use rayon::prelude::*;
 #[cfg(feature = "parallel")]
 use itertools::izip;
 
+#[cfg(feature = "parallel")]
+use super::graph::PreviousAccess;
+
 use super::{
     command::CommandBuffer,
-    resources::{ResourceTypeId, Resources, UnsafeResources},
-    system::SystemId,
+    graph::{find_ambiguities, Ambiguity, DependencyGraph},
+    profile::{Profiler, SpanKind},
+    resources::{ResourceTypeId, Resources, SyncResources, UnsafeResources},
+    system::{SystemId, SystemLabel},
+    timestep::FixedTimestep,
 };
 use crate::internals::{
     storage::component::ComponentTypeId,
     subworld::ArchetypeAccess,
     world::{World, WorldId},
 };
-use std::fmt::{Debug, Formatter};
 #[cfg(feature = "parallel")]
 use std::iter::repeat;
+use std::{
+    borrow::Cow,
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fmt::{Debug, Formatter},
+};
 
 /// A `Runnable` which is also `Send` and `Sync`.
 pub trait ParallelRunnable: Runnable + Send + Sync {}
@@ -44,6 +58,27 @@ pub trait Runnable {
     /// Gets the name of the system.
     fn name(&self) -> Option<&SystemId>;
 
+    /// Gets the labels attached to the system.
+    fn labels(&self) -> &[SystemLabel] {
+        &[]
+    }
+
+    /// Gets the labels of the systems which this system must run before.
+    fn before(&self) -> &[SystemLabel] {
+        &[]
+    }
+
+    /// Gets the labels of the systems which this system must run after.
+    fn after(&self) -> &[SystemLabel] {
+        &[]
+    }
+
+    /// Returns `true` if the system accesses resources which are not `Send` or `Sync`, and so
+    /// must run on the thread which owns the `Resources`.
+    fn requires_main_thread(&self) -> bool {
+        false
+    }
+
     /// Gets the resources and component types read by the system.
     fn reads(&self) -> (&[ResourceTypeId], &[ComponentTypeId]);
 
@@ -51,6 +86,8 @@ pub trait Runnable {
     fn writes(&self) -> (&[ResourceTypeId], &[ComponentTypeId]);
 
     /// Prepares the system for execution against a world.
+    ///
+    /// Schedules prepare their systems in order, before running any of them.
     fn prepare(&mut self, world: &World);
 
     /// Gets the set of archetypes the system will access when run,
@@ -83,6 +120,108 @@ impl Debug for dyn Runnable {
     }
 }
 
+/// Returns `true` if `first` is required to run before `second` by their ordering constraints.
+pub(crate) fn must_run_before<A, B>(first: &A, second: &B) -> bool
+where
+    A: Runnable + ?Sized,
+    B: Runnable + ?Sized,
+{
+    first.before().iter().any(|l| second.labels().contains(l))
+        || second.after().iter().any(|l| first.labels().contains(l))
+}
+
+pub(crate) fn display_name<R: Runnable + ?Sized>(system: &R) -> String {
+    system
+        .name()
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| "<unnamed>".to_string())
+}
+
+fn flush_system<R: Runnable + ?Sized>(
+    system: &mut R,
+    world: &mut World,
+    resources: &mut Resources,
+    profiler: Option<&Profiler>,
+) {
+    let name = profiler.map(|_| display_name(system));
+    if let Some(cmd) = system.command_buffer_mut(world.id()) {
+        let timer = profiler.map(Profiler::begin);
+        cmd.flush(world, resources);
+        if let (Some(timer), Some(name)) = (timer, name) {
+            timer.end(SpanKind::Flush, name);
+        }
+    }
+}
+
+// Visits the systems of each step, numbering steps in execution order including the steps of
+// nested schedules.
+fn visit_systems<'a>(
+    steps: &'a [Step],
+    next: &mut usize,
+    visit: &mut dyn FnMut(usize, Vec<&'a dyn Runnable>),
+) {
+    for step in steps {
+        let i = *next;
+        *next += 1;
+        match step {
+            Step::Systems(executor) => {
+                visit(i, executor.systems().map(|s| s as &dyn Runnable).collect())
+            }
+            Step::ThreadLocalSystem(system) => visit(i, vec![system.as_ref()]),
+            Step::Schedule(schedule) => visit_systems(&schedule.steps, next, visit),
+            Step::FixedTimestep(timestep) => visit_systems(&timestep.schedule().steps, next, visit),
+            Step::FlushCmdBuffers | Step::ThreadLocalFn(_) => {}
+        }
+    }
+}
+
+/// Errors which may occur when building a `Schedule`.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleError {
+    /// The ordering constraints between the named systems form a cycle.
+    #[error("systems have cyclic ordering constraints: {}", .0.join(", "))]
+    Cycle(Vec<String>),
+    /// A system is required to run before another system which is placed in an earlier step
+    /// of the schedule, on the other side of a flush or thread local step.
+    #[error("system `{before}` must run before `{after}`, but is in a later step of the schedule")]
+    Unsatisfiable {
+        /// The name of the system which must run first.
+        before: String,
+        /// The name of the system which must run second.
+        after: String,
+    },
+    /// The schedule contains ambiguously ordered systems, and was built with
+    /// `Builder::deny_ambiguities`.
+    #[error("schedule contains ambiguously ordered systems: {}", .0.iter().map(|a| a.to_string()).collect::<Vec<_>>().join("; "))]
+    Ambiguous(Vec<Ambiguity>),
+}
+
+/// A predicate which determines whether a system will run during an execution of its schedule.
+///
+/// Conditions are evaluated by the `Executor` immediately before it begins running its systems,
+/// and so observe the state of resources as it was at the start of the executor's step.
+pub struct RunCondition(Box<dyn FnMut(&SyncResources) -> bool + Send + Sync>);
+
+impl RunCondition {
+    /// Constructs a new run condition.
+    pub fn new<F: FnMut(&SyncResources) -> bool + Send + Sync + 'static>(condition: F) -> Self {
+        Self(Box::new(condition))
+    }
+
+    /// Evaluates the condition. Returns `true` if the system should run.
+    pub fn evaluate(&mut self, resources: &SyncResources) -> bool {
+        (self.0)(resources)
+    }
+}
+
+impl Debug for RunCondition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.debug_tuple("RunCondition")
+            .field(&"dyn FnMut(&SyncResources) -> bool")
+            .finish()
+    }
+}
+
 /// Executes a sequence of systems, potentially in parallel, and then commits their command buffers.
 ///
 /// Systems are provided in execution order. When the `parallel` feature is enabled, the `Executor`
@@ -91,6 +230,8 @@ impl Debug for dyn Runnable {
 #[derive(Debug)]
 pub struct Executor {
     systems: Vec<SystemBox>,
+    conditions: Vec<Option<RunCondition>>,
+    skipped: Vec<bool>,
     #[cfg(feature = "parallel")]
     static_dependants: Vec<Vec<usize>>,
     #[cfg(feature = "parallel")]
@@ -99,6 +240,8 @@ pub struct Executor {
     static_dependency_counts: Vec<AtomicUsize>,
     #[cfg(feature = "parallel")]
     awaiting: Vec<AtomicUsize>,
+    #[cfg(feature = "parallel")]
+    main_thread: Vec<bool>,
 }
 
 struct SystemBox(UnsafeCell<Box<dyn ParallelRunnable>>);
@@ -135,6 +278,8 @@ impl Executor {
     #[cfg(not(feature = "parallel"))]
     pub fn new(systems: Vec<Box<dyn ParallelRunnable>>) -> Self {
         Self {
+            conditions: systems.iter().map(|_| None).collect(),
+            skipped: vec![false; systems.len()],
             systems: systems
                 .into_iter()
                 .map(|s| SystemBox(UnsafeCell::new(s)))
@@ -145,7 +290,9 @@ impl Executor {
     /// Constructs a new executor for all systems to be run in a single stage.
     ///
     /// Systems are provided in the order in which side-effects (e.g. writes to resources or entities)
-    /// are to be observed.
+    /// are to be observed. Systems which are labelled to run before a later system are always
+    /// completed before the later system starts; constraints which contradict the provided order
+    /// are ignored.
     #[cfg(feature = "parallel")]
     #[allow(clippy::cognitive_complexity)]
     // TODO: we should break this up
@@ -158,28 +305,6 @@ impl Executor {
             let mut dynamic_dependants: Vec<Vec<_>> =
                 repeat(Vec::with_capacity(64)).take(systems.len()).collect();
 
-            #[derive(Default)]
-            struct PreviousAccess {
-                readers: Vec<usize>,
-                last_writer: Option<usize>,
-            }
-
-            impl PreviousAccess {
-                fn add_read(&mut self, idx: usize) -> Option<usize> {
-                    self.readers.push(idx);
-                    self.last_writer
-                }
-
-                fn add_write(&mut self, idx: usize) -> Vec<usize> {
-                    let mut dependencies = Vec::new();
-                    std::mem::swap(&mut self.readers, &mut dependencies);
-                    if let Some(writer) = self.last_writer.replace(idx) {
-                        dependencies.push(writer)
-                    }
-                    dependencies
-                }
-            }
-
             let mut resource_accesses =
                 HashMap::<ResourceTypeId, PreviousAccess>::with_capacity_and_hasher(
                     64,
@@ -195,8 +320,15 @@ impl Executor {
                 let (read_res, read_comp) = system.reads();
                 let (write_res, write_comp) = system.writes();
 
-                // find resource access dependencies
+                // find explicit ordering dependencies
                 let mut dependencies = HashSet::with_capacity(64);
+                for (j, other) in systems[..i].iter().enumerate() {
+                    if must_run_before(other.as_ref(), system.as_ref()) {
+                        dependencies.insert(j);
+                    }
+                }
+
+                // find resource access dependencies
                 for res in read_res {
                     let access = resource_accesses.entry(*res).or_default();
                     if let Some(dep) = access.add_read(i) {
@@ -250,9 +382,12 @@ impl Executor {
 
             Executor {
                 awaiting,
+                main_thread: systems.iter().map(|s| s.requires_main_thread()).collect(),
                 static_dependants,
                 dynamic_dependants,
                 static_dependency_counts,
+                conditions: systems.iter().map(|_| None).collect(),
+                skipped: vec![false; systems.len()],
                 systems: systems
                     .into_iter()
                     .map(|s| SystemBox(UnsafeCell::new(s)))
@@ -261,9 +396,12 @@ impl Executor {
         } else {
             Executor {
                 awaiting: Vec::with_capacity(0),
+                main_thread: systems.iter().map(|s| s.requires_main_thread()).collect(),
                 static_dependants: Vec::with_capacity(0),
                 dynamic_dependants: Vec::with_capacity(0),
                 static_dependency_counts: Vec::with_capacity(0),
+                conditions: systems.iter().map(|_| None).collect(),
+                skipped: vec![false; systems.len()],
                 systems: systems
                     .into_iter()
                     .map(|s| SystemBox(UnsafeCell::new(s)))
@@ -272,6 +410,35 @@ impl Executor {
         }
     }
 
+    /// Sets a condition which must pass for the system at the given index to run.
+    ///
+    /// Systems which are skipped do not block the execution of systems which depend upon them.
+    pub fn set_condition(&mut self, system: usize, condition: RunCondition) {
+        self.conditions[system] = Some(condition);
+    }
+
+    fn evaluate_conditions(&mut self, resources: &UnsafeResources) {
+        let resources = resources.sync();
+        for (condition, skipped) in self.conditions.iter_mut().zip(self.skipped.iter_mut()) {
+            *skipped = match condition {
+                Some(condition) => !condition.evaluate(&resources),
+                None => false,
+            };
+        }
+    }
+
+    fn systems(&self) -> impl Iterator<Item = &dyn ParallelRunnable> {
+        // safety: we have &self access, and systems are only mutably accessed via &mut self
+        self.systems.iter().map(|s| unsafe { s.get() })
+    }
+
+    /// Computes the dependencies between the executor's systems, and the reasons for them.
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        let mut graph = DependencyGraph::default();
+        graph.add_systems(0, self.systems().map(|system| system as &dyn Runnable));
+        graph
+    }
+
     /// Converts this executor into a vector of its component systems.
     pub fn into_vec(self) -> Vec<Box<dyn ParallelRunnable>> {
         self.systems.into_iter().map(|s| s.0.into_inner()).collect()
@@ -289,20 +456,50 @@ impl Executor {
     #[cfg(feature = "parallel")]
     pub fn execute(&mut self, world: &mut World, resources: &mut Resources) {
         let unsafe_resources = resources.internal();
-        rayon::join(|| self.run_systems(world, unsafe_resources), || {});
+        if self.requires_main_thread() {
+            self.run_systems(world, unsafe_resources);
+        } else {
+            rayon::join(|| self.run_systems(world, unsafe_resources), || {});
+        }
         self.flush_command_buffers(world, resources);
     }
 
+    /// Returns `true` if any of the executor's systems must run on the thread which owns the
+    /// `Resources`.
+    #[cfg(feature = "parallel")]
+    pub fn requires_main_thread(&self) -> bool {
+        self.main_thread.iter().any(|main_thread| *main_thread)
+    }
+
     /// Executes all systems sequentially.
     ///
     /// Only enabled with parallel is disabled
     #[cfg(not(feature = "parallel"))]
     pub fn run_systems(&mut self, world: &mut World, resources: &UnsafeResources) {
-        self.systems.iter_mut().for_each(|system| {
-            let system = unsafe { system.get_mut() };
-            system.prepare(world);
-            unsafe { system.run_unsafe(world, resources) };
-        });
+        self.run_systems_profiled(world, resources, None);
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    pub(crate) fn run_systems_profiled(
+        &mut self,
+        world: &mut World,
+        resources: &UnsafeResources,
+        profiler: Option<&Profiler>,
+    ) {
+        self.evaluate_conditions(resources);
+        self.systems
+            .iter_mut()
+            .zip(self.skipped.iter())
+            .filter(|(_, skipped)| !**skipped)
+            .for_each(|(system, _)| {
+                let system = unsafe { system.get_mut() };
+                system.prepare(world);
+                let timer = profiler.map(Profiler::begin);
+                unsafe { system.run_unsafe(world, resources) };
+                if let Some(timer) = timer {
+                    timer.end(SpanKind::System, display_name(system));
+                }
+            });
     }
 
     /// Executes all systems, potentially in parallel.
@@ -311,39 +508,70 @@ impl Executor {
     /// accesses is maintained.
     ///
     /// Call from within `rayon::ThreadPool::install()` to execute within a specific thread pool.
+    ///
+    /// Systems which [require the main thread](#method.requires_main_thread) are run on the
+    /// calling thread, which must be the thread which owns the `Resources`. If the calling thread
+    /// is itself a rayon worker thread, such as within `ThreadPool::install()`, it cannot wait for
+    /// the other systems without risking a deadlock, and all systems are run sequentially on it.
     #[cfg(feature = "parallel")]
     pub fn run_systems(&mut self, world: &mut World, resources: &UnsafeResources) {
+        self.run_systems_profiled(world, resources, None, None);
+    }
+
+    #[cfg(feature = "parallel")]
+    pub(crate) fn run_systems_profiled(
+        &mut self,
+        world: &mut World,
+        resources: &UnsafeResources,
+        profiler: Option<&Profiler>,
+        pool: Option<&rayon::ThreadPool>,
+    ) {
+        self.evaluate_conditions(resources);
         match self.systems.len() {
+            1 if self.skipped[0] => {}
             1 => {
                 // safety: we have exlusive access to all systems, world and resources here
                 unsafe {
                     let system = self.systems[0].get_mut();
                     system.prepare(world);
+                    let timer = profiler.map(Profiler::begin);
                     system.run_unsafe(world, resources);
+                    if let Some(timer) = timer {
+                        timer.end(SpanKind::System, display_name(system));
+                    }
                 };
             }
             _ => {
+                let requires_main_thread = self.requires_main_thread();
                 let systems = &mut self.systems;
+                let skipped = &self.skipped;
                 let static_dependency_counts = &self.static_dependency_counts;
                 let awaiting = &mut self.awaiting;
 
-                // prepare all systems - archetype filters are pre-executed here
-                systems
-                    .par_iter_mut()
-                    .for_each(|sys| unsafe { sys.get_mut() }.prepare(world));
+                // prepare all systems which will run - archetype filters are pre-executed here
+                // systems are prepared in order, as they may allocate from the world
+                for (sys, _) in systems.iter_mut().zip(skipped).filter(|(_, s)| !**s) {
+                    unsafe { sys.get_mut() }.prepare(world);
+                }
 
                 // determine dynamic dependencies
                 izip!(
                     systems.iter(),
+                    skipped.iter(),
                     self.static_dependants.iter_mut(),
                     self.dynamic_dependants.iter_mut()
                 )
                 .par_bridge()
-                .for_each(|(sys, static_dep, dyn_dep)| {
+                .filter(|(_, skipped, _, _)| !**skipped)
+                .for_each(|(sys, _, static_dep, dyn_dep)| {
                     // safety: systems is held exclusively, and we are only reading each system
                     let archetypes = unsafe { sys.get() }.accesses_archetypes();
                     for i in (0..dyn_dep.len()).rev() {
                         let dep = dyn_dep[i];
+                        // skipped systems were not prepared and do not access any archetypes
+                        if skipped[dep] {
+                            continue;
+                        }
                         let other = unsafe { systems[dep].get() };
 
                         // if the archetype sets intersect,
@@ -363,15 +591,37 @@ impl Executor {
 
                 let awaiting = &self.awaiting;
 
-                // execute all systems with no outstanding dependencies
-                (0..systems.len())
-                    .into_par_iter()
-                    .filter(|i| static_dependency_counts[*i].load(Ordering::SeqCst) == 0)
-                    .for_each(|i| {
-                        // safety: we are at the root of the execution tree, so we know each
-                        // index is exclusive here
-                        unsafe { self.run_recursive(i, world, resources) };
-                    });
+                if requires_main_thread && rayon::current_thread_index().is_some() {
+                    // blocking a worker thread while it waits for systems to become ready could
+                    // deadlock its pool, so run every system in order on this thread instead
+                    for i in 0..self.systems.len() {
+                        // safety: each system is run exactly once
+                        unsafe { self.run_system(i, world, resources, profiler) };
+                        for dep in &self.static_dependants[i] {
+                            awaiting[*dep].fetch_sub(1, Ordering::Relaxed);
+                        }
+                    }
+                } else if requires_main_thread {
+                    let queue = MainThreadQueue::default();
+                    match pool {
+                        Some(pool) => pool.in_place_scope(|scope| {
+                            self.run_on_main_thread(scope, &queue, world, resources, profiler)
+                        }),
+                        None => rayon::in_place_scope(|scope| {
+                            self.run_on_main_thread(scope, &queue, world, resources, profiler)
+                        }),
+                    }
+                } else {
+                    // execute all systems with no outstanding dependencies
+                    (0..systems.len())
+                        .into_par_iter()
+                        .filter(|i| static_dependency_counts[*i].load(Ordering::SeqCst) == 0)
+                        .for_each(|i| {
+                            // safety: we are at the root of the execution tree, so we know each
+                            // index is exclusive here
+                            unsafe { self.run_recursive(i, world, resources, profiler, None) };
+                        });
+                }
 
                 debug_assert!(
                     awaiting.iter().all(|x| x.load(Ordering::SeqCst) == 0),
@@ -384,12 +634,19 @@ impl Executor {
 
     /// Flushes the recorded command buffers for all systems.
     pub fn flush_command_buffers(&mut self, world: &mut World, resources: &mut Resources) {
+        self.flush_command_buffers_profiled(world, resources, None);
+    }
+
+    pub(crate) fn flush_command_buffers_profiled(
+        &mut self,
+        world: &mut World,
+        resources: &mut Resources,
+        profiler: Option<&Profiler>,
+    ) {
         self.systems.iter().for_each(|system| {
             // safety: systems are exlcusive due to &mut self
             let system = unsafe { system.get_mut() };
-            if let Some(cmd) = system.command_buffer_mut(world.id()) {
-                cmd.flush(world, resources);
-            }
+            flush_system(system, world, resources, profiler);
         });
     }
 
@@ -399,29 +656,249 @@ impl Executor {
     ///
     /// Ensure the system indexed by `i` is only accessed once.
     #[cfg(feature = "parallel")]
-    unsafe fn run_recursive(&self, i: usize, world: &World, resources: &UnsafeResources) {
-        // safety: the caller ensures nothing else is accessing systems[i]
-        self.systems[i].get_mut().run_unsafe(world, resources);
+    unsafe fn run_recursive(
+        &self,
+        i: usize,
+        world: &World,
+        resources: &UnsafeResources,
+        profiler: Option<&Profiler>,
+        main_thread: Option<&MainThreadQueue>,
+    ) {
+        self.run_system(i, world, resources, profiler);
 
         self.static_dependants[i].par_iter().for_each(|dep| {
             if self.awaiting[*dep].fetch_sub(1, Ordering::Relaxed) == 1 {
-                // safety: each dependency is unique, so run_recursive is safe to call
-                self.run_recursive(*dep, world, resources);
+                match main_thread {
+                    Some(queue) if self.main_thread[*dep] => queue.push(*dep),
+                    // safety: each dependency is unique, so run_recursive is safe to call
+                    _ => self.run_recursive(*dep, world, resources, profiler, main_thread),
+                }
             }
         });
     }
+
+    /// Runs the system indexed by `i`, unless it has been skipped.
+    ///
+    /// # Safety
+    ///
+    /// Ensure the system indexed by `i` is only accessed once.
+    #[cfg(feature = "parallel")]
+    unsafe fn run_system(
+        &self,
+        i: usize,
+        world: &World,
+        resources: &UnsafeResources,
+        profiler: Option<&Profiler>,
+    ) {
+        // skipped systems still unblock their dependants
+        if !self.skipped[i] {
+            // safety: the caller ensures nothing else is accessing systems[i]
+            let system = self.systems[i].get_mut();
+            let timer = profiler.map(Profiler::begin);
+            system.run_unsafe(world, resources);
+            if let Some(timer) = timer {
+                timer.end(SpanKind::System, display_name(system));
+            }
+        }
+    }
+
+    /// Executes the dependency cascade, running systems which require the main thread on the
+    /// calling thread and all other systems within the scope.
+    #[cfg(feature = "parallel")]
+    fn run_on_main_thread<'s>(
+        &'s self,
+        scope: &rayon::Scope<'s>,
+        queue: &'s MainThreadQueue,
+        world: &'s World,
+        resources: &'s UnsafeResources,
+        profiler: Option<&'s Profiler>,
+    ) {
+        let dispatch = |i: usize| {
+            if self.main_thread[i] {
+                queue.push(i);
+            } else {
+                scope.spawn(move |_| {
+                    let _guard = queue.abort_on_panic();
+                    // safety: each system is dispatched exactly once
+                    unsafe { self.run_recursive(i, world, resources, profiler, Some(queue)) };
+                });
+            }
+        };
+
+        // execute all systems with no outstanding dependencies
+        for i in 0..self.systems.len() {
+            if self.static_dependency_counts[i].load(Ordering::SeqCst) == 0 {
+                dispatch(i);
+            }
+        }
+
+        // every system eventually becomes ready exactly once, including skipped systems
+        for _ in self.main_thread.iter().filter(|main_thread| **main_thread) {
+            let i = match queue.pop() {
+                Some(i) => i,
+                // a system panicked; the scope will resume the panic
+                None => return,
+            };
+
+            // safety: each system is dispatched exactly once
+            unsafe { self.run_system(i, world, resources, profiler) };
+            for dep in &self.static_dependants[i] {
+                if self.awaiting[*dep].fetch_sub(1, Ordering::Relaxed) == 1 {
+                    dispatch(*dep);
+                }
+            }
+        }
+    }
+}
+
+/// Systems which are ready to run on the thread which owns the `Resources`.
+#[cfg(feature = "parallel")]
+#[derive(Default)]
+struct MainThreadQueue {
+    ready: Mutex<Vec<usize>>,
+    aborted: AtomicBool,
+    condvar: Condvar,
+}
+
+#[cfg(feature = "parallel")]
+impl MainThreadQueue {
+    fn push(&self, i: usize) {
+        self.ready.lock().push(i);
+        self.condvar.notify_one();
+    }
+
+    /// Waits for the next ready system. Returns `None` if a system panicked on another thread.
+    fn pop(&self) -> Option<usize> {
+        let mut ready = self.ready.lock();
+        loop {
+            if self.aborted.load(Ordering::SeqCst) {
+                return None;
+            }
+            if let Some(i) = ready.pop() {
+                return Some(i);
+            }
+            self.condvar.wait(&mut ready);
+        }
+    }
+
+    /// Returns a guard which wakes the main thread if it is dropped during a panic, as systems
+    /// which depend upon the panicking system will never become ready.
+    fn abort_on_panic(&self) -> AbortOnPanic<'_> {
+        AbortOnPanic(self)
+    }
+}
+
+#[cfg(feature = "parallel")]
+struct AbortOnPanic<'a>(&'a MainThreadQueue);
+
+#[cfg(feature = "parallel")]
+impl<'a> Drop for AbortOnPanic<'a> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            let _ready = self.0.ready.lock();
+            self.0.aborted.store(true, Ordering::SeqCst);
+            self.0.condvar.notify_one();
+        }
+    }
+}
+
+/// The name of a stage within a schedule.
+///
+/// See [Builder::add_stage](struct.Builder.html#method.add_stage).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StageLabel(Cow<'static, str>);
+
+impl std::fmt::Display for StageLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<T: Into<Cow<'static, str>>> From<T> for StageLabel {
+    fn from(name: T) -> StageLabel {
+        StageLabel(name.into())
+    }
+}
+
+enum BuilderStep {
+    Step(Step),
+    Stage(StageLabel, Builder),
 }
 
 /// A factory for `Schedule`.
+///
+/// Systems added between flushes and thread local steps are sorted according to their
+/// [labels](../struct.SystemBuilder.html#method.label) when the schedule is built. Systems without
+/// ordering constraints retain the order in which they were added.
+///
+/// A schedule can be divided into named stages, which are each built with their own `Builder`.
+/// Systems can be added to a stage at any time before the schedule is built, which allows
+/// independent modules to contribute systems to a shared schedule.
+///
+/// # Examples
+///
+/// ```
+/// # use legion::*;
+/// # let handle_input = SystemBuilder::new("handle_input").build(|_,_,_,_| {});
+/// # let update_positions = SystemBuilder::new("update_positions").build(|_,_,_,_| {});
+/// let mut builder = Schedule::builder();
+/// builder
+///     .add_stage("pre_update")
+///     .add_stage("update")
+///     .add_stage("post_update");
+///
+/// // stages can be filled in any order
+/// builder.add_system_to_stage("update", update_positions);
+/// builder.add_system_to_stage("pre_update", handle_input);
+///
+/// let schedule = builder.build();
+/// ```
 pub struct Builder {
-    steps: Vec<Step>,
+    steps: Vec<BuilderStep>,
     accumulator: Vec<Box<dyn ParallelRunnable>>,
+    conditions: Vec<Option<RunCondition>>,
+    errors: Vec<ScheduleError>,
+    allowed_ambiguities: Vec<(SystemLabel, SystemLabel)>,
+    deny_ambiguities: bool,
 }
 
 impl Builder {
     /// Adds a system to the schedule.
     pub fn add_system<T: ParallelRunnable + 'static>(&mut self, system: T) -> &mut Self {
         self.accumulator.push(Box::new(system));
+        self.conditions.push(None);
+        self
+    }
+
+    /// Adds a system to the schedule which will only run when the given condition passes.
+    ///
+    /// The condition is evaluated each time the schedule is executed, before any of the systems
+    /// between the surrounding flushes or thread local steps begin to run.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # let update_positions = SystemBuilder::new("update_positions").build(|_,_,_,_| {});
+    /// #[derive(PartialEq)]
+    /// enum GameState {
+    ///     Playing,
+    ///     Paused,
+    /// }
+    ///
+    /// let mut schedule = Schedule::builder()
+    ///     .add_system_with_condition(update_positions, |resources| {
+    ///         *resources.get::<GameState>().unwrap() == GameState::Playing
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn add_system_with_condition<T, F>(&mut self, system: T, condition: F) -> &mut Self
+    where
+        T: ParallelRunnable + 'static,
+        F: FnMut(&SyncResources) -> bool + Send + Sync + 'static,
+    {
+        self.accumulator.push(Box::new(system));
+        self.conditions.push(Some(RunCondition::new(condition)));
         self
     }
 
@@ -429,17 +906,106 @@ impl Builder {
     /// command buffers.
     pub fn flush(&mut self) -> &mut Self {
         self.finalize_executor();
-        self.steps.push(Step::FlushCmdBuffers);
+        self.steps.push(BuilderStep::Step(Step::FlushCmdBuffers));
         self
     }
 
     fn finalize_executor(&mut self) {
         if !self.accumulator.is_empty() {
-            let mut systems = Vec::new();
-            std::mem::swap(&mut self.accumulator, &mut systems);
-            let executor = Executor::new(systems);
-            self.steps.push(Step::Systems(executor));
+            let mut systems = std::mem::take(&mut self.accumulator)
+                .into_iter()
+                .zip(self.conditions.drain(..))
+                .map(Some)
+                .collect::<Vec<_>>();
+
+            let order = match Self::order_systems(&systems) {
+                Ok(order) => order,
+                Err(err) => {
+                    self.errors.push(err);
+                    (0..systems.len()).collect()
+                }
+            };
+
+            let (systems, conditions): (Vec<_>, Vec<_>) = order
+                .into_iter()
+                .map(|i| systems[i].take().unwrap())
+                .unzip();
+
+            let mut executor = Executor::new(systems);
+            for (i, condition) in conditions.into_iter().enumerate() {
+                if let Some(condition) = condition {
+                    executor.set_condition(i, condition);
+                }
+            }
+            self.steps.push(BuilderStep::Step(Step::Systems(executor)));
+        }
+    }
+
+    // Topologically sorts systems by their ordering constraints, preferring insertion order.
+    fn order_systems(
+        systems: &[Option<(Box<dyn ParallelRunnable>, Option<RunCondition>)>],
+    ) -> Result<Vec<usize>, ScheduleError> {
+        let systems = systems
+            .iter()
+            .map(|s| &s.as_ref().unwrap().0)
+            .collect::<Vec<_>>();
+
+        let mut dependants = vec![Vec::new(); systems.len()];
+        let mut dependency_counts = vec![0usize; systems.len()];
+        for (i, first) in systems.iter().enumerate() {
+            for (j, second) in systems.iter().enumerate() {
+                if i != j && must_run_before(first.as_ref(), second.as_ref()) {
+                    dependants[i].push(j);
+                    dependency_counts[j] += 1;
+                }
+            }
+        }
+
+        let mut ready = (0..systems.len())
+            .filter(|i| dependency_counts[*i] == 0)
+            .map(Reverse)
+            .collect::<BinaryHeap<_>>();
+        let mut order = Vec::with_capacity(systems.len());
+        while let Some(Reverse(i)) = ready.pop() {
+            order.push(i);
+            for &j in &dependants[i] {
+                dependency_counts[j] -= 1;
+                if dependency_counts[j] == 0 {
+                    ready.push(Reverse(j));
+                }
+            }
+        }
+
+        if order.len() < systems.len() {
+            let cycle = (0..systems.len())
+                .filter(|i| dependency_counts[*i] > 0)
+                .map(|i| display_name(systems[i].as_ref()))
+                .collect();
+            return Err(ScheduleError::Cycle(cycle));
+        }
+
+        Ok(order)
+    }
+
+    // Ensures that no system must run before a system in an earlier step.
+    fn validate_steps(steps: &[Step]) -> Result<(), ScheduleError> {
+        let mut placed = Vec::new();
+        visit_systems(steps, &mut 0, &mut |step, systems| {
+            placed.extend(systems.into_iter().map(|system| (step, system)))
+        });
+
+        for (step, system) in &placed {
+            for (other_step, other) in &placed {
+                if other_step < step && must_run_before(*system, *other) {
+                    return Err(ScheduleError::Unsatisfiable {
+                        before: display_name(*system),
+                        after: display_name(*other),
+                    });
+                }
+            }
         }
+
+        Ok(())
     }
 
     /// Adds a thread local function to the schedule. This function will be executed on the main thread.
@@ -448,9 +1014,9 @@ impl Builder {
         f: F,
     ) -> &mut Self {
         self.finalize_executor();
-        self.steps.push(Step::ThreadLocalFn(
+        self.steps.push(BuilderStep::Step(Step::ThreadLocalFn(
             Box::new(f) as Box<dyn FnMut(&mut World, &mut Resources)>
-        ));
+        )));
         self
     }
 
@@ -458,16 +1024,203 @@ impl Builder {
     pub fn add_thread_local<S: Runnable + 'static>(&mut self, system: S) -> &mut Self {
         self.finalize_executor();
         let system = Box::new(system) as Box<dyn Runnable>;
-        self.steps.push(Step::ThreadLocalSystem(system));
+        self.steps
+            .push(BuilderStep::Step(Step::ThreadLocalSystem(system)));
+        self
+    }
+
+    /// Permits the systems matching `a` and `b` to run in either order, despite conflicting
+    /// access. Systems match if they carry the given label or have the given name.
+    ///
+    /// See `Schedule::ambiguities`.
+    pub fn allow_ambiguity<A: Into<SystemLabel>, B: Into<SystemLabel>>(
+        &mut self,
+        a: A,
+        b: B,
+    ) -> &mut Self {
+        self.allowed_ambiguities.push((a.into(), b.into()));
+        self
+    }
+
+    /// Causes the schedule to fail to build if it contains any ambiguously ordered systems.
+    ///
+    /// See `Schedule::ambiguities`.
+    pub fn deny_ambiguities(&mut self) -> &mut Self {
+        self.deny_ambiguities = true;
+        self
+    }
+
+    /// Adds an already built schedule, which will be run as a single step of this schedule.
+    pub fn add_schedule(&mut self, schedule: Schedule) -> &mut Self {
+        self.finalize_executor();
+        self.steps.push(BuilderStep::Step(Step::Schedule(schedule)));
+        self
+    }
+
+    /// Adds a fixed timestep step, which runs its inner schedule once for each step of time
+    /// accumulated in the `FixedTimesteps` resource.
+    pub fn add_fixed_timestep(&mut self, timestep: FixedTimestep) -> &mut Self {
+        self.finalize_executor();
+        self.steps
+            .push(BuilderStep::Step(Step::FixedTimestep(timestep)));
+        self
+    }
+
+    /// Appends a new, empty, named stage to the schedule.
+    ///
+    /// Systems are added to the stage via [stage](#method.stage). The stage runs in the position
+    /// at which it was added, and flushes its systems' command buffers when it completes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a stage with the same label already exists.
+    pub fn add_stage<L: Into<StageLabel>>(&mut self, label: L) -> &mut Self {
+        let label = label.into();
+        self.assert_new_stage(&label);
+        self.finalize_executor();
+        self.steps
+            .push(BuilderStep::Stage(label, Builder::default()));
+        self
+    }
+
+    /// Inserts a new, empty, named stage immediately before an existing stage.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a stage with the same label already exists, or if `existing` does not exist.
+    pub fn add_stage_before<L: Into<StageLabel>, E: Into<StageLabel>>(
+        &mut self,
+        label: L,
+        existing: E,
+    ) -> &mut Self {
+        let label = label.into();
+        self.assert_new_stage(&label);
+        let index = self.stage_index(&existing.into());
+        self.steps
+            .insert(index, BuilderStep::Stage(label, Builder::default()));
+        self
+    }
+
+    /// Inserts a new, empty, named stage immediately after an existing stage.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a stage with the same label already exists, or if `existing` does not exist.
+    pub fn add_stage_after<L: Into<StageLabel>, E: Into<StageLabel>>(
+        &mut self,
+        label: L,
+        existing: E,
+    ) -> &mut Self {
+        let label = label.into();
+        self.assert_new_stage(&label);
+        let index = self.stage_index(&existing.into());
+        self.steps
+            .insert(index + 1, BuilderStep::Stage(label, Builder::default()));
+        self
+    }
+
+    /// Returns `true` if the schedule contains a stage with the given label.
+    pub fn has_stage<L: Into<StageLabel>>(&self, label: L) -> bool {
+        let label = label.into();
+        self.steps
+            .iter()
+            .any(|step| matches!(step, BuilderStep::Stage(l, _) if *l == label))
+    }
+
+    /// Gets the builder for the named stage.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stage does not exist.
+    pub fn stage<L: Into<StageLabel>>(&mut self, label: L) -> &mut Builder {
+        let index = self.stage_index(&label.into());
+        match &mut self.steps[index] {
+            BuilderStep::Stage(_, builder) => builder,
+            BuilderStep::Step(_) => unreachable!(),
+        }
+    }
+
+    /// Adds a system to the named stage.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stage does not exist.
+    pub fn add_system_to_stage<L, T>(&mut self, label: L, system: T) -> &mut Self
+    where
+        L: Into<StageLabel>,
+        T: ParallelRunnable + 'static,
+    {
+        self.stage(label).add_system(system);
         self
     }
 
+    fn stage_index(&self, label: &StageLabel) -> usize {
+        self.steps
+            .iter()
+            .position(|step| matches!(step, BuilderStep::Stage(l, _) if l == label))
+            .unwrap_or_else(|| panic!("schedule does not contain stage `{}`", label))
+    }
+
+    fn assert_new_stage(&self, label: &StageLabel) {
+        if self.has_stage(label.clone()) {
+            panic!("schedule already contains stage `{}`", label);
+        }
+    }
+
     /// Finalizes the builder into a `Schedule`.
+    ///
+    /// Schedules whose systems have no
+    /// [ordering constraints](../struct.SystemBuilder.html#method.before), and which do not
+    /// [deny ambiguities](#method.deny_ambiguities), always build successfully.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the systems' ordering constraints form a cycle or require a system to run before
+    /// a system in an earlier step, or if ambiguities are denied and the schedule contains
+    /// ambiguously ordered systems. Use [try_build](#method.try_build) to handle these errors
+    /// instead.
     pub fn build(&mut self) -> Schedule {
+        self.try_build().unwrap_or_else(|err| {
+            panic!(
+                "failed to build schedule: {} (use `try_build` to handle this error)",
+                err
+            )
+        })
+    }
+
+    /// Finalizes the builder into a `Schedule`, returning an error if the systems' ordering
+    /// constraints contain a cycle or require a system to run before a system in an earlier step,
+    /// or if ambiguities are denied and the schedule contains ambiguously ordered systems.
+    pub fn try_build(&mut self) -> Result<Schedule, ScheduleError> {
         self.flush();
-        let mut steps = Vec::new();
-        std::mem::swap(&mut self.steps, &mut steps);
-        Schedule { steps }
+        if let Some(err) = self.errors.drain(..).next() {
+            self.steps.clear();
+            return Err(err);
+        }
+
+        let steps = std::mem::take(&mut self.steps)
+            .into_iter()
+            .map(|step| match step {
+                BuilderStep::Step(step) => Ok(step),
+                BuilderStep::Stage(_, mut builder) => builder.try_build().map(Step::Schedule),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Self::validate_steps(&steps)?;
+        let schedule = Schedule {
+            steps,
+            profiling: false,
+            allowed_ambiguities: std::mem::take(&mut self.allowed_ambiguities),
+        };
+
+        if self.deny_ambiguities {
+            let ambiguities = schedule.ambiguities();
+            if !ambiguities.is_empty() {
+                return Err(ScheduleError::Ambiguous(ambiguities));
+            }
+        }
+
+        Ok(schedule)
     }
 }
 
@@ -476,10 +1229,18 @@ impl Default for Builder {
         Self {
             steps: Vec::new(),
             accumulator: Vec::new(),
+            conditions: Vec::new(),
+            errors: Vec::new(),
+            allowed_ambiguities: Vec::new(),
+            deny_ambiguities: false,
         }
     }
 }
 
+/// Runs an executor's systems, optionally recording their timings.
+pub(crate) type RunExecutor<'a> =
+    dyn FnMut(&mut World, &mut Resources, &mut Executor, Option<&Profiler>) + 'a;
+
 /// A step in a schedule.
 pub enum Step {
     /// A batch of systems.
@@ -490,6 +1251,10 @@ pub enum Step {
     ThreadLocalFn(Box<dyn FnMut(&mut World, &mut Resources)>),
     /// A thread local system
     ThreadLocalSystem(Box<dyn Runnable>),
+    /// A nested schedule, such as a stage.
+    Schedule(Schedule),
+    /// A nested schedule which is run at a fixed rate.
+    FixedTimestep(FixedTimestep),
 }
 
 impl Debug for Step {
@@ -502,6 +1267,8 @@ impl Debug for Step {
                 .field(&"dyn FnMut(&mut World, &mut Resources)")
                 .finish(),
             Step::ThreadLocalSystem(x) => f.debug_tuple("ThreadLocalSystem").field(&x).finish(),
+            Step::Schedule(x) => f.debug_tuple("Schedule").field(&x).finish(),
+            Step::FixedTimestep(x) => f.debug_tuple("FixedTimestep").field(&x).finish(),
         }
     }
 }
@@ -529,6 +1296,8 @@ impl Debug for Step {
 #[derive(Debug)]
 pub struct Schedule {
     steps: Vec<Step>,
+    profiling: bool,
+    allowed_ambiguities: Vec<(SystemLabel, SystemLabel)>,
 }
 
 impl Schedule {
@@ -537,21 +1306,49 @@ impl Schedule {
         Builder::default()
     }
 
+    /// Enables or disables profiling.
+    ///
+    /// While enabled, each execution of the schedule records the timing of every system and
+    /// command buffer flush, and inserts the results into the resources as a `ScheduleProfile`.
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profiling = enabled;
+    }
+
+    /// Returns `true` if profiling is enabled.
+    pub fn is_profiling(&self) -> bool {
+        self.profiling
+    }
+
     /// Executes all of the steps in the schedule.
     #[cfg(not(feature = "parallel"))]
     pub fn execute(&mut self, world: &mut World, resources: &mut Resources) {
-        self.execute_internal(world, resources, |world, resources, executor| {
-            executor.run_systems(world, resources.internal())
-        });
+        self.execute_profiled(
+            world,
+            resources,
+            &mut |world, resources, executor, profiler| {
+                executor.run_systems_profiled(world, resources.internal(), profiler)
+            },
+        );
     }
 
     /// Executes all of the steps in the schedule.
     #[cfg(feature = "parallel")]
     pub fn execute(&mut self, world: &mut World, resources: &mut Resources) {
-        self.execute_internal(world, resources, |world, resources, executor| {
-            let resources = resources.internal();
-            rayon::join(|| executor.run_systems(world, resources), || {});
-        });
+        self.execute_profiled(
+            world,
+            resources,
+            &mut |world, resources, executor, profiler| {
+                let resources = resources.internal();
+                if executor.requires_main_thread() {
+                    executor.run_systems_profiled(world, resources, profiler, None);
+                } else {
+                    rayon::join(
+                        || executor.run_systems_profiled(world, resources, profiler, None),
+                        || {},
+                    );
+                }
+            },
+        );
     }
 
     /// Executes all of the steps in the schedule, with parallelized systems running in
@@ -563,44 +1360,156 @@ impl Schedule {
         resources: &mut Resources,
         pool: &rayon::ThreadPool,
     ) {
-        self.execute_internal(world, resources, |world, resources, executor| {
-            let resources = resources.internal();
-            pool.install(|| executor.run_systems(world, resources));
-        });
+        self.execute_profiled(
+            world,
+            resources,
+            &mut |world, resources, executor, profiler| {
+                let resources = resources.internal();
+                if executor.requires_main_thread() {
+                    executor.run_systems_profiled(world, resources, profiler, Some(pool));
+                } else {
+                    pool.install(|| {
+                        executor.run_systems_profiled(world, resources, profiler, None)
+                    });
+                }
+            },
+        );
     }
 
-    fn execute_internal<F: FnMut(&mut World, &mut Resources, &mut Executor)>(
+    fn execute_profiled(
         &mut self,
         world: &mut World,
         resources: &mut Resources,
-        mut run_executor: F,
+        run_executor: &mut RunExecutor,
+    ) {
+        if self.profiling {
+            let profiler = Profiler::new();
+            self.execute_internal(world, resources, run_executor, Some(&profiler));
+            resources.insert(profiler.finish());
+        } else {
+            self.execute_internal(world, resources, run_executor, None);
+        }
+    }
+
+    pub(crate) fn execute_internal(
+        &mut self,
+        world: &mut World,
+        resources: &mut Resources,
+        run_executor: &mut RunExecutor,
+        profiler: Option<&Profiler>,
     ) {
         enum ToFlush<'a> {
             Executor(&'a mut Executor),
-            System(&'a mut CommandBuffer),
+            System(&'a mut dyn Runnable),
         }
 
         let mut waiting_flush: Vec<ToFlush> = Vec::new();
         for step in &mut self.steps {
             match step {
                 Step::Systems(executor) => {
-                    run_executor(world, resources, executor);
+                    run_executor(world, resources, executor, profiler);
                     waiting_flush.push(ToFlush::Executor(executor));
                 }
                 Step::FlushCmdBuffers => {
                     waiting_flush.drain(..).for_each(|e| match e {
-                        ToFlush::Executor(exec) => exec.flush_command_buffers(world, resources),
-                        ToFlush::System(cmd) => cmd.flush(world, resources),
+                        ToFlush::Executor(exec) => {
+                            exec.flush_command_buffers_profiled(world, resources, profiler)
+                        }
+                        ToFlush::System(system) => flush_system(system, world, resources, profiler),
                     });
                 }
-                Step::ThreadLocalFn(function) => function(world, resources),
+                Step::ThreadLocalFn(function) => {
+                    let timer = profiler.map(Profiler::begin);
+                    function(world, resources);
+                    if let Some(timer) = timer {
+                        timer.end(SpanKind::ThreadLocal, "<thread local fn>".to_string());
+                    }
+                }
                 Step::ThreadLocalSystem(system) => {
                     system.prepare(world);
+                    let timer = profiler.map(Profiler::begin);
                     system.run(world, resources);
-                    if let Some(cmd) = system.command_buffer_mut(world.id()) {
-                        waiting_flush.push(ToFlush::System(cmd));
+                    if let Some(timer) = timer {
+                        timer.end(SpanKind::ThreadLocal, display_name(system.as_ref()));
                     }
+                    waiting_flush.push(ToFlush::System(system.as_mut()));
+                }
+                Step::Schedule(schedule) => {
+                    schedule.execute_internal(world, resources, run_executor, profiler)
+                }
+                Step::FixedTimestep(timestep) => {
+                    timestep.run(world, resources, run_executor, profiler)
+                }
+            }
+        }
+    }
+
+    /// Flushes the command buffers of all systems in the schedule.
+    pub(crate) fn flush_command_buffers(
+        &mut self,
+        world: &mut World,
+        resources: &mut Resources,
+        profiler: Option<&Profiler>,
+    ) {
+        for step in &mut self.steps {
+            match step {
+                Step::Systems(executor) => {
+                    executor.flush_command_buffers_profiled(world, resources, profiler)
+                }
+                Step::ThreadLocalSystem(system) => {
+                    flush_system(system.as_mut(), world, resources, profiler)
+                }
+                Step::Schedule(schedule) => {
+                    schedule.flush_command_buffers(world, resources, profiler)
                 }
+                Step::FlushCmdBuffers | Step::ThreadLocalFn(_) | Step::FixedTimestep(_) => {}
+            }
+        }
+    }
+
+    /// Computes the dependencies between the systems in the schedule, and the reasons for them.
+    ///
+    /// Steps are numbered in execution order, including the steps of nested schedules.
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        let mut graph = DependencyGraph::default();
+        visit_systems(&self.steps, &mut 0, &mut |step, systems| {
+            graph.add_systems(step, systems)
+        });
+        graph
+    }
+
+    /// Finds pairs of systems which may run in either order, or concurrently, despite
+    /// conflicting access to the same resources or components.
+    ///
+    /// Systems are only reported if they are in the same step, and if neither is required to run
+    /// before the other by their [labels](../struct.SystemBuilder.html#method.label). Pairs which
+    /// were permitted with `Builder::allow_ambiguity` are not reported.
+    ///
+    /// Conflicting component access is reported even if the systems' queries never match the
+    /// same archetypes.
+    pub fn ambiguities(&self) -> Vec<Ambiguity> {
+        let mut allowed = Vec::new();
+        self.collect_allowed_ambiguities(&mut allowed);
+
+        let mut ambiguities = Vec::new();
+        visit_systems(&self.steps, &mut 0, &mut |step, systems| {
+            ambiguities.extend(find_ambiguities(step, &systems, &allowed))
+        });
+        ambiguities
+    }
+
+    fn collect_allowed_ambiguities<'a>(
+        &'a self,
+        allowed: &mut Vec<&'a (SystemLabel, SystemLabel)>,
+    ) {
+        allowed.extend(self.allowed_ambiguities.iter());
+        for step in &self.steps {
+            match step {
+                Step::Schedule(schedule) => schedule.collect_allowed_ambiguities(allowed),
+                Step::FixedTimestep(timestep) => {
+                    timestep.schedule().collect_allowed_ambiguities(allowed)
+                }
+                _ => {}
             }
         }
     }
@@ -619,7 +1528,11 @@ impl From<Builder> for Schedule {
 
 impl From<Vec<Step>> for Schedule {
     fn from(steps: Vec<Step>) -> Self {
-        Self { steps }
+        Self {
+            steps,
+            profiling: false,
+            allowed_ambiguities: Vec::new(),
+        }
     }
 }
 
@@ -671,6 +1584,289 @@ mod tests {
         assert_eq!(*order, sorted);
     }
 
+    #[test]
+    fn conditional_systems() {
+        let mut world = World::default();
+
+        struct Resource;
+        struct Paused(bool);
+
+        let mut resources = Resources::default();
+        resources.insert(Resource);
+        resources.insert(Paused(true));
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let order_clone = order.clone();
+        let system_one = SystemBuilder::new("one")
+            .write_resource::<Resource>()
+            .build(move |_, _, _, _| order_clone.lock().unwrap().push(1usize));
+        let order_clone = order.clone();
+        let system_two = SystemBuilder::new("two")
+            .write_resource::<Resource>()
+            .build(move |_, _, _, _| order_clone.lock().unwrap().push(2usize));
+        let order_clone = order.clone();
+        let system_three = SystemBuilder::new("three")
+            .write_resource::<Resource>()
+            .build(move |_, _, _, _| order_clone.lock().unwrap().push(3usize));
+
+        let mut schedule = Schedule::builder()
+            .add_system(system_one)
+            .add_system_with_condition(system_two, |resources| {
+                !resources.get::<Paused>().unwrap().0
+            })
+            .add_system(system_three)
+            .build();
+
+        // the skipped system must not block its dependants
+        schedule.execute(&mut world, &mut resources);
+        assert_eq!(*order.lock().unwrap(), vec![1, 3]);
+
+        order.lock().unwrap().clear();
+        resources.get_mut::<Paused>().unwrap().0 = false;
+        schedule.execute(&mut world, &mut resources);
+        assert_eq!(*order.lock().unwrap(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn conditional_systems_not_prepared() {
+        use crate::internals::{query::view::read::Read, subworld::ArchetypeAccess};
+
+        let mut world = World::default();
+        world.push((1usize,));
+        let mut resources = Resources::default();
+
+        let systems = (0..3)
+            .map(|_| {
+                Box::new(
+                    SystemBuilder::new("system")
+                        .with_query(Read::<usize>::query())
+                        .build(|_, _, _, _| {}),
+                ) as Box<dyn ParallelRunnable>
+            })
+            .collect();
+        let mut executor = Executor::new(systems);
+        executor.set_condition(1, RunCondition::new(|_| false));
+        executor.execute(&mut world, &mut resources);
+
+        // only the systems which ran have filtered the world's archetypes
+        let prepared = executor
+            .systems()
+            .map(|system| match system.accesses_archetypes() {
+                ArchetypeAccess::Some(bitset) => !bitset.is_empty(),
+                ArchetypeAccess::All => true,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(prepared, vec![true, false, true]);
+    }
+
+    #[test]
+    fn labelled_systems() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        // systems share no data, so only their labels order them
+        let order_clone = order.clone();
+        let render = SystemBuilder::new("render")
+            .label("render")
+            .after("physics")
+            .build(move |_, _, _, _| order_clone.lock().unwrap().push("render"));
+        let order_clone = order.clone();
+        let physics = SystemBuilder::new("physics")
+            .label("physics")
+            .after("input")
+            .build(move |_, _, _, _| order_clone.lock().unwrap().push("physics"));
+        let order_clone = order.clone();
+        let input = SystemBuilder::new("input")
+            .label("input")
+            .build(move |_, _, _, _| order_clone.lock().unwrap().push("input"));
+        let order_clone = order.clone();
+        let audio = SystemBuilder::new("audio")
+            .before("physics")
+            .build(move |_, _, _, _| order_clone.lock().unwrap().push("audio"));
+
+        let mut schedule = Schedule::builder()
+            .add_system(render)
+            .add_system(physics)
+            .add_system(input)
+            .add_system(audio)
+            .build();
+
+        for _ in 0..10 {
+            schedule.execute(&mut world, &mut resources);
+            let mut order = order.lock().unwrap();
+            let position = |name| order.iter().position(|x| *x == name).unwrap();
+            assert!(position("input") < position("physics"));
+            assert!(position("audio") < position("physics"));
+            assert!(position("physics") < position("render"));
+            order.clear();
+        }
+    }
+
+    #[test]
+    fn labelled_system_cycles() {
+        let one = SystemBuilder::new("one")
+            .label("one")
+            .after("two")
+            .build(|_, _, _, _| {});
+        let two = SystemBuilder::new("two")
+            .label("two")
+            .after("one")
+            .build(|_, _, _, _| {});
+        let three = SystemBuilder::new("three")
+            .after("one")
+            .build(|_, _, _, _| {});
+
+        let result = Schedule::builder()
+            .add_system(one)
+            .add_system(two)
+            .add_system(three)
+            .try_build();
+        assert_eq!(
+            result.unwrap_err(),
+            ScheduleError::Cycle(vec![
+                "one".to_string(),
+                "two".to_string(),
+                "three".to_string()
+            ])
+        );
+
+        let one = SystemBuilder::new("one")
+            .label("one")
+            .build(|_, _, _, _| {});
+        let two = SystemBuilder::new("two")
+            .before("one")
+            .build(|_, _, _, _| {});
+
+        let result = Schedule::builder()
+            .add_system(one)
+            .flush()
+            .add_system(two)
+            .try_build();
+        assert_eq!(
+            result.unwrap_err(),
+            ScheduleError::Unsatisfiable {
+                before: "two".to_string(),
+                after: "one".to_string()
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "use `try_build` to handle this error")]
+    fn build_panics_on_cycles() {
+        let one = SystemBuilder::new("one")
+            .label("one")
+            .after("two")
+            .build(|_, _, _, _| {});
+        let two = SystemBuilder::new("two")
+            .label("two")
+            .after("one")
+            .build(|_, _, _, _| {});
+
+        Schedule::builder().add_system(one).add_system(two).build();
+    }
+
+    #[test]
+    fn stages() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let system = |name: &'static str| {
+            let order = order.clone();
+            SystemBuilder::new(name).build(move |_, _, _, _| order.lock().unwrap().push(name))
+        };
+
+        let nested = Schedule::builder()
+            .add_system(system("nested_one"))
+            .flush()
+            .add_system(system("nested_two"))
+            .build();
+
+        let mut builder = Schedule::builder();
+        builder
+            .add_system(system("first"))
+            .add_stage("update")
+            .add_stage("post_update")
+            .add_schedule(nested)
+            .add_stage_before("pre_update", "update");
+        assert!(builder.has_stage("pre_update"));
+        assert!(!builder.has_stage("render"));
+
+        builder.add_system_to_stage("post_update", system("post_update"));
+        builder
+            .stage("update")
+            .add_system(system("update_one"))
+            .flush()
+            .add_system(system("update_two"));
+        builder.add_system_to_stage("pre_update", system("pre_update"));
+
+        let mut schedule = builder.build();
+        schedule.execute(&mut world, &mut resources);
+
+        assert_eq!(
+            *order.lock().unwrap(),
+            vec![
+                "first",
+                "pre_update",
+                "update_one",
+                "update_two",
+                "post_update",
+                "nested_one",
+                "nested_two"
+            ]
+        );
+    }
+
+    #[test]
+    fn profiling() {
+        use crate::internals::systems::profile::{ScheduleProfile, SpanKind};
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+
+        let spawn = SystemBuilder::new("spawn").build(|cmd, _, _, _| {
+            cmd.push((1usize,));
+        });
+        let idle = SystemBuilder::new("idle").build(|_, _, _, _| {});
+
+        let mut schedule = Schedule::builder()
+            .add_system(spawn)
+            .add_system(idle)
+            .add_thread_local_fn(|_, _| {})
+            .build();
+
+        schedule.execute(&mut world, &mut resources);
+        assert!(resources.get::<ScheduleProfile>().is_none());
+
+        schedule.set_profiling(true);
+        schedule.execute(&mut world, &mut resources);
+
+        let profile = resources.get::<ScheduleProfile>().unwrap();
+        let mut systems = profile
+            .systems()
+            .map(|span| (span.name.as_str(), span.kind))
+            .collect::<Vec<_>>();
+        systems.sort_by_key(|(name, _)| *name);
+        assert_eq!(
+            systems,
+            vec![
+                ("<thread local fn>", SpanKind::ThreadLocal),
+                ("idle", SpanKind::System),
+                ("spawn", SpanKind::System)
+            ]
+        );
+        assert_eq!(profile.flushes().count(), 2);
+        assert!(profile.spans().iter().all(|span| span.end >= span.start));
+        assert!(profile
+            .spans()
+            .iter()
+            .all(|span| span.end <= profile.duration()));
+    }
+
     #[test]
     fn flush() {
         let mut world = World::default();
@@ -753,4 +1949,140 @@ mod tests {
 
         schedule.execute(&mut world, &mut resources);
     }
+
+    #[test]
+    fn non_send_resource() {
+        use std::{cell::RefCell, rc::Rc, thread::ThreadId};
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+
+        struct Window(Rc<RefCell<Vec<ThreadId>>>);
+
+        let threads = Rc::new(RefCell::new(Vec::new()));
+        resources.insert(Window(threads.clone()));
+        resources.insert(0usize);
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let system = |name: &'static str| {
+            let order = order.clone();
+            SystemBuilder::new(name)
+                .write_resource::<usize>()
+                .build(move |_, _, _, _| order.lock().unwrap().push(name))
+        };
+        let render = |name: &'static str| {
+            let order = order.clone();
+            SystemBuilder::new(name)
+                .read_resource::<usize>()
+                .read_non_send_resource::<Window>()
+                .build(move |_, _, (_, window), _| {
+                    window.0.borrow_mut().push(std::thread::current().id());
+                    order.lock().unwrap().push(name);
+                })
+        };
+
+        let render_one = render("render_one");
+        assert!(render_one.requires_main_thread());
+        assert!(!system("update").requires_main_thread());
+
+        let mut schedule = Schedule::builder()
+            .add_system(system("update"))
+            .add_system(render_one)
+            .add_system(render("render_two"))
+            .add_system(system("late_update"))
+            .build();
+        schedule.execute(&mut world, &mut resources);
+
+        let mut order = order.lock().unwrap();
+        assert_eq!(order.first(), Some(&"update"));
+        assert_eq!(order.last(), Some(&"late_update"));
+        order.sort_unstable();
+        assert_eq!(
+            *order,
+            vec!["late_update", "render_one", "render_two", "update"]
+        );
+        assert_eq!(*threads.borrow(), vec![std::thread::current().id(); 2]);
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn non_send_resource_in_thread_pool() {
+        use std::{cell::RefCell, rc::Rc};
+
+        struct Window(Rc<RefCell<usize>>);
+
+        // a single worker must not block waiting for systems which are queued behind it
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+        let count = pool.install(|| {
+            let mut world = World::default();
+            let mut resources = Resources::default();
+            let count = Rc::new(RefCell::new(0usize));
+            resources.insert(Window(count.clone()));
+            resources.insert(0usize);
+
+            // the render system must wait for the update system
+            let update = SystemBuilder::new("update")
+                .write_resource::<usize>()
+                .build(|_, _, value, _| **value += 1);
+            let render = SystemBuilder::new("render")
+                .read_resource::<usize>()
+                .read_non_send_resource::<Window>()
+                .build(|_, _, (value, window), _| *window.0.borrow_mut() += **value);
+
+            let mut schedule = Schedule::builder()
+                .add_system(update)
+                .add_system(render)
+                .build();
+            schedule.execute(&mut world, &mut resources);
+            let count = *count.borrow();
+            count
+        });
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn deterministic_command_buffers() {
+        use crate::internals::{
+            entity::{Entity, EntityAllocation},
+            world::WorldOptions,
+        };
+
+        let record = || {
+            let mut world = World::new(WorldOptions {
+                entity_allocation: EntityAllocation::Deterministic,
+                ..Default::default()
+            });
+            let mut resources = Resources::default();
+            let pushed = Arc::new(Mutex::new(vec![Vec::new(), Vec::new()]));
+
+            // the systems may run in parallel, and push their entities in any order
+            let mut builder = Schedule::builder();
+            for i in 0..2 {
+                let pushed = pushed.clone();
+                builder.add_system(SystemBuilder::new(format!("push {}", i)).build(
+                    move |cmd, _, _, _| {
+                        let entities = cmd.extend(vec![(i,); 64]).to_vec();
+                        pushed.lock().unwrap()[i].extend(entities);
+                    },
+                ));
+            }
+            let mut schedule = builder.build();
+            for _ in 0..3 {
+                schedule.execute(&mut world, &mut resources);
+                world.push((2usize,));
+            }
+
+            assert_eq!(world.len(), 387);
+            let pushed = pushed.lock().unwrap().clone();
+            pushed
+        };
+
+        let pushed: Vec<Vec<Entity>> = record();
+        for _ in 0..4 {
+            assert_eq!(record(), pushed);
+        }
+    }
 }
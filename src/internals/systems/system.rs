@@ -2,7 +2,9 @@
 
 use super::{
     command::CommandBuffer,
-    resources::{Resource, ResourceSet, ResourceTypeId, UnsafeResources},
+    events::Events,
+    future::{AsyncContext, AsyncSystemFn},
+    resources::{NonSend, NonSendMut, Resource, ResourceSet, ResourceTypeId, UnsafeResources},
     schedule::Runnable,
 };
 use crate::internals::{
@@ -21,7 +23,7 @@ use crate::internals::{
     world::{World, WorldId},
 };
 use bit_set::BitSet;
-use std::{any::TypeId, borrow::Cow, collections::HashMap, marker::PhantomData};
+use std::{any::TypeId, borrow::Cow, collections::HashMap, future::Future, marker::PhantomData};
 
 /// Provides an abstraction across tuples of queries for system closures.
 pub trait QuerySet: Send + Sync {
@@ -108,6 +110,32 @@ impl<T: Into<Cow<'static, str>>> From<T> for SystemId {
     }
 }
 
+/// A label which can be attached to systems so that other systems can be ordered relative to them.
+///
+/// See [SystemBuilder::label](struct.SystemBuilder.html#method.label).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SystemLabel(Cow<'static, str>);
+
+impl std::fmt::Display for SystemLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<T: Into<Cow<'static, str>>> From<T> for SystemLabel {
+    fn from(name: T) -> SystemLabel {
+        SystemLabel(name.into())
+    }
+}
+
+/// The labels and ordering constraints of a system.
+#[derive(Debug, Clone, Default)]
+struct SystemOrdering {
+    labels: Vec<SystemLabel>,
+    before: Vec<SystemLabel>,
+    after: Vec<SystemLabel>,
+}
+
 struct ResourceMarker<T>(PhantomData<*const T>);
 unsafe impl<T: Send> Send for ResourceMarker<T> {}
 unsafe impl<T: Sync> Sync for ResourceMarker<T> {}
@@ -126,6 +154,8 @@ pub struct System<R, Q, F> {
     run_fn: F,
     archetypes: ArchetypeAccess,
     access: SystemAccess,
+    ordering: SystemOrdering,
+    main_thread: bool,
     command_buffer: HashMap<WorldId, CommandBuffer>,
 }
 
@@ -139,6 +169,22 @@ where
         self.name.as_ref()
     }
 
+    fn labels(&self) -> &[SystemLabel] {
+        &self.ordering.labels
+    }
+
+    fn before(&self) -> &[SystemLabel] {
+        &self.ordering.before
+    }
+
+    fn after(&self) -> &[SystemLabel] {
+        &self.ordering.after
+    }
+
+    fn requires_main_thread(&self) -> bool {
+        self.main_thread
+    }
+
     fn reads(&self) -> (&[ResourceTypeId], &[ComponentTypeId]) {
         (
             &self.access.resources.reads_only(),
@@ -157,6 +203,12 @@ where
         if let ArchetypeAccess::Some(bitset) = &mut self.archetypes {
             self.queries.filter_archetypes(world, bitset);
         }
+
+        // systems are prepared in schedule order, so that their command buffers are assigned
+        // deterministic entity ID ranges
+        self.command_buffer
+            .entry(world.id())
+            .or_insert_with(|| CommandBuffer::new(world));
     }
 
     fn accesses_archetypes(&self) -> &ArchetypeAccess {
@@ -257,6 +309,8 @@ pub struct SystemBuilder<Q = (), R = ()> {
     resource_access: Permissions<ResourceTypeId>,
     component_access: Permissions<ComponentTypeId>,
     access_all_archetypes: bool,
+    ordering: SystemOrdering,
+    main_thread: bool,
 }
 
 impl SystemBuilder<(), ()> {
@@ -281,6 +335,8 @@ impl Default for SystemBuilder<(), ()> {
             resource_access: Permissions::default(),
             component_access: Permissions::default(),
             access_all_archetypes: false,
+            ordering: SystemOrdering::default(),
+            main_thread: false,
         }
     }
 }
@@ -298,6 +354,49 @@ where
         }
     }
 
+    /// Attaches a label to the system. Other systems can use the label to declare that they must
+    /// run [before](#method.before) or [after](#method.after) this system.
+    ///
+    /// Many systems may share the same label.
+    pub fn label<L: Into<SystemLabel>>(mut self, label: L) -> Self {
+        self.ordering.labels.push(label.into());
+        self
+    }
+
+    /// Requires that this system runs before all systems with the given label.
+    ///
+    /// Ordering constraints are resolved when the schedule is built. Labels which are not
+    /// attached to any system are ignored.
+    ///
+    /// ```
+    /// # use legion::*;
+    /// let physics = SystemBuilder::new("physics")
+    ///     .label("physics")
+    ///     .after("input")
+    ///     .build(|_, _, _, _| {});
+    /// let input = SystemBuilder::new("input")
+    ///     .label("input")
+    ///     .build(|_, _, _, _| {});
+    ///
+    /// // `input` will run first, despite being added last
+    /// let schedule = Schedule::builder()
+    ///     .add_system(physics)
+    ///     .add_system(input)
+    ///     .build();
+    /// ```
+    pub fn before<L: Into<SystemLabel>>(mut self, label: L) -> Self {
+        self.ordering.before.push(label.into());
+        self
+    }
+
+    /// Requires that this system runs after all systems with the given label.
+    ///
+    /// See [before](#method.before).
+    pub fn after<L: Into<SystemLabel>>(mut self, label: L) -> Self {
+        self.ordering.after.push(label.into());
+        self
+    }
+
     /// Defines a query to provide this system for its execution. Multiple queries can be provided,
     /// and queries are cached internally for efficiency for filtering and archetype ID handling.
     ///
@@ -321,6 +420,8 @@ where
             resource_access: self.resource_access,
             component_access: self.component_access,
             access_all_archetypes: self.access_all_archetypes,
+            ordering: self.ordering,
+            main_thread: self.main_thread,
         }
     }
 
@@ -343,6 +444,8 @@ where
             resource_access: self.resource_access,
             component_access: self.component_access,
             access_all_archetypes: self.access_all_archetypes,
+            ordering: self.ordering,
+            main_thread: self.main_thread,
         }
     }
 
@@ -365,9 +468,115 @@ where
             resource_access: self.resource_access,
             component_access: self.component_access,
             access_all_archetypes: self.access_all_archetypes,
+            ordering: self.ordering,
+            main_thread: self.main_thread,
+        }
+    }
+
+    /// Flag this resource type as being read by this system, where the resource is not `Send`
+    /// or `Sync`.
+    ///
+    /// The system will always be run on the thread which owns the `Resources`, while other
+    /// systems in the schedule continue to run in parallel.
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # use std::rc::Rc;
+    /// struct Window(Rc<String>);
+    ///
+    /// let system = SystemBuilder::new("render")
+    ///     .read_non_send_resource::<Window>()
+    ///     .build(|_, _, window, _| println!("drawing to {}", window.0));
+    /// ```
+    pub fn read_non_send_resource<T>(
+        mut self,
+    ) -> SystemBuilder<Q, <R as ConsAppend<NonSend<T>>>::Output>
+    where
+        T: 'static + Resource,
+        R: ConsAppend<NonSend<T>>,
+        <R as ConsAppend<NonSend<T>>>::Output: ConsFlatten,
+    {
+        self.resource_access.push_read(ResourceTypeId::of::<T>());
+
+        SystemBuilder {
+            name: self.name,
+            queries: self.queries,
+            resources: ConsAppend::append(self.resources, NonSend::<T>::default()),
+            resource_access: self.resource_access,
+            component_access: self.component_access,
+            access_all_archetypes: self.access_all_archetypes,
+            ordering: self.ordering,
+            main_thread: true,
+        }
+    }
+
+    /// Flag this resource type as being written by this system, where the resource is not
+    /// `Send` or `Sync`.
+    ///
+    /// See [read_non_send_resource](#method.read_non_send_resource).
+    pub fn write_non_send_resource<T>(
+        mut self,
+    ) -> SystemBuilder<Q, <R as ConsAppend<NonSendMut<T>>>::Output>
+    where
+        T: 'static + Resource,
+        R: ConsAppend<NonSendMut<T>>,
+        <R as ConsAppend<NonSendMut<T>>>::Output: ConsFlatten,
+    {
+        self.resource_access.push(ResourceTypeId::of::<T>());
+
+        SystemBuilder {
+            name: self.name,
+            queries: self.queries,
+            resources: ConsAppend::append(self.resources, NonSendMut::<T>::default()),
+            resource_access: self.resource_access,
+            component_access: self.component_access,
+            access_all_archetypes: self.access_all_archetypes,
+            ordering: self.ordering,
+            main_thread: true,
         }
     }
 
+    /// Flag the `Events<T>` resource as being read by this system.
+    ///
+    /// The system can read events with an [EventReader](struct.EventReader.html), using a cursor
+    /// owned by the system closure.
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # use legion::systems::{EventCursor, EventReader};
+    /// struct Collision(u32);
+    ///
+    /// let mut cursor = EventCursor::<Collision>::default();
+    /// let system = SystemBuilder::new("print_collisions")
+    ///     .read_events::<Collision>()
+    ///     .build(move |_, _, events, _| {
+    ///         for collision in EventReader::new(events, &mut cursor).iter() {
+    ///             println!("collision {}", collision.0);
+    ///         }
+    ///     });
+    /// ```
+    pub fn read_events<T>(self) -> SystemBuilder<Q, <R as ConsAppend<Read<Events<T>>>>::Output>
+    where
+        T: 'static,
+        R: ConsAppend<Read<Events<T>>>,
+        <R as ConsAppend<Read<Events<T>>>>::Output: ConsFlatten,
+    {
+        self.read_resource::<Events<T>>()
+    }
+
+    /// Flag the `Events<T>` resource as being written by this system.
+    ///
+    /// The system can send events with an [EventWriter](struct.EventWriter.html), or by calling
+    /// `Events::send` directly.
+    pub fn write_events<T>(self) -> SystemBuilder<Q, <R as ConsAppend<Write<Events<T>>>>::Output>
+    where
+        T: 'static,
+        R: ConsAppend<Write<Events<T>>>,
+        <R as ConsAppend<Write<Events<T>>>>::Output: ConsFlatten,
+    {
+        self.write_resource::<Events<T>>()
+    }
+
     /// This performs a soft resource block on the component for writing. The dispatcher will
     /// generally handle dispatching read and writes on components based on archetype, allowing
     /// for more granular access and more parallelization of systems.
@@ -426,6 +635,57 @@ where
             &mut <Q as ConsFlatten>::Output,
         ),
     {
+        self.build_system(run_fn)
+    }
+
+    /// Builds a system whose body returns a future, which is polled across many executions of
+    /// the schedule. The system is scheduled against its declared accesses like any other.
+    ///
+    /// The body is called to start a new future whenever the system runs without a future in
+    /// progress. The future is then polled at most once each time the system runs, and only if it
+    /// has been woken since it was last polled. Inside the future, the system's data can be
+    /// accessed with [AsyncContext::with](struct.AsyncContext.html#method.with), which only holds
+    /// borrows for the duration of the poll.
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # async fn load_level() -> Vec<(usize,)> { Vec::new() }
+    /// struct Loading(bool);
+    ///
+    /// let system = SystemBuilder::new("load_level")
+    ///     .write_resource::<Loading>()
+    ///     .build_async(|context| async move {
+    ///         context.with(|_, _, loading, _| loading.0 = true);
+    ///         let level = load_level().await;
+    ///         context.with(|cmd, _, loading, _| {
+    ///             cmd.extend(level);
+    ///             loading.0 = false;
+    ///         });
+    ///     });
+    /// ```
+    pub fn build_async<F, Fut>(
+        self,
+        body: F,
+    ) -> System<
+        <R as ConsFlatten>::Output,
+        <Q as ConsFlatten>::Output,
+        AsyncSystemFn<<R as ConsFlatten>::Output, <Q as ConsFlatten>::Output>,
+    >
+    where
+        <R as ConsFlatten>::Output: for<'a> ResourceSet<'a>,
+        <Q as ConsFlatten>::Output: QuerySet,
+        F: FnMut(AsyncContext<<R as ConsFlatten>::Output, <Q as ConsFlatten>::Output>) -> Fut
+            + Send
+            + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.build_system(AsyncSystemFn::new(body))
+    }
+
+    fn build_system<F>(
+        self,
+        run_fn: F,
+    ) -> System<<R as ConsFlatten>::Output, <Q as ConsFlatten>::Output, F> {
         System {
             name: self.name,
             run_fn,
@@ -440,6 +700,8 @@ where
                 resources: self.resource_access,
                 components: self.component_access,
             },
+            ordering: self.ordering,
+            main_thread: self.main_thread,
             command_buffer: HashMap::default(),
         }
     }
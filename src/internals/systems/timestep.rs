@@ -0,0 +1,263 @@
+//! Contains types related to running schedules at a fixed rate.
+//!
+//! A [FixedTimestep](struct.FixedTimestep.html) step runs an inner schedule zero or more times
+//! per execution of its parent schedule, consuming time which has been added to the
+//! [FixedTimesteps](struct.FixedTimesteps.html) resource.
+
+use super::{
+    profile::Profiler,
+    resources::Resources,
+    schedule::{RunExecutor, Schedule, StageLabel},
+};
+use crate::internals::world::World;
+use std::{collections::HashMap, time::Duration};
+
+/// The accumulated time and interpolation alpha of a single fixed timestep.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedTimestepState {
+    step: Duration,
+    accumulator: Duration,
+    alpha: f64,
+}
+
+impl FixedTimestepState {
+    /// Gets the duration of each step.
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// Gets the time which has been accumulated but not yet consumed by a step.
+    pub fn accumulator(&self) -> Duration {
+        self.accumulator
+    }
+
+    /// Gets how far, as a fraction of a step, the accumulated time has progressed towards the
+    /// next step. Usually used to interpolate between the previous and current fixed states
+    /// when rendering.
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+}
+
+/// A resource which accumulates time for all fixed timesteps in a schedule.
+///
+/// Add the time elapsed since the previous frame with [advance](#method.advance) before each
+/// execution of the schedule. Timesteps are registered when they are first run, starting with
+/// the time given to the most recent call to `advance`. The resource is inserted automatically
+/// if it does not exist when a `FixedTimestep` step runs.
+#[derive(Debug, Default)]
+pub struct FixedTimesteps {
+    last_elapsed: Duration,
+    timesteps: HashMap<StageLabel, FixedTimestepState>,
+}
+
+impl FixedTimesteps {
+    /// Adds elapsed time to the accumulator of every fixed timestep.
+    pub fn advance(&mut self, elapsed: Duration) {
+        self.last_elapsed = elapsed;
+        for state in self.timesteps.values_mut() {
+            state.accumulator += elapsed;
+        }
+    }
+
+    /// Gets the state of the named fixed timestep.
+    pub fn get<L: Into<StageLabel>>(&self, label: L) -> Option<&FixedTimestepState> {
+        self.timesteps.get(&label.into())
+    }
+
+    fn state_mut(&mut self, label: &StageLabel, step: Duration) -> &mut FixedTimestepState {
+        let elapsed = self.last_elapsed;
+        let state = self
+            .timesteps
+            .entry(label.clone())
+            .or_insert_with(|| FixedTimestepState {
+                step,
+                accumulator: elapsed,
+                alpha: 0.0,
+            });
+        state.step = step;
+        state
+    }
+}
+
+/// A schedule step which runs an inner schedule once for each fixed step of time accumulated in
+/// the [FixedTimesteps](struct.FixedTimesteps.html) resource.
+///
+/// Command buffers are flushed after each iteration of the inner schedule.
+///
+/// # Examples
+///
+/// ```
+/// # use legion::*;
+/// # use legion::systems::{FixedTimestep, FixedTimesteps};
+/// # use std::time::Duration;
+/// # let integrate = SystemBuilder::new("integrate").build(|_,_,_,_| {});
+/// # let render = SystemBuilder::new("render").build(|_,_,_,_| {});
+/// let physics = Schedule::builder().add_system(integrate).build();
+///
+/// let mut schedule = Schedule::builder()
+///     .add_fixed_timestep(FixedTimestep::new(
+///         "physics",
+///         Duration::from_secs_f64(1.0 / 60.0),
+///         physics,
+///     ))
+///     .add_system(render)
+///     .build();
+///
+/// let mut world = World::default();
+/// let mut resources = Resources::default();
+/// resources.insert(FixedTimesteps::default());
+///
+/// // each frame
+/// resources
+///     .get_mut::<FixedTimesteps>()
+///     .unwrap()
+///     .advance(Duration::from_millis(20));
+/// schedule.execute(&mut world, &mut resources);
+/// ```
+#[derive(Debug)]
+pub struct FixedTimestep {
+    label: StageLabel,
+    step: Duration,
+    max_steps: Option<usize>,
+    schedule: Schedule,
+}
+
+impl FixedTimestep {
+    /// Constructs a new fixed timestep which runs `schedule` once every `step`.
+    ///
+    /// The label identifies the timestep's state in the `FixedTimesteps` resource, and so must be
+    /// unique for each rate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` is zero.
+    pub fn new<L: Into<StageLabel>>(label: L, step: Duration, schedule: Schedule) -> Self {
+        assert!(
+            step > Duration::from_secs(0),
+            "fixed timestep must be non-zero"
+        );
+        Self {
+            label: label.into(),
+            step,
+            max_steps: None,
+            schedule,
+        }
+    }
+
+    /// Limits the number of times the inner schedule may run in a single execution. Any further
+    /// accumulated whole steps are discarded.
+    ///
+    /// This prevents a slow inner schedule from falling ever further behind.
+    pub fn with_max_steps(self, max_steps: usize) -> Self {
+        Self {
+            max_steps: Some(max_steps),
+            ..self
+        }
+    }
+
+    /// Gets the label of the timestep.
+    pub fn label(&self) -> &StageLabel {
+        &self.label
+    }
+
+    /// Gets the duration of each step.
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// Gets the inner schedule.
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+
+    pub(crate) fn run(
+        &mut self,
+        world: &mut World,
+        resources: &mut Resources,
+        run_executor: &mut RunExecutor,
+        profiler: Option<&Profiler>,
+    ) {
+        let iterations = {
+            let mut timesteps = resources.get_mut_or_default::<FixedTimesteps>();
+            let state = timesteps.state_mut(&self.label, self.step);
+
+            let step = self.step.as_nanos();
+            let mut iterations = state.accumulator.as_nanos() / step;
+            let accumulator = state.accumulator.as_nanos() - iterations * step;
+            if let Some(max_steps) = self.max_steps {
+                iterations = iterations.min(max_steps as u128);
+            }
+
+            state.accumulator = Duration::from_nanos(accumulator as u64);
+            state.alpha = accumulator as f64 / step as f64;
+            iterations
+        };
+
+        for _ in 0..iterations {
+            self.schedule
+                .execute_internal(world, resources, run_executor, profiler);
+            self.schedule
+                .flush_command_buffers(world, resources, profiler);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internals::systems::system::SystemBuilder;
+
+    #[test]
+    fn fixed_timestep() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(0usize);
+
+        let physics = Schedule::builder()
+            .add_system(SystemBuilder::new("count").write_resource::<usize>().build(
+                |cmd, _, count, _| {
+                    **count += 1;
+                    cmd.push((**count,));
+                },
+            ))
+            .build();
+        let mut schedule = Schedule::builder()
+            .add_fixed_timestep(
+                FixedTimestep::new("physics", Duration::from_millis(10), physics).with_max_steps(3),
+            )
+            .build();
+
+        let mut advance = |resources: &mut Resources, millis| {
+            resources
+                .get_mut_or_default::<FixedTimesteps>()
+                .advance(Duration::from_millis(millis));
+        };
+
+        advance(&mut resources, 25);
+        schedule.execute(&mut world, &mut resources);
+        assert_eq!(*resources.get::<usize>().unwrap(), 2);
+        assert_eq!(world.len(), 2);
+        let alpha = resources
+            .get::<FixedTimesteps>()
+            .unwrap()
+            .get("physics")
+            .unwrap()
+            .alpha();
+        assert!((alpha - 0.5).abs() < 1e-9);
+
+        advance(&mut resources, 4);
+        schedule.execute(&mut world, &mut resources);
+        assert_eq!(*resources.get::<usize>().unwrap(), 2);
+
+        // excess steps beyond the limit are discarded
+        advance(&mut resources, 100);
+        schedule.execute(&mut world, &mut resources);
+        assert_eq!(*resources.get::<usize>().unwrap(), 5);
+        let state = resources.get::<FixedTimesteps>().unwrap();
+        assert_eq!(
+            state.get("physics").unwrap().accumulator(),
+            Duration::from_millis(9)
+        );
+    }
+}
@@ -1,23 +1,26 @@
 //! Contains types related to the [World](struct.World.html) entity collection.
 
 use super::entity::{
-    Allocate, Entity, EntityHasher, EntityLocation, LocationMap, ID_CLONE_MAPPINGS,
+    Allocate, Entity, EntityAllocation, EntityHasher, EntityLocation, EntityRange, IdSource,
+    LocationMap, ID_CLONE_MAPPINGS,
 };
 use super::insert::{ArchetypeSource, ArchetypeWriter, ComponentSource, IntoComponentSource};
 use super::{
-    entry::{Entry, EntryMut, EntryRef},
-    event::{EventSender, Subscriber, Subscribers},
+    entry::{DynamicArchetype, Entry, EntryMut, EntryRef},
+    event::{Event, EventSender, RemovalKind, Subscriber, Subscribers},
+    hierarchy::{Children, Parent},
     query::{
         filter::{EntityFilter, LayoutFilter},
         view::{IntoView, View},
         Query,
     },
+    relation::{has_relations, RelationCache},
     storage::{
         archetype::{Archetype, ArchetypeIndex, EntityLayout},
         component::{Component, ComponentTypeId},
         group::{Group, GroupDef},
         index::SearchIndex,
-        ComponentIndex, Components, PackOptions, UnknownComponentStorage,
+        ComponentIndex, ComponentStorage, Components, PackOptions, UnknownComponentStorage,
     },
     subworld::{ComponentAccess, SubWorld},
 };
@@ -83,6 +86,8 @@ pub struct WorldOptions {
     /// A vector of component [groups](../storage/struct.Group.html) to provide
     /// layout hints for query optimization.
     pub groups: Vec<GroupDef>,
+    /// How the world allocates the IDs of new entities.
+    pub entity_allocation: EntityAllocation,
 }
 
 /// A container of entities.
@@ -100,8 +105,13 @@ pub struct World {
     group_members: HashMap<ComponentTypeId, usize>,
     archetypes: Vec<Archetype>,
     entities: LocationMap,
+    ids: IdSource,
     allocation_buffer: Vec<Entity>,
     subscribers: Subscribers,
+    removed: HashMap<ComponentTypeId, Vec<Entity>>,
+    relations: RelationCache,
+    // an empty world in which entities are staged while they are cloned within this world
+    staging: Option<Box<World>>,
 }
 
 impl Default for World {
@@ -128,6 +138,7 @@ impl World {
             }
         }
 
+        let ids = IdSource::new(options.entity_allocation);
         Self {
             id: WorldId::next(),
             index: SearchIndex::default(),
@@ -135,9 +146,13 @@ impl World {
             groups,
             group_members,
             archetypes: Vec::default(),
-            entities: LocationMap::default(),
+            entities: LocationMap::new(&ids),
+            ids,
             allocation_buffer: Vec::default(),
             subscribers: Subscribers::default(),
+            removed: HashMap::default(),
+            relations: RelationCache::default(),
+            staging: None,
         }
     }
 
@@ -161,6 +176,83 @@ impl World {
         self.entities.contains(entity)
     }
 
+    /// Returns `true` if the entity has been removed from the world and its ID has been
+    /// invalidated, distinguishing stale IDs from those which were never allocated.
+    ///
+    /// Staleness can only be detected in worlds which use
+    /// [generational](enum.EntityAllocation.html#variant.Generational) entity allocation. This
+    /// function always returns `false` for other worlds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # use legion::world::{EntityAllocation, WorldOptions};
+    /// let mut world = World::new(WorldOptions {
+    ///     entity_allocation: EntityAllocation::Generational,
+    ///     ..Default::default()
+    /// });
+    /// let entity = world.push((1usize,));
+    /// world.remove(entity);
+    /// assert!(world.is_stale(entity));
+    ///
+    /// // the index of the removed entity is reused with a new generation
+    /// let reused = world.push((2usize,));
+    /// assert_ne!(reused, entity);
+    /// assert!(!world.contains(entity));
+    /// ```
+    pub fn is_stale(&self, entity: Entity) -> bool {
+        self.entities.is_stale(entity)
+    }
+
+    /// Returns an iterator which allocates new entity IDs for this world.
+    pub fn entity_allocator(&self) -> Allocate {
+        Allocate::from_source(&self.ids)
+    }
+
+    // Returns an allocator for a new command buffer.
+    pub(crate) fn command_allocator(&self) -> Allocate {
+        Allocate::from_source(&self.ids.stream())
+    }
+
+    /// Reserves a contiguous range of entity IDs which will never be allocated by this world's
+    /// [allocator](#method.entity_allocator). With global entity allocation, the IDs will not be
+    /// allocated by any world.
+    ///
+    /// The reserved IDs can be used with [push_with_id](#method.push_with_id), or by a
+    /// [CommandBuffer](../systems/struct.CommandBuffer.html#method.allocate_from), without
+    /// colliding with entities which are allocated locally.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// let mut world = World::default();
+    /// let reserved = world.reserve_entities(16);
+    ///
+    /// for (i, entity) in reserved.clone().enumerate() {
+    ///     world.push_with_id(entity, (i,));
+    /// }
+    ///
+    /// // locally allocated IDs never collide with the reserved range
+    /// let local = world.push((16usize,));
+    /// assert!(!reserved.contains(local));
+    /// ```
+    pub fn reserve_entities(&self, count: usize) -> EntityRange {
+        self.ids.reserve(count)
+    }
+
+    /// Prevents this world from allocating any of the IDs in a range which was reserved
+    /// elsewhere, such as by an authoritative server, so that they can be inserted with
+    /// [push_with_id](#method.push_with_id). With global entity allocation, the IDs will not be
+    /// allocated by any world.
+    ///
+    /// Returns `false` if some of the IDs may already have been allocated, in which case they
+    /// may collide with existing entities.
+    pub fn claim_entities(&self, range: &EntityRange) -> bool {
+        self.ids.claim(range)
+    }
+
     /// Appends a named entity to the word, replacing any existing entity with the given ID.
     pub fn push_with_id<T>(&mut self, entity_id: Entity, components: T)
     where
@@ -177,7 +269,9 @@ impl World {
         components.push_components(&mut writer, std::iter::once(entity_id));
 
         let (base, entities) = writer.inserted();
-        self.entities.insert(entities, arch_index, base);
+        let replaced = self.entities.insert(entities, arch_index, base);
+        drop(writer);
+        self.remove_replaced(replaced);
     }
 
     /// Appends a new entity to the world. Returns the ID of the new entity.
@@ -241,23 +335,32 @@ impl World {
             let archetype = &mut self.archetypes[arch_index.0 as usize];
             let mut writer =
                 ArchetypeWriter::new(arch_index, archetype, self.components.get_multi_mut());
-            components.push_components(&mut writer, Allocate::new());
+            components.push_components(&mut writer, Allocate::from_source(&self.ids));
 
             let (base, entities) = writer.inserted();
             self.allocation_buffer.clear();
             self.allocation_buffer.extend_from_slice(entities);
             self.entities.insert(entities, arch_index, base)
         };
-
-        for location in replaced {
-            self.remove_at_location(location);
-        }
+        self.remove_replaced(replaced);
 
         &self.allocation_buffer
     }
 
     /// Removes the specified entity from the world. Returns `true` if an entity was removed.
+    ///
+    /// If the entity has a [parent](../hierarchy/struct.Parent.html), it is removed from its
+    /// parent's children. Its own children become roots; use
+    /// [remove_recursive](#method.remove_recursive) to remove them as well. The entity is also
+    /// removed from both ends of its [relations](../relation/index.html).
     pub fn remove(&mut self, entity: Entity) -> bool {
+        self.detach(entity);
+        self.remove_detached(entity)
+    }
+
+    /// Removes the specified entity and its relations, without updating the hierarchy.
+    pub(crate) fn remove_detached(&mut self, entity: Entity) -> bool {
+        self.remove_relations(entity);
         let location = self.entities.remove(entity);
         if let Some(location) = location {
             self.remove_at_location(location);
@@ -267,18 +370,45 @@ impl World {
         }
     }
 
+    // Removes entities whose locations were replaced by the insertion of an entity with the
+    // same ID, such as a newly allocated ID which was previously inserted explicitly.
+    fn remove_replaced(&mut self, mut replaced: Vec<EntityLocation>) {
+        // removing the last rows of each archetype first keeps the remaining locations valid
+        replaced.sort_by_key(|location| {
+            std::cmp::Reverse((location.archetype().0, location.component()))
+        });
+        for location in replaced {
+            self.remove_at_location(location);
+        }
+    }
+
     fn remove_at_location(&mut self, location: EntityLocation) {
         let EntityLocation(arch_index, component_index) = location;
         let archetype = &mut self.archetypes[arch_index];
-        archetype.swap_remove(component_index.0);
+        let entity = archetype.swap_remove(component_index.0, RemovalKind::Despawned);
         for type_id in archetype.layout().component_types() {
             let storage = self.components.get_mut(*type_id).unwrap();
             storage.swap_remove(arch_index, component_index);
+            if let Some(log) = self.removed.get_mut(type_id) {
+                log.push(entity);
+            }
         }
         if component_index.0 < archetype.entities().len() {
             let swapped = archetype.entities()[component_index.0];
             self.entities.set(swapped, location);
         }
+        self.remove_sparse_all(entity);
+    }
+
+    // Removes all of an entity's sparse components.
+    fn remove_sparse_all(&mut self, entity: Entity) {
+        for (type_id, set) in self.components.iter_sparse_mut() {
+            if set.remove(entity) {
+                if let Some(log) = self.removed.get_mut(&type_id) {
+                    log.push(entity);
+                }
+            }
+        }
     }
 
     /// Removes all entities from the world.
@@ -291,6 +421,323 @@ impl World {
         }
     }
 
+    /// Adds a component to each of the given entities, moving entities which share an
+    /// archetype together rather than one at a time. Entities which already have the component
+    /// have it replaced, and entities which do not exist are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// let mut world = World::default();
+    /// let entities = world.extend(vec![(1usize,), (2usize,), (3usize,)]).to_vec();
+    /// world.add_component_batch(&entities[..2], false);
+    ///
+    /// let entry = world.entry(entities[1]).unwrap();
+    /// assert_eq!(entry.get_component::<bool>(), Ok(&false));
+    /// ```
+    pub fn add_component_batch<T: Component + Clone>(&mut self, entities: &[Entity], component: T) {
+        self.add_components_batch(
+            entities
+                .iter()
+                .map(|entity| (*entity, component.clone()))
+                .collect(),
+        );
+    }
+
+    /// Adds components to entities in a batch. If an entity appears more than once, the last
+    /// component given for it is added.
+    pub(crate) fn add_components_batch<T: Component>(&mut self, components: Vec<(Entity, T)>) {
+        // group the entities which need to move by their source archetype
+        let mut groups: Vec<(ArchetypeIndex, Vec<(ComponentIndex, T)>)> = Vec::new();
+        let mut group_indices = HashMap::new();
+        let mut pending = HashMap::<Entity, (usize, usize), EntityHasher>::default();
+        for (entity, component) in components {
+            if let Some(&(group, i)) = pending.get(&entity) {
+                groups[group].1[i].1 = component;
+                continue;
+            }
+
+            let location = match self.entities.get(entity) {
+                Some(location) => location,
+                None => continue,
+            };
+            if self.archetypes[location.archetype()]
+                .layout()
+                .has_component::<T>()
+            {
+                *self
+                    .entry(entity)
+                    .unwrap()
+                    .get_component_mut::<T>()
+                    .unwrap() = component;
+                continue;
+            }
+
+            let group = *group_indices
+                .entry(location.archetype())
+                .or_insert_with(|| {
+                    groups.push((location.archetype(), Vec::new()));
+                    groups.len() - 1
+                });
+            let (_, group_components) = &mut groups[group];
+            group_components.push((location.component(), component));
+            pending.insert(entity, (group, group_components.len() - 1));
+        }
+
+        for (source, mut components) in groups {
+            let target = {
+                let mut archetype = DynamicArchetype {
+                    base: self.archetypes[source].layout().clone(),
+                    add: &[ComponentTypeId::of::<T>()],
+                    add_constructors: &[|| Box::new(T::Storage::default())],
+                    remove: &[],
+                };
+                self.get_archetype_for_components(&mut archetype)
+            };
+
+            components.sort_by_key(|(ComponentIndex(index), _)| std::cmp::Reverse(*index));
+            let (indices, mut components): (Vec<_>, Vec<_>) = components.into_iter().unzip();
+            unsafe {
+                self.transfer_batch(source, target, &indices);
+                self.components
+                    .get_downcast_mut::<T>()
+                    .unwrap()
+                    .extend_memcopy(target, components.as_ptr(), components.len());
+                // the components have been copied into the storage, so must not be dropped
+                components.set_len(0);
+            }
+        }
+    }
+
+    /// Removes a component from each of the given entities, moving entities which share an
+    /// archetype together rather than one at a time. Entities which do not have the component,
+    /// or do not exist, are ignored.
+    pub fn remove_component_batch<T: Component>(&mut self, entities: &[Entity]) {
+        let mut groups = HashMap::<_, Vec<_>>::new();
+        for entity in entities {
+            if let Some(location) = self.entities.get(*entity) {
+                if self.archetypes[location.archetype()]
+                    .layout()
+                    .has_component::<T>()
+                {
+                    groups
+                        .entry(location.archetype())
+                        .or_default()
+                        .push(location.component());
+                }
+            }
+        }
+
+        for (source, mut indices) in groups {
+            let target = {
+                let mut archetype = DynamicArchetype {
+                    base: self.archetypes[source].layout().clone(),
+                    add: &[],
+                    add_constructors: &[],
+                    remove: &[ComponentTypeId::of::<T>()],
+                };
+                self.get_archetype_for_components(&mut archetype)
+            };
+
+            indices.sort_by_key(|ComponentIndex(index)| std::cmp::Reverse(*index));
+            indices.dedup();
+            unsafe { self.transfer_batch(source, target, &indices) };
+        }
+    }
+
+    /// Removes all entities which match the given [filter](../query/trait.LayoutFilter.html),
+    /// dropping the components of each matching archetype together rather than removing
+    /// entities one at a time. Returns the number of entities removed.
+    ///
+    /// As with [remove](#method.remove), the entities are removed from the hierarchy and from
+    /// their relations. Entities which have such links are removed individually.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # struct Bullet;
+    /// let mut world = World::default();
+    /// world.extend(vec![(Bullet, 1usize), (Bullet, 2usize)]);
+    /// let player = world.push((3usize,));
+    ///
+    /// assert_eq!(world.remove_matching(&component::<Bullet>()), 2);
+    /// assert_eq!(world.len(), 1);
+    /// assert!(world.contains(player));
+    /// ```
+    pub fn remove_matching<F: LayoutFilter>(&mut self, filter: &F) -> usize {
+        let mut removed = 0;
+
+        // unlinking an entity may move other entities between archetypes, so each linked
+        // entity is checked against the filter again before it is removed
+        loop {
+            let linked = self
+                .archetypes
+                .iter()
+                .filter(|arch| Self::matches(filter, arch) && self.is_linked(arch.layout()))
+                .flat_map(|arch| arch.entities().iter().copied())
+                .collect::<Vec<_>>();
+            if linked.is_empty() {
+                break;
+            }
+            for entity in linked {
+                if let Some(location) = self.entities.get(entity) {
+                    if Self::matches(filter, &self.archetypes[location.archetype()]) {
+                        self.remove(entity);
+                        removed += 1;
+                    }
+                }
+            }
+        }
+
+        let mut drained = Vec::new();
+        for archetype in self
+            .archetypes
+            .iter_mut()
+            .filter(|arch| Self::matches(filter, arch))
+        {
+            let entities = archetype.drain();
+            for type_id in archetype.layout().component_types() {
+                let storage = self.components.get_mut(*type_id).unwrap();
+                storage.clear_archetype(archetype.index());
+                if let Some(log) = self.removed.get_mut(type_id) {
+                    log.extend_from_slice(&entities);
+                }
+            }
+            for entity in &entities {
+                self.entities.remove(*entity);
+            }
+            removed += entities.len();
+            drained.extend(entities);
+        }
+
+        for entity in drained {
+            self.remove_sparse_all(entity);
+        }
+
+        removed
+    }
+
+    /// Removes a component from all entities which match the given
+    /// [filter](../query/trait.LayoutFilter.html), moving the entities of each matching
+    /// archetype together rather than one at a time. Returns the number of entities which had
+    /// the component removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// let mut world = World::default();
+    /// world.extend(vec![(1usize, false), (2usize, false)]);
+    /// world.push((3usize, 0.5f32));
+    ///
+    /// assert_eq!(world.remove_component_matching::<usize>(&component::<bool>()), 2);
+    /// let mut query = <&usize>::query();
+    /// assert_eq!(query.iter(&world).collect::<Vec<_>>(), vec![&3usize]);
+    /// ```
+    pub fn remove_component_matching<T: Component>(&mut self, filter: &impl LayoutFilter) -> usize {
+        let sources = self
+            .archetypes
+            .iter()
+            .filter(|arch| arch.layout().has_component::<T>() && Self::matches(filter, arch))
+            .map(|arch| arch.index())
+            .collect::<Vec<_>>();
+
+        let mut removed = 0;
+        for source in sources {
+            let target = {
+                let mut archetype = DynamicArchetype {
+                    base: self.archetypes[source].layout().clone(),
+                    add: &[],
+                    add_constructors: &[],
+                    remove: &[ComponentTypeId::of::<T>()],
+                };
+                self.get_archetype_for_components(&mut archetype)
+            };
+
+            // removing from the back of the archetype avoids swapping entities within it
+            let indices = (0..self.archetypes[source].entities().len())
+                .rev()
+                .map(ComponentIndex)
+                .collect::<Vec<_>>();
+            if !indices.is_empty() {
+                unsafe { self.transfer_batch(source, target, &indices) };
+                removed += indices.len();
+            }
+        }
+
+        removed
+    }
+
+    /// Adds a component to an entity in [sparse storage](../storage/struct.SparseSet.html),
+    /// returning the entity's previous sparse component of the same type.
+    ///
+    /// Sparse components are not part of the entity's layout, so adding and removing them does
+    /// not move the entity between archetypes. They are read by queries through the
+    /// [TryReadSparse](../query/struct.TryReadSparse.html) and
+    /// [TryWriteSparse](../query/struct.TryWriteSparse.html) views.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// struct Selected;
+    ///
+    /// let mut world = World::default();
+    /// let entity = world.push((1usize,));
+    /// world.insert_sparse(entity, Selected).unwrap();
+    /// assert!(world.get_sparse::<Selected>(entity).is_some());
+    ///
+    /// world.remove_sparse::<Selected>(entity);
+    /// assert!(world.get_sparse::<Selected>(entity).is_none());
+    /// ```
+    pub fn insert_sparse<T: Component>(
+        &mut self,
+        entity: Entity,
+        component: T,
+    ) -> Result<Option<T>, EntityAccessError> {
+        if !self.contains(entity) {
+            return Err(EntityAccessError::EntityNotFound);
+        }
+        Ok(self
+            .components
+            .get_sparse_or_insert::<T>()
+            .insert(entity, component))
+    }
+
+    /// Removes a component from an entity in sparse storage, returning the component.
+    pub fn remove_sparse<T: Component>(&mut self, entity: Entity) -> Option<T> {
+        let component = self.components.get_sparse_mut::<T>()?.remove(entity)?;
+        if let Some(log) = self.removed.get_mut(&ComponentTypeId::of::<T>()) {
+            log.push(entity);
+        }
+        Some(component)
+    }
+
+    /// Returns an entity's component in sparse storage.
+    pub fn get_sparse<T: Component>(&self, entity: Entity) -> Option<&T> {
+        self.components.get_sparse::<T>()?.get(entity)
+    }
+
+    /// Returns an entity's component in sparse storage mutably.
+    pub fn get_sparse_mut<T: Component>(&mut self, entity: Entity) -> Option<&mut T> {
+        self.components.get_sparse_mut::<T>()?.get_mut(entity)
+    }
+
+    fn matches<F: LayoutFilter>(filter: &F, archetype: &Archetype) -> bool {
+        filter
+            .matches_layout(archetype.layout().component_types())
+            .is_pass()
+    }
+
+    // Returns `true` if entities with the given layout are part of the hierarchy or a relation.
+    fn is_linked(&self, layout: &EntityLayout) -> bool {
+        layout.has_component::<Parent>()
+            || layout.has_component::<Children>()
+            || has_relations(layout)
+    }
+
     /// Gets an [entry](struct.Entry.html) for an entity, allowing manipulation of the
     /// entity.
     ///
@@ -343,6 +790,48 @@ impl World {
         self.subscribers.push(subscriber);
     }
 
+    /// Begins recording the entities which lose a component of type `T`, either because the
+    /// component was removed from the entity or because the entity was removed from the world.
+    ///
+    /// Recorded entities can be retrieved with [removed](#method.removed). The log is not
+    /// cleared automatically; call [clear_removed](#method.clear_removed) once it has been
+    /// processed.
+    pub fn track_removed<T: Component>(&mut self) {
+        self.removed.entry(ComponentTypeId::of::<T>()).or_default();
+    }
+
+    /// Returns the entities which have lost a component of type `T` since the log was last
+    /// cleared. Returns an empty slice if removals of `T` are not being
+    /// [tracked](#method.track_removed).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// let mut world = World::default();
+    /// world.track_removed::<usize>();
+    ///
+    /// let entity = world.push((1usize, false));
+    /// world.remove(entity);
+    /// assert_eq!(world.removed::<usize>(), &[entity]);
+    ///
+    /// world.clear_removed();
+    /// assert!(world.removed::<usize>().is_empty());
+    /// ```
+    pub fn removed<T: Component>(&self) -> &[Entity] {
+        self.removed
+            .get(&ComponentTypeId::of::<T>())
+            .map(|log| log.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Clears the removal logs of all tracked component types.
+    pub fn clear_removed(&mut self) {
+        for log in self.removed.values_mut() {
+            log.clear();
+        }
+    }
+
     /// Packs the world's internal component storage to optimise iteration performance for
     /// [queries](../query/index.html) which match a [group](../storage/struct.Group.html)
     /// defined when this world was created.
@@ -363,6 +852,15 @@ impl World {
         &self.archetypes
     }
 
+    /// Returns the world's relation cache, along with the archetype of the given entity.
+    pub(crate) fn relation_cache(
+        &mut self,
+        entity: Entity,
+    ) -> Option<(&mut RelationCache, &Archetype)> {
+        let location = self.entities.get(entity)?;
+        Some((&mut self.relations, &self.archetypes[location.archetype()]))
+    }
+
     pub(crate) unsafe fn transfer_archetype(
         &mut self,
         ArchetypeIndex(from): ArchetypeIndex,
@@ -383,7 +881,7 @@ impl World {
         };
 
         // move entity ID
-        let entity = from_arch.swap_remove(idx);
+        let entity = from_arch.swap_remove(idx, RemovalKind::Migrated);
         to_arch.push(entity);
         self.entities.set(
             entity,
@@ -400,6 +898,32 @@ impl World {
             );
         }
 
+        // notify subscribers of either archetype
+        let mut subscribers = from_arch.subscribers().union(to_arch.subscribers());
+        if !subscribers.is_empty() {
+            subscribers.send(Event::EntityMoved {
+                entity,
+                from: ArchetypeIndex(from),
+                to: ArchetypeIndex(to),
+            });
+            for component in to_arch.layout().component_types() {
+                if !from_arch.layout().has_component_by_id(*component) {
+                    subscribers.send(Event::ComponentAdded {
+                        entity,
+                        component: *component,
+                    });
+                }
+            }
+            for component in from_arch.layout().component_types() {
+                if !to_arch.layout().has_component_by_id(*component) {
+                    subscribers.send(Event::ComponentRemoved {
+                        entity,
+                        component: *component,
+                    });
+                }
+            }
+        }
+
         // move components
         let from_layout = from_arch.layout();
         let to_layout = to_arch.layout();
@@ -413,12 +937,108 @@ impl World {
                 );
             } else {
                 storage.swap_remove(ArchetypeIndex(from), ComponentIndex(idx));
+                if let Some(log) = self.removed.get_mut(type_id) {
+                    log.push(entity);
+                }
             }
         }
 
         ComponentIndex(to_arch.entities().len() - 1)
     }
 
+    /// Moves a batch of entities from one archetype to another, with a single move per
+    /// component storage. The component indices must be sorted in descending order, and the
+    /// entities are appended to the destination archetype in that order.
+    ///
+    /// Returns the index of the first moved entity in the destination archetype.
+    ///
+    /// # Safety
+    /// The caller must ensure that components which are in the destination archetype but not
+    /// the source archetype are pushed into their storage for each moved entity, in order.
+    unsafe fn transfer_batch(
+        &mut self,
+        ArchetypeIndex(from): ArchetypeIndex,
+        ArchetypeIndex(to): ArchetypeIndex,
+        indices: &[ComponentIndex],
+    ) -> ComponentIndex {
+        debug_assert_ne!(from, to);
+
+        // find archetypes
+        let (from_arch, to_arch) = if from < to {
+            let (a, b) = self.archetypes.split_at_mut(to as usize);
+            (&mut a[from as usize], &mut b[0])
+        } else {
+            let (a, b) = self.archetypes.split_at_mut(from as usize);
+            (&mut b[0], &mut a[to as usize])
+        };
+
+        // move entity IDs
+        let mut moved = Vec::with_capacity(indices.len());
+        for ComponentIndex(idx) in indices {
+            moved.push(from_arch.swap_remove(*idx, RemovalKind::Migrated));
+            if from_arch.entities().len() > *idx {
+                let swapped = from_arch.entities()[*idx];
+                self.entities.set(
+                    swapped,
+                    EntityLocation::new(ArchetypeIndex(from), ComponentIndex(*idx)),
+                );
+            }
+        }
+        let base = ComponentIndex(to_arch.entities().len());
+        to_arch.reserve(moved.len());
+        for entity in &moved {
+            to_arch.push(*entity);
+        }
+        self.entities.insert(&moved, ArchetypeIndex(to), base);
+
+        // notify subscribers of either archetype
+        let mut subscribers = from_arch.subscribers().union(to_arch.subscribers());
+        if !subscribers.is_empty() {
+            for entity in &moved {
+                subscribers.send(Event::EntityMoved {
+                    entity: *entity,
+                    from: ArchetypeIndex(from),
+                    to: ArchetypeIndex(to),
+                });
+                for component in to_arch.layout().component_types() {
+                    if !from_arch.layout().has_component_by_id(*component) {
+                        subscribers.send(Event::ComponentAdded {
+                            entity: *entity,
+                            component: *component,
+                        });
+                    }
+                }
+                for component in from_arch.layout().component_types() {
+                    if !to_arch.layout().has_component_by_id(*component) {
+                        subscribers.send(Event::ComponentRemoved {
+                            entity: *entity,
+                            component: *component,
+                        });
+                    }
+                }
+            }
+        }
+
+        // move components
+        let from_layout = from_arch.layout();
+        let to_layout = to_arch.layout();
+        for type_id in from_layout.component_types() {
+            let storage = self.components.get_mut(*type_id).unwrap();
+            if to_layout.component_types().contains(type_id) {
+                storage.move_components(ArchetypeIndex(from), indices, ArchetypeIndex(to));
+            } else {
+                for index in indices {
+                    storage.swap_remove(ArchetypeIndex(from), *index);
+                }
+                if let Some(log) = self.removed.get_mut(type_id) {
+                    log.extend_from_slice(&moved);
+                }
+            }
+        }
+
+        base
+    }
+
     pub(crate) fn get_archetype_for_components<T: ArchetypeSource>(
         &mut self,
         components: &mut T,
@@ -571,13 +1191,23 @@ impl World {
                 dst_storage.move_archetype_from(src_arch.index(), src_storage);
             }
 
-            for entity in src_arch.drain() {
-                source.entities.remove(entity);
+            let moved = src_arch.drain();
+            for entity in &moved {
+                source.entities.remove(*entity);
             }
 
             // record entity locations
             let (base, entities) = writer.inserted();
-            self.entities.insert(entities, dst_arch_index, base);
+            let replaced = self.entities.insert(entities, dst_arch_index, base);
+            drop(writer);
+            self.remove_replaced(replaced);
+
+            // move sparse components along with their entities
+            for (_, set) in source.components.iter_sparse_mut() {
+                for entity in &moved {
+                    set.transfer(*entity, &mut self.components);
+                }
+            }
         }
     }
 
@@ -613,7 +1243,7 @@ impl World {
         filter: &F,
         merger: &mut M,
     ) -> HashMap<Entity, Entity, EntityHasher> {
-        let mut allocator = Allocate::new();
+        let mut allocator = self.entity_allocator();
         let mut reallocated = HashMap::default();
 
         // assign destination IDs
@@ -688,7 +1318,9 @@ impl World {
 
             // record entity locations
             let (base, entities) = writer.inserted();
-            self.entities.insert(entities, dst_arch_index, base);
+            let replaced = self.entities.insert(entities, dst_arch_index, base);
+            drop(writer);
+            self.remove_replaced(replaced);
         }
 
         reallocated.unwrap_or_else(|| {
@@ -708,7 +1340,7 @@ impl World {
         merger: &mut M,
     ) -> Entity {
         // determine the destination ID
-        let mut allocator = Allocate::new();
+        let mut allocator = self.entity_allocator();
         let dst_entity = merger.assign_id(entity, &mut allocator);
 
         // find conflicts, and remove the existing entity, to be replaced with that defined in the source
@@ -725,7 +1357,8 @@ impl World {
         let layout = merger.convert_layout((**src_arch.layout()).clone());
 
         // find or construct the destination archetype
-        let dst_arch_index = self.insert_archetype(layout);
+        let dst_arch_index = self.index.search(&layout).next();
+        let dst_arch_index = dst_arch_index.unwrap_or_else(|| self.insert_archetype(layout));
         let dst_arch = &mut self.archetypes[dst_arch_index.0 as usize];
 
         // build a writer for the destination archetype
@@ -764,7 +1397,9 @@ impl World {
 
         // record entity location
         let (base, entities) = writer.inserted();
-        self.entities.insert(entities, dst_arch_index, base);
+        let replaced = self.entities.insert(entities, dst_arch_index, base);
+        drop(writer);
+        self.remove_replaced(replaced);
 
         ID_CLONE_MAPPINGS.with(|cell| {
             cell.borrow_mut().clear();
@@ -773,6 +1408,151 @@ impl World {
         dst_entity
     }
 
+    /// Clones an entity within this world, returning the ID of the clone.
+    ///
+    /// A [merger](trait.Merger.html), such as [Duplicate](struct.Duplicate.html), describes how
+    /// each component is cloned. References to the entity inside its cloned components are
+    /// rewritten to refer to the clone, as are any other references given by the merger's
+    /// [entity map](trait.Merger.html#method.entity_map).
+    ///
+    /// If the merger clones the entity's [Parent](../hierarchy/struct.Parent.html), the clone
+    /// is added to the children of that parent. Likewise, a cloned
+    /// [Relation](../relation/struct.Relation.html) relates the clone to each of its targets.
+    /// The clone does not take the entity's children, nor the entities related to it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the entity does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::*;
+    /// # use legion::world::Duplicate;
+    /// let mut world = World::default();
+    /// let entity = world.push((1usize, false));
+    ///
+    /// let mut merger = Duplicate::default();
+    /// merger.register_copy::<usize>();
+    /// let clone = world.clone_entity(entity, &mut merger);
+    ///
+    /// let entry = world.entry(clone).unwrap();
+    /// assert_eq!(entry.get_component::<usize>(), Ok(&1usize));
+    /// assert!(entry.get_component::<bool>().is_err());
+    /// ```
+    pub fn clone_entity<M: Merger>(&mut self, entity: Entity, merger: &mut M) -> Entity {
+        let clone = self.entity_allocator().next().unwrap();
+        self.clone_entity_as(entity, clone, merger);
+        clone
+    }
+
+    /// Clones an entity within this world, giving the clone the specified ID.
+    pub(crate) fn clone_entity_as<M: Merger>(
+        &mut self,
+        entity: Entity,
+        clone: Entity,
+        merger: &mut M,
+    ) {
+        // the clone cannot be written into storage while it is being read from, so it is
+        // staged in a separate world before being moved into this one; the staging world is
+        // kept so that its archetypes and storage are reused by later clones
+        let mut ids = HashMap::default();
+        ids.insert(entity, clone);
+        let mut staging = self.staging.take().unwrap_or_default();
+        staging.clone_from_single(self, entity, &mut AssignIds { merger, ids: &ids });
+
+        // only the clone's archetype needs to be moved
+        let location = staging.entities.get(clone).unwrap();
+        let layout = staging.archetypes[location.archetype()].layout().clone();
+        let filter = DynamicArchetype {
+            base: layout,
+            add: &[],
+            add_constructors: &[],
+            remove: &[],
+        };
+        self.move_from(&mut staging, &filter);
+        self.staging = Some(staging);
+        self.relink_hierarchy(clone);
+        self.relink_relations(clone);
+    }
+
+    /// Moves the components of each entity in `source` which also exists in this world into
+    /// that entity, replacing its components of the same types. Components for which `keep`
+    /// returns `true` are left unchanged and dropped from the source. Entities which do not
+    /// exist in this world are left in the source.
+    pub(crate) fn replace_components_from<K>(&mut self, source: &mut World, keep: K)
+    where
+        K: Fn(Entity, ComponentTypeId) -> bool,
+    {
+        let entities = source
+            .archetypes
+            .iter()
+            .flat_map(|arch| arch.entities().iter().copied())
+            .filter(|entity| self.contains(*entity))
+            .collect::<Vec<_>>();
+
+        for entity in entities {
+            let src = source.entities.get(entity).unwrap();
+            let dst = self.entities.get(entity).unwrap();
+            let src_layout = source.archetypes[src.archetype()].layout().clone();
+            let dst_layout = self.archetypes[dst.archetype()].layout().clone();
+
+            let mut add = Vec::new();
+            let mut add_constructors = Vec::new();
+            for (type_id, constructor) in src_layout
+                .component_types()
+                .iter()
+                .zip(src_layout.component_constructors())
+            {
+                let src_storage = source.components.get_mut(*type_id).unwrap();
+                if keep(entity, *type_id) {
+                    src_storage.swap_remove(src.archetype(), src.component());
+                } else if dst_layout.has_component_by_id(*type_id) {
+                    // appending the new component and then swap removing the old one moves the
+                    // new component into the old one's place
+                    let dst_storage = self.components.get_mut(*type_id).unwrap();
+                    src_storage.transfer_component(
+                        src.archetype(),
+                        src.component(),
+                        dst.archetype(),
+                        dst_storage,
+                    );
+                    dst_storage.swap_remove(dst.archetype(), dst.component());
+                } else {
+                    add.push(*type_id);
+                    add_constructors.push(*constructor);
+                }
+            }
+
+            if !add.is_empty() {
+                let target = self.get_archetype_for_components(&mut DynamicArchetype {
+                    base: dst_layout,
+                    add: &add,
+                    add_constructors: &add_constructors,
+                    remove: &[],
+                });
+                unsafe { self.transfer_archetype(dst.archetype(), target, dst.component()) };
+                for type_id in &add {
+                    let src_storage = source.components.get_mut(*type_id).unwrap();
+                    let dst_storage = self.components.get_mut(*type_id).unwrap();
+                    src_storage.transfer_component(
+                        src.archetype(),
+                        src.component(),
+                        target,
+                        dst_storage,
+                    );
+                }
+            }
+
+            let archetype = &mut source.archetypes[src.archetype()];
+            archetype.swap_remove(src.component().0, RemovalKind::Migrated);
+            source.entities.remove(entity);
+            if let Some(swapped) = archetype.entities().get(src.component().0) {
+                source.entities.set(*swapped, src);
+            }
+        }
+    }
+
     /// Creates a serde serializable representation of the world.
     ///
     /// A [filter](../query/trait.LayoutFilter.html) selects which entities shall be serialized.  
@@ -986,6 +1766,7 @@ pub trait Merger {
 
 /// Describes how a merger wishes `Entity` references inside cloned components to be
 /// rewritten.
+#[derive(Clone)]
 pub enum EntityRewrite {
     /// Replace references to entities which have been cloned with the ID of their clone.
     /// May also provide a map of additional IDs to replace.
@@ -1020,6 +1801,7 @@ pub struct Duplicate {
             >,
         ),
     >,
+    entity_map: EntityRewrite,
 }
 
 impl Duplicate {
@@ -1028,6 +1810,12 @@ impl Duplicate {
         Self::default()
     }
 
+    /// Sets how `Entity` references inside cloned components are rewritten. By default,
+    /// references to entities which have been cloned are replaced with the ID of their clone.
+    pub fn set_entity_map(&mut self, entity_map: EntityRewrite) {
+        self.entity_map = entity_map;
+    }
+
     /// Allows the merger to copy the given component into the destination world.
     pub fn register_copy<T: Component + Copy>(&mut self) {
         use crate::internals::storage::ComponentStorage;
@@ -1112,6 +1900,10 @@ impl Duplicate {
 }
 
 impl Merger for Duplicate {
+    fn entity_map(&mut self) -> EntityRewrite {
+        self.entity_map.clone()
+    }
+
     fn convert_layout(&mut self, source_layout: EntityLayout) -> EntityLayout {
         let mut layout = EntityLayout::new();
         for src_type in source_layout.component_types() {
@@ -1139,6 +1931,41 @@ impl Merger for Duplicate {
     }
 }
 
+// Wraps a merger to give each cloned entity a predetermined ID.
+pub(crate) struct AssignIds<'a, M: Merger> {
+    pub(crate) merger: &'a mut M,
+    pub(crate) ids: &'a HashMap<Entity, Entity, EntityHasher>,
+}
+
+impl<'a, M: Merger> Merger for AssignIds<'a, M> {
+    fn prefers_new_archetype() -> bool {
+        M::prefers_new_archetype()
+    }
+
+    fn entity_map(&mut self) -> EntityRewrite {
+        self.merger.entity_map()
+    }
+
+    fn assign_id(&mut self, existing: Entity, _: &mut Allocate) -> Entity {
+        self.ids[&existing]
+    }
+
+    fn convert_layout(&mut self, source_layout: EntityLayout) -> EntityLayout {
+        self.merger.convert_layout(source_layout)
+    }
+
+    fn merge_archetype(
+        &mut self,
+        src_entity_range: Range<usize>,
+        src_arch: &Archetype,
+        src_components: &Components,
+        dst: &mut ArchetypeWriter,
+    ) {
+        self.merger
+            .merge_archetype(src_entity_range, src_arch, src_components, dst)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1223,6 +2050,527 @@ mod test {
         assert_eq!(world.len(), 2);
     }
 
+    #[test]
+    fn generational_ids() {
+        use crate::internals::systems::{command::CommandBuffer, resources::Resources};
+
+        let mut world = World::new(WorldOptions {
+            entity_allocation: EntityAllocation::Generational,
+            ..Default::default()
+        });
+        let entities = world.extend(vec![(1usize,), (2usize,), (3usize,)]).to_vec();
+        assert!(world.remove(entities[1]));
+        assert!(world.is_stale(entities[1]));
+        assert!(!world.is_stale(entities[0]));
+
+        // the removed index is reused with a new generation
+        let reused = world.push((4usize,));
+        assert_ne!(reused, entities[1]);
+        assert!(!world.contains(entities[1]));
+        assert!(world.entry(entities[1]).is_none());
+        assert_eq!(
+            world.entry(reused).unwrap().get_component::<usize>(),
+            Ok(&4usize)
+        );
+
+        let mut cmd = CommandBuffer::new(&world);
+        let pushed = cmd.push((5usize,));
+        cmd.flush(&mut world, &mut Resources::default());
+        assert_eq!(
+            world.entry(pushed).unwrap().get_component::<usize>(),
+            Ok(&5usize)
+        );
+
+        // entities from other worlds claim their index
+        let mut other = World::default();
+        let moved = other.push((6usize,));
+        world.move_from(&mut other, &any());
+        assert_eq!(world.len(), 5);
+        let allocated = world.extend(vec![(7usize,); 8]).to_vec();
+        assert!(!allocated.contains(&moved));
+        assert_eq!(world.len(), 13);
+        assert!(world.contains(moved));
+    }
+
+    #[test]
+    fn generational_collisions() {
+        use crate::internals::query::IntoQuery;
+
+        let mut world = World::new(WorldOptions {
+            entity_allocation: EntityAllocation::Generational,
+            ..Default::default()
+        });
+        let old = world.push((1usize,));
+        let rows = |world: &World| {
+            let mut query = <(Entity, &usize)>::query();
+            query
+                .iter(world)
+                .map(|(entity, value)| (*entity, *value))
+                .collect::<Vec<_>>()
+        };
+
+        // an entity with the same index but another generation is stored alongside the
+        // existing entity
+        let newer = Entity::from_parts(old.index(), old.generation() + 1);
+        let mut other = World::default();
+        other.push_with_id(newer, (2usize,));
+        world.move_from(&mut other, &any());
+        assert_eq!(world.len(), 2);
+        assert!(world.contains(old));
+        assert!(!world.is_stale(newer));
+        assert_eq!(rows(&world), vec![(old, 1), (newer, 2)]);
+
+        let newest = Entity::from_parts(old.index(), old.generation() + 2);
+        world.push_with_id(newest, (3usize,));
+        assert_eq!(world.len(), 3);
+        assert!(world.contains(newer));
+        assert_eq!(rows(&world), vec![(old, 1), (newer, 2), (newest, 3)]);
+
+        let mut merger = Duplicate::default();
+        merger.register_copy::<usize>();
+        let mut other = World::default();
+        let source = other.push((4usize,));
+        world.push((5usize,));
+        let latest = Entity::from_parts(old.index(), old.generation() + 3);
+        let ids = Some((source, latest)).into_iter().collect();
+        world.clone_from_single(
+            &other,
+            source,
+            &mut AssignIds {
+                merger: &mut merger,
+                ids: &ids,
+            },
+        );
+        assert_eq!(world.len(), 5);
+        assert!(world.contains(newest));
+        assert_eq!(world.entry(latest).unwrap().get_component(), Ok(&4usize));
+
+        // removing the entity which uses the index does not affect the others, and the
+        // world does not allocate their IDs
+        assert!(world.remove(old));
+        assert!(world.contains(newer));
+        let allocated = world.extend(vec![(6usize,); 4]).to_vec();
+        assert!(!allocated.contains(&newer));
+        assert!(!allocated.contains(&newest));
+        assert!(!allocated.contains(&latest));
+        assert_eq!(world.len(), 8);
+
+        assert!(world.remove(newer));
+        assert!(world.is_stale(newer));
+        assert_eq!(world.len(), 7);
+    }
+
+    #[test]
+    fn generational_large_index() {
+        let mut world = World::new(WorldOptions {
+            entity_allocation: EntityAllocation::Generational,
+            ..Default::default()
+        });
+        world.push((1usize,));
+
+        // an index far beyond those allocated is not stored densely
+        let distant = Entity::from_parts(u32::MAX - 1, 0);
+        world.push_with_id(distant, (2usize,));
+        assert_eq!(world.entry(distant).unwrap().get_component(), Ok(&2usize));
+
+        let reserved = world.reserve_entities(4);
+        assert!(!reserved.contains(distant));
+        assert!(world.remove(distant));
+        assert!(!world.contains(distant));
+        assert_eq!(world.len(), 1);
+    }
+
+    #[test]
+    fn deterministic_ids() {
+        use crate::internals::systems::{command::CommandBuffer, resources::Resources};
+
+        let record = || {
+            let mut world = World::new(WorldOptions {
+                entity_allocation: EntityAllocation::Deterministic,
+                ..Default::default()
+            });
+            let mut entities = world.extend(vec![(1usize,), (2usize,)]).to_vec();
+            world.remove(entities[0]);
+
+            // allocations in other worlds do not affect this world
+            World::default().push((3usize,));
+
+            let mut cmd = CommandBuffer::new(&world);
+            entities.push(cmd.push((4usize,)));
+            cmd.flush(&mut world, &mut Resources::default());
+            entities.push(world.push((5usize,)));
+            entities
+        };
+
+        let entities = record();
+        assert_eq!(entities, record());
+        assert_eq!(
+            entities
+                .iter()
+                .collect::<std::collections::HashSet<_>>()
+                .len(),
+            4
+        );
+
+        // each command buffer draws from its own range, so buffers recorded concurrently
+        // allocate the same IDs regardless of the order in which they push entities
+        let interleaved = |reverse: bool| {
+            let world = World::new(WorldOptions {
+                entity_allocation: EntityAllocation::Deterministic,
+                ..Default::default()
+            });
+            let mut first = CommandBuffer::new(&world);
+            let mut second = CommandBuffer::new(&world);
+            if reverse {
+                let b = second.push((1usize,));
+                (first.push((0usize,)), b)
+            } else {
+                (first.push((0usize,)), second.push((1usize,)))
+            }
+        };
+        assert_eq!(interleaved(false), interleaved(true));
+    }
+
+    #[test]
+    fn reserve_entities() {
+        for allocation in vec![
+            EntityAllocation::Global,
+            EntityAllocation::Generational,
+            EntityAllocation::Deterministic,
+        ] {
+            let options = || WorldOptions {
+                entity_allocation: allocation,
+                ..Default::default()
+            };
+
+            let mut server = World::new(options());
+            server.push((0usize,));
+            let reserved = server.reserve_entities(20);
+            assert_eq!(reserved.len(), 20);
+            let local = server.extend(vec![(1usize,); 40]).to_vec();
+            assert!(local.iter().all(|entity| !reserved.contains(*entity)));
+
+            // a client claims the server's range before allocating locally
+            let mut client = World::new(options());
+            let claimed = client.claim_entities(&reserved);
+            assert!(claimed || allocation == EntityAllocation::Global);
+            for entity in reserved.clone() {
+                client.push_with_id(entity, (2usize,));
+            }
+            let local = client.extend(vec![(3usize,); 40]).to_vec();
+            assert!(local.iter().all(|entity| !reserved.contains(*entity)));
+            assert_eq!(client.len(), 60);
+
+            if allocation != EntityAllocation::Global {
+                assert!(!client.claim_entities(&reserved));
+            }
+        }
+    }
+
+    #[test]
+    fn component_batch() {
+        let mut world = World::default();
+        world.track_removed::<bool>();
+        let a = world.extend(vec![(1usize,), (2usize,), (3usize,)]).to_vec();
+        let b = world
+            .extend(vec![(4usize, 0.5f32), (5usize, 1.5f32)])
+            .to_vec();
+        let existing = world.push((6usize, true));
+
+        let entities = vec![a[0], b[1], a[2], existing, b[0]];
+        world.add_component_batch(&entities, false);
+        for entity in &entities {
+            let entry = world.entry(*entity).unwrap();
+            assert_eq!(entry.get_component::<bool>(), Ok(&false));
+        }
+        let values = |world: &World, entities: &[Entity]| {
+            entities
+                .iter()
+                .map(|entity| {
+                    *world
+                        .entry_ref(*entity)
+                        .unwrap()
+                        .get_component::<usize>()
+                        .unwrap()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(values(&world, &a), vec![1, 2, 3]);
+        assert_eq!(values(&world, &b), vec![4, 5]);
+        assert_eq!(
+            world.entry(b[1]).unwrap().get_component::<f32>(),
+            Ok(&1.5f32)
+        );
+        assert!(world.entry(a[1]).unwrap().get_component::<bool>().is_err());
+
+        world.remove_component_batch::<bool>(&[a[2], existing, b[1], a[1]]);
+        assert!(world.entry(a[2]).unwrap().get_component::<bool>().is_err());
+        assert!(world.entry(b[1]).unwrap().get_component::<bool>().is_err());
+        assert!(world.entry(a[0]).unwrap().get_component::<bool>().is_ok());
+        assert_eq!(values(&world, &a), vec![1, 2, 3]);
+        assert_eq!(values(&world, &[existing]), vec![6]);
+        assert_eq!(world.removed::<bool>().len(), 3);
+    }
+
+    #[test]
+    #[cfg(feature = "crossbeam-events")]
+    fn remove_matching() {
+        use crate::internals::query::filter::filter_fns::component;
+
+        let mut world = World::default();
+        world.track_removed::<usize>();
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        world.subscribe(sender, component::<bool>());
+
+        let bullets = world.extend(vec![(1usize, true), (2usize, true)]).to_vec();
+        let parent = world.push((3usize,));
+        let child = world.push((4usize, false));
+        world.set_parent(child, parent).unwrap();
+        let other = world.push((5usize, 0.5f32));
+        while receiver.try_recv().is_ok() {}
+
+        assert_eq!(world.remove_matching(&component::<bool>()), 3);
+        assert_eq!(world.len(), 2);
+        assert!(bullets.iter().all(|entity| !world.contains(*entity)));
+        assert!(!world.contains(child));
+        assert!(world
+            .entry(parent)
+            .unwrap()
+            .get_component::<Children>()
+            .is_err());
+        assert!(world.contains(other));
+        assert_eq!(world.removed::<usize>().len(), 3);
+
+        let despawned = receiver
+            .try_iter()
+            .filter(|event| matches!(event, Event::EntityDespawned(_, _)))
+            .count();
+        assert_eq!(despawned, 3);
+
+        let values = world
+            .extend(vec![(6usize, 1.5f32), (7usize, 2.5f32)])
+            .to_vec();
+        assert_eq!(
+            world.remove_component_matching::<f32>(&component::<usize>()),
+            3
+        );
+        assert!(values.iter().chain(Some(&other)).all(|entity| {
+            let entry = world.entry(*entity).unwrap();
+            entry.get_component::<f32>().is_err() && entry.get_component::<usize>().is_ok()
+        }));
+        assert_eq!(
+            world.remove_component_matching::<f32>(&component::<usize>()),
+            0
+        );
+    }
+
+    #[test]
+    fn clone_entity() {
+        #[derive(Debug, PartialEq)]
+        struct Link(Entity);
+
+        impl Clone for Link {
+            #[allow(clippy::clone_on_copy)]
+            fn clone(&self) -> Self {
+                Self(self.0.clone())
+            }
+        }
+
+        let mut world = World::default();
+        let other = world.push((0usize,));
+        let entity = world.push((1usize,));
+        world.entry(entity).unwrap().add_component(Link(entity));
+        let linked = world.push((Link(other),));
+
+        let mut merger = Duplicate::default();
+        merger.register_copy::<usize>();
+        merger.register_clone::<Link>();
+
+        // references to the source are rewritten to the clone
+        let clone = world.clone_entity(entity, &mut merger);
+        assert_ne!(clone, entity);
+        let entry = world.entry(clone).unwrap();
+        assert_eq!(entry.get_component::<usize>(), Ok(&1usize));
+        assert_eq!(entry.get_component::<Link>(), Ok(&Link(clone)));
+        assert_eq!(
+            world.entry(entity).unwrap().get_component::<Link>(),
+            Ok(&Link(entity))
+        );
+
+        // other references are rewritten according to the entity map
+        let mut map = HashMap::default();
+        map.insert(other, entity);
+        merger.set_entity_map(EntityRewrite::Auto(Some(map)));
+        let clone = world.clone_entity(linked, &mut merger);
+        assert_eq!(
+            world.entry(clone).unwrap().get_component::<Link>(),
+            Ok(&Link(entity))
+        );
+        assert_eq!(world.len(), 5);
+
+        // clones reuse the archetypes of both the world and its staging world
+        let archetypes = world.archetypes().len();
+        for _ in 0..3 {
+            world.clone_entity(entity, &mut merger);
+        }
+        assert_eq!(world.archetypes().len(), archetypes);
+        assert_eq!(world.staging.as_ref().unwrap().archetypes().len(), 2);
+        assert_eq!(world.len(), 8);
+    }
+
+    #[test]
+    fn clone_entity_links() {
+        use crate::internals::relation::{sources, targets, RelatedBy, Relation};
+
+        struct Likes;
+
+        let children = |world: &World, entity: Entity| {
+            world
+                .entry_ref(entity)
+                .unwrap()
+                .get_component::<Children>()
+                .map(|children| children.entities().to_vec())
+                .unwrap_or_default()
+        };
+
+        let mut world = World::default();
+        let root = world.push((0usize,));
+        let child = world.push((1usize,));
+        let other = world.push((2usize,));
+        world.set_parent(child, root).unwrap();
+        world.relate::<Likes>(child, other);
+        world.relate::<Likes>(root, child);
+
+        let mut merger = Duplicate::default();
+        merger.register_copy::<usize>();
+        merger.register_clone::<Parent>();
+        merger.register_clone::<Children>();
+        merger.register_clone::<Relation<Likes>>();
+        merger.register_clone::<RelatedBy<Likes>>();
+
+        // a cloned child becomes a sibling, related to the same targets
+        let child_clone = world.clone_entity(child, &mut merger);
+        assert_eq!(children(&world, root), vec![child, child_clone]);
+        assert_eq!(targets::<Likes, _>(&world, child_clone), vec![other]);
+        assert_eq!(sources::<Likes, _>(&world, other), vec![child, child_clone]);
+        assert!(sources::<Likes, _>(&world, child_clone).is_empty());
+
+        // a cloned parent does not take the original's children
+        let root_clone = world.clone_entity(root, &mut merger);
+        assert!(children(&world, root_clone).is_empty());
+        assert_eq!(sources::<Likes, _>(&world, child), vec![root, root_clone]);
+
+        world.remove(root_clone);
+        world.remove(child_clone);
+        assert_eq!(children(&world, root), vec![child]);
+        assert_eq!(
+            world
+                .entry(child)
+                .unwrap()
+                .get_component::<Parent>()
+                .map(Parent::entity),
+            Ok(root)
+        );
+        assert_eq!(sources::<Likes, _>(&world, child), vec![root]);
+        assert_eq!(sources::<Likes, _>(&world, other), vec![child]);
+    }
+
+    #[test]
+    fn add_components() {
+        let mut world = World::default();
+        world.track_removed::<usize>();
+        let entity = world.push((1usize, true));
+        let other = world.push((2usize, true));
+        let archetypes = world.archetypes().len();
+
+        let mut entry = world.entry(entity).unwrap();
+        entry.add_components((3usize, 0.5f32, 'a'));
+        assert_eq!(entry.get_component::<usize>(), Ok(&3usize));
+        assert_eq!(entry.get_component::<bool>(), Ok(&true));
+        assert_eq!(entry.get_component::<f32>(), Ok(&0.5f32));
+        assert_eq!(entry.get_component::<char>(), Ok(&'a'));
+        assert_eq!(entry.archetype().layout().component_types().len(), 4);
+
+        // only the final archetype is created
+        assert_eq!(world.archetypes().len(), archetypes + 1);
+        assert_eq!(
+            world.entry(other).unwrap().get_component::<usize>(),
+            Ok(&2usize)
+        );
+
+        let mut entry = world.entry(entity).unwrap();
+        entry.remove_components::<(usize, char, Pos)>();
+        assert!(entry.get_component::<usize>().is_err());
+        assert_eq!(entry.get_component::<f32>(), Ok(&0.5f32));
+        assert_eq!(entry.archetype().layout().component_types().len(), 2);
+        assert_eq!(world.removed::<usize>(), &[entity]);
+    }
+
+    #[test]
+    fn sparse_components() {
+        use crate::internals::query::{
+            view::sparse::{TryReadSparse, TryWriteSparse},
+            IntoQuery,
+        };
+
+        struct Selected(usize);
+
+        let mut world = World::default();
+        world.track_removed::<Selected>();
+        let entities = world.extend(vec![(1usize,), (2usize,), (3usize,)]).to_vec();
+        world.push((4usize, false));
+        let archetypes = world.archetypes().len();
+
+        assert!(world
+            .insert_sparse(entities[0], Selected(0))
+            .unwrap()
+            .is_none());
+        assert!(world
+            .insert_sparse(entities[1], Selected(1))
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            world
+                .insert_sparse(entities[1], Selected(2))
+                .unwrap()
+                .unwrap()
+                .0,
+            1
+        );
+        assert_eq!(world.archetypes().len(), archetypes);
+
+        let mut query = <(Entity, TryWriteSparse<Selected>)>::query();
+        for (_, selected) in query.iter_mut(&mut world) {
+            if let Some(selected) = selected {
+                selected.0 += 10;
+            }
+        }
+
+        let mut query = <(&usize, TryReadSparse<Selected>)>::query();
+        let mut selected = query
+            .iter(&world)
+            .filter_map(|(value, selected)| selected.map(|selected| (*value, selected.0)))
+            .collect::<Vec<_>>();
+        selected.sort_unstable();
+        assert_eq!(selected, vec![(1, 10), (2, 12)]);
+
+        world.remove(entities[0]);
+        assert!(world.get_sparse::<Selected>(entities[0]).is_none());
+        assert_eq!(world.removed::<Selected>(), &[entities[0]]);
+
+        let mut other = World::default();
+        other.move_from(&mut world, &any());
+        assert!(world
+            .components()
+            .get_sparse::<Selected>()
+            .unwrap()
+            .is_empty());
+        assert_eq!(other.get_sparse::<Selected>(entities[1]).unwrap().0, 12);
+
+        assert!(world.insert_sparse(entities[1], Selected(0)).is_err());
+        assert_eq!(other.remove_sparse::<Selected>(entities[1]).unwrap().0, 12);
+    }
+
     #[test]
     fn remove_repeat() {
         let mut world = World::default();
@@ -1263,6 +2611,7 @@ mod test {
 
         let mut world = crate::internals::world::World::new(WorldOptions {
             groups: vec![<(A, B, C, D)>::to_group()],
+            ..Default::default()
         });
 
         world.extend(std::iter::repeat((A(0f32),)).take(10000));
@@ -105,7 +105,8 @@
 //!
 //! You can augment a basic query with additional filters. For example, you can choose to exclude
 //! entities which also have a certain component, or only include entities for which a certain
-//! component has changed since the last time the query ran (this filtering is conservative and coarse-grained)
+//! component has changed since the last time the query ran (this filtering is conservative and coarse-grained,
+//! use `changed` for exact per-entity change detection)
 //!
 //! ```
 //! # use legion::*;
@@ -186,7 +187,10 @@
 mod internals;
 
 // public API organized into logical modules
+pub mod hierarchy;
+pub mod prefab;
 pub mod query;
+pub mod relation;
 pub mod storage;
 pub mod systems;
 pub mod world;
@@ -197,8 +201,8 @@ pub mod serialize;
 // re-export most common types into the root
 pub use crate::{
     query::{
-        any, component, maybe_changed, passthrough, Fetch, IntoQuery, Read, TryRead, TryWrite,
-        Write,
+        added, any, changed, component, maybe_changed, passthrough, Fetch, IntoQuery, Read,
+        TryRead, TryWrite, Write,
     },
     storage::{GroupSource, IntoSoa},
     systems::{Resources, Schedule, SystemBuilder},
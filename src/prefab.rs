@@ -0,0 +1,50 @@
+//! Prefabs, which are templates for groups of entities.
+//!
+//! A [Prefab](struct.Prefab.html) stores a template world. Each call to
+//! [World::instantiate](../world/struct.World.html#method.instantiate), or the equivalent
+//! [CommandBuffer](../systems/struct.CommandBuffer.html) method, clones the template's entities
+//! into a world, rewriting references between them to refer to their clones. Per-instance
+//! [Overrides](struct.Overrides.html) replace the components of particular entities.
+//!
+//! Each instantiated entity is given a [PrefabInstance](struct.PrefabInstance.html) component,
+//! which links it back to the template entity it was cloned from. Changes made to the template
+//! are applied to existing instances by
+//! [World::sync_prefab](../world/struct.World.html#method.sync_prefab), which keeps the
+//! components each instance overrode.
+//!
+//! A template world may itself contain instances of other prefabs. Syncing the template with
+//! `sync_prefab` before syncing the outer prefab's instances propagates changes through each
+//! level of nesting.
+//!
+//! ```
+//! # use legion::*;
+//! # use legion::prefab::{Overrides, Prefab, PrefabInstance};
+//! # use legion::world::Duplicate;
+//! # struct Health(usize);
+//! let mut template = World::default();
+//! let turret = template.push((Health(50), 1usize));
+//! let prefab = Prefab::new(template);
+//!
+//! let mut merger = Duplicate::default();
+//! merger.register_convert(|health: &Health| Health(health.0));
+//! merger.register_copy::<usize>();
+//!
+//! let mut world = World::default();
+//! let weak = world.instantiate(&prefab, Overrides::new().with(turret, Health(10)), &mut merger);
+//! let _strong = world.instantiate(&prefab, Overrides::new(), &mut merger);
+//!
+//! let entry = world.entry(weak.get(turret).unwrap()).unwrap();
+//! assert_eq!(entry.get_component::<Health>().unwrap().0, 10);
+//!
+//! // find every instance of the prefab
+//! let mut query = Read::<PrefabInstance>::query();
+//! let instances = query
+//!     .iter(&world)
+//!     .filter(|link| link.prefab() == prefab.id())
+//!     .count();
+//! assert_eq!(instances, 2);
+//! ```
+
+pub use crate::internals::prefab::{
+    Instance, InstanceId, Overrides, Prefab, PrefabId, PrefabInstance,
+};
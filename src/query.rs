@@ -10,6 +10,8 @@
 //!
 //! View types include [Entity](../world/struct.Entity.html), [Read](struct.Read.html),
 //! [Write](struct.Write.html), [TryRead](struct.TryRead.html) and [TryWrite](struct.TryWrite.html).
+//! Components in [sparse storage](../storage/struct.SparseSet.html) are accessed with
+//! [TryReadSparse](struct.TryReadSparse.html) and [TryWriteSparse](struct.TryWriteSparse.html).
 //!
 //! ```
 //! # use legion::*;
@@ -82,10 +84,12 @@
 
 pub use crate::internals::query::{
     filter::{
+        added::EntityAddedFilter,
         and::And,
         any::Any,
+        changed::EntityChangedFilter,
         component::ComponentFilter,
-        filter_fns::{any, component, maybe_changed, passthrough},
+        filter_fns::{added, any, changed, component, maybe_changed, passthrough},
         maybe_changed::ComponentChangedFilter,
         not::Not,
         or::Or,
@@ -94,8 +98,12 @@ pub use crate::internals::query::{
         DynamicFilter, EntityFilter, EntityFilterTuple, FilterResult, GroupMatcher, LayoutFilter,
     },
     view::{
-        read::Read, try_read::TryRead, try_write::TryWrite, write::Write, DefaultFilter, Fetch,
-        IntoIndexableIter, ReadOnly, View,
+        read::Read,
+        sparse::{TryReadSparse, TryWriteSparse},
+        try_read::TryRead,
+        try_write::TryWrite,
+        write::Write,
+        DefaultFilter, Fetch, IntoIndexableIter, ReadOnly, View,
     },
     ChunkIter, ChunkView, IntoQuery, Query,
 };
@@ -0,0 +1,41 @@
+//! Typed many-to-many relations between entities.
+//!
+//! A relation of kind `K` from a source entity to a target entity is stored in the source's
+//! [Relation<K>](struct.Relation.html) component, and indexed in reverse by the target's
+//! [RelatedBy<K>](struct.RelatedBy.html) component. Both are maintained together by
+//! [World::relate](../world/struct.World.html#method.relate) and
+//! [World::unrelate](../world/struct.World.html#method.unrelate), or the equivalent
+//! [CommandBuffer](../systems/struct.CommandBuffer.html) methods. Removing either entity from the
+//! world also removes the relation.
+//!
+//! Both components can be queried like any other component.
+//!
+//! ```
+//! # use legion::*;
+//! # use legion::relation::{sources, targets, Relation};
+//! struct Attached;
+//!
+//! let mut world = World::default();
+//! let ship = world.push((0usize,));
+//! let gun = world.push((1usize,));
+//! let shield = world.push((2usize,));
+//! world.relate::<Attached>(gun, ship);
+//! world.relate::<Attached>(shield, ship);
+//!
+//! // look up relations in either direction
+//! assert_eq!(targets::<Attached, _>(&world, gun), vec![ship]);
+//! assert_eq!(sources::<Attached, _>(&world, ship), vec![gun, shield]);
+//!
+//! // find all entities which are attached to the ship
+//! let mut query = <(Entity, Read<Relation<Attached>>)>::query();
+//! let attached = query
+//!     .iter(&world)
+//!     .filter(|(_, relation)| relation.contains(ship))
+//!     .count();
+//! assert_eq!(attached, 2);
+//!
+//! world.remove(ship);
+//! assert!(targets::<Attached, _>(&world, gun).is_empty());
+//! ```
+
+pub use crate::internals::relation::{sources, targets, RelatedBy, Relation};
@@ -70,9 +70,17 @@
 //! // create a world optimized for cases where (A, B) and/or
 //! // (A, B, C) are significant queries.
 //! let group = <(A, B, C)>::to_group();
-//! let options = WorldOptions { groups: vec![group] };
+//! let options = WorldOptions {
+//!     groups: vec![group],
+//!     ..Default::default()
+//! };
 //! let world = World::new(options);
 //! ```
+//!
+//! Components which are frequently added to and removed from entities, such as markers, can
+//! instead be stored in a [SparseSet](struct.SparseSet.html) with
+//! [World::insert_sparse](../world/struct.World.html#method.insert_sparse). Sparse components
+//! are not part of an entity's archetype, so adding or removing them never moves the entity.
 
 pub use crate::internals::{
     cons::{ConsAppend, ConsFlatten},
@@ -87,6 +95,7 @@ pub use crate::internals::{
         group::{Group, GroupDef, GroupSource},
         index::SearchIndex,
         packed::PackedStorage,
+        sparse::{SparseSet, UnknownSparseSet},
         ComponentIndex, ComponentMeta, ComponentSlice, ComponentSliceMut, ComponentStorage,
         Components, Epoch, MultiMut, PackOptions, UnknownComponentStorage, Version,
     },
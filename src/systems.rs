@@ -2,10 +2,18 @@
 
 pub use crate::internals::systems::{
     command::{CommandBuffer, WorldWritable},
+    events::{EventCursor, EventIter, EventReader, EventWriter, Events},
+    future::{AsyncContext, AsyncSystemFn},
+    graph::{AccessConflict, Ambiguity, DependencyGraph, EdgeReason, GraphEdge, GraphNode},
+    profile::{ProfileSpan, ScheduleProfile, SpanKind},
     resources::{
-        Fetch, FetchMut, Resource, ResourceSet, ResourceTypeId, Resources, SyncResources,
-        UnsafeResources,
+        Fetch, FetchMut, NonSend, NonSendMut, Resource, ResourceSet, ResourceTypeId, Resources,
+        SyncResources, UnsafeResources,
     },
-    schedule::{Builder, Executor, ParallelRunnable, Runnable, Schedule, Step},
-    system::{QuerySet, System, SystemAccess, SystemBuilder, SystemFn, SystemId},
+    schedule::{
+        Builder, Executor, ParallelRunnable, RunCondition, Runnable, Schedule, ScheduleError,
+        StageLabel, Step,
+    },
+    system::{QuerySet, System, SystemAccess, SystemBuilder, SystemFn, SystemId, SystemLabel},
+    timestep::{FixedTimestep, FixedTimestepState, FixedTimesteps},
 };
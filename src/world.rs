@@ -109,6 +109,11 @@
 //! to an [EventSender](trait.EventSender.html) by subscribing to the world. A layout filter specifies
 //! which archetypes the subscriber is interested in.
 //!
+//! When components are added to or removed from an entity, the entity migrates into a new archetype.
+//! Subscribers will see its removal and insertion, followed by an `EntityMoved` event and a
+//! `ComponentAdded` or `ComponentRemoved` event for each component type which differs between the
+//! two archetypes. When an entity is deleted, its removal is followed by an `EntityDespawned` event.
+//!
 //! ```ignore
 //! # use legion::*;
 //! # let mut world = World::default();
@@ -119,6 +124,20 @@
 //! world.subscribe(tx, component::<Position>());
 //! ```
 //!
+//! Without the `crossbeam-events` feature, an [EventQueue](struct.EventQueue.html) can be used instead.
+//! Event queues can be stored in `Resources` and drained by a system.
+//!
+//! ```
+//! # use legion::*;
+//! # use legion::world::EventQueue;
+//! # let mut world = World::default();
+//! # let mut resources = Resources::default();
+//! # struct Position;
+//! let events = EventQueue::unbounded();
+//! world.subscribe(events.clone(), component::<Position>());
+//! resources.insert(events);
+//! ```
+//!
 //! # World splitting
 //!
 //! World splitting allows mutable access to a world via multiple entries or queries at the same time,
@@ -142,9 +161,11 @@
 //! ```
 
 pub use crate::internals::{
-    entity::{Allocate, Entity, EntityHasher, EntityLocation, LocationMap},
-    entry::{ComponentError, Entry, EntryMut, EntryRef},
-    event::{Event, EventSender},
+    entity::{
+        Allocate, Entity, EntityAllocation, EntityHasher, EntityLocation, EntityRange, LocationMap,
+    },
+    entry::{ComponentError, ComponentSet, Entry, EntryMut, EntryRef},
+    event::{Event, EventDrain, EventQueue, EventSender, OverflowPolicy},
     permissions::Permissions,
     subworld::{ArchetypeAccess, ComponentAccess, SubWorld},
     world::{
@@ -0,0 +1,8 @@
+#![allow(unused_imports)]
+
+use legion::{system, systems::EventReader, Entity};
+
+#[system(for_each)]
+fn for_each(_: &Entity, _: EventReader<usize>) {}
+
+fn main() {}
@@ -1,7 +1,7 @@
 #![allow(clippy::map_clone)]
 
 use legion::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use query::Query;
 #[cfg(feature = "parallel")]
@@ -509,3 +509,154 @@ fn query_try_with_changed_filter() {
         Ok(Sum(4.))
     );
 }
+
+#[test]
+fn query_changed_per_entity() {
+    let mut world = World::default();
+
+    let entities = world
+        .extend(vec![
+            (Pos(1., 2., 3.), Rot(0.1, 0.2, 0.3)),
+            (Pos(4., 5., 6.), Rot(0.4, 0.5, 0.6)),
+            (Pos(7., 8., 9.), Rot(0.7, 0.8, 0.9)),
+            (Pos(10., 11., 12.), Rot(1.0, 1.1, 1.2)),
+        ])
+        .to_vec();
+
+    let mut query = <(Entity, Read<Pos>)>::query().filter(changed::<Pos>());
+
+    assert_eq!(4, query.iter(&world).count());
+    assert_eq!(0, query.iter(&world).count());
+
+    // modify a single entity via an entry
+    *world
+        .entry(entities[1])
+        .unwrap()
+        .get_component_mut::<Pos>()
+        .unwrap() = Pos(0., 0., 0.);
+
+    let changed = query.iter(&world).map(|(e, _)| *e).collect::<Vec<_>>();
+    assert_eq!(vec![entities[1]], changed);
+
+    // modify two non-adjacent entities via a query
+    let mut writer = Write::<Pos>::query();
+    writer.get_mut(&mut world, entities[0]).unwrap().0 += 1.;
+    writer.get_mut(&mut world, entities[3]).unwrap().0 += 1.;
+
+    let changed = query.iter(&world).map(|(e, _)| *e).collect::<Vec<_>>();
+    assert_eq!(vec![entities[0], entities[3]], changed);
+
+    // chunks only contain the changed entities
+    writer.get_mut(&mut world, entities[2]).unwrap().0 += 1.;
+
+    let chunks = query
+        .iter_chunks(&world)
+        .map(|chunk| {
+            chunk
+                .into_iter_entities()
+                .map(|(e, _)| e)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(vec![vec![entities[2]]], chunks);
+}
+
+#[test]
+fn query_changed_write_access() {
+    let mut world = World::default();
+
+    world.extend(vec![(Pos(1., 2., 3.),), (Pos(4., 5., 6.),)]);
+
+    let mut query = <(Entity, Read<Pos>)>::query().filter(changed::<Pos>());
+    assert_eq!(2, query.iter(&world).count());
+
+    // only the entities which are visited by a writing query are changed
+    let mut writer = Write::<Pos>::query();
+    if let Some(pos) = writer.iter_mut(&mut world).next() {
+        pos.0 += 1.;
+    }
+    assert_eq!(1, query.iter(&world).count());
+}
+
+#[test]
+fn query_changed_retained_on_move() {
+    let mut world = World::default();
+
+    let entities = world
+        .extend(vec![(Pos(1., 2., 3.),), (Pos(4., 5., 6.),)])
+        .to_vec();
+
+    let mut query = <(Entity, Read<Pos>)>::query().filter(changed::<Pos>());
+    assert_eq!(2, query.iter(&world).count());
+
+    // moving an entity into a new archetype does not modify its existing components
+    world
+        .entry(entities[0])
+        .unwrap()
+        .add_component(Rot(0., 0., 0.));
+    assert_eq!(0, query.iter(&world).count());
+}
+
+#[test]
+fn query_changed_chunk_size_hint() {
+    let mut world = World::default();
+
+    let entities = world
+        .extend(vec![
+            (Pos(1., 2., 3.),),
+            (Pos(4., 5., 6.),),
+            (Pos(7., 8., 9.),),
+        ])
+        .to_vec();
+
+    let mut query = <(Entity, Read<Pos>)>::query().filter(changed::<Pos>());
+    assert_eq!(3, query.iter(&world).count());
+
+    // non-adjacent changes are split into separate chunks
+    let mut writer = Write::<Pos>::query();
+    writer.get_mut(&mut world, entities[0]).unwrap().0 += 1.;
+    writer.get_mut(&mut world, entities[2]).unwrap().0 += 1.;
+
+    let chunks = query.iter_chunks(&world);
+    assert_eq!((0, Some(3)), chunks.size_hint());
+    assert_eq!(2, chunks.count());
+}
+
+#[test]
+fn query_added() {
+    let mut world = World::default();
+
+    let entities = world
+        .extend(vec![(Pos(1., 2., 3.),), (Pos(4., 5., 6.),)])
+        .to_vec();
+
+    let mut query = <(Entity, Read<Pos>)>::query().filter(added::<Pos>());
+    assert_eq!(2, query.iter(&world).count());
+    assert_eq!(0, query.iter(&world).count());
+
+    // modifying a component does not count as an addition
+    *world
+        .entry(entities[0])
+        .unwrap()
+        .get_component_mut::<Pos>()
+        .unwrap() = Pos(0., 0., 0.);
+    assert_eq!(0, query.iter(&world).count());
+
+    // moving an entity into a new archetype does not count as an addition
+    world
+        .entry(entities[0])
+        .unwrap()
+        .add_component(Rot(0., 0., 0.));
+    assert_eq!(0, query.iter(&world).count());
+
+    // adding the component to an existing entity does
+    let entity = world.push((Rot(0., 0., 0.),));
+    world.entry(entity).unwrap().add_component(Pos(7., 8., 9.));
+    let pushed = world.push((Pos(10., 11., 12.),));
+
+    let added = query.iter(&world).map(|(e, _)| *e).collect::<HashSet<_>>();
+    assert_eq!(
+        vec![entity, pushed].into_iter().collect::<HashSet<_>>(),
+        added
+    );
+}
@@ -57,3 +57,130 @@ fn query_get() {
 
     schedule.execute(&mut world, &mut resources);
 }
+
+#[test]
+#[cfg(feature = "codegen")]
+fn drain_event_queue_resource() {
+    use legion::world::{Event, EventQueue};
+
+    #[system]
+    fn count_inserted(#[resource] events: &EventQueue, #[state] count: &mut usize) {
+        *count += events
+            .drain()
+            .filter(|event| matches!(event, Event::EntityInserted(..)))
+            .count();
+    }
+
+    let mut world = World::default();
+    let mut resources = Resources::default();
+    let events = EventQueue::unbounded();
+    world.subscribe(events.clone(), any());
+    resources.insert(events);
+
+    world.extend(vec![(1usize,), (2usize,)]);
+
+    let mut schedule = Schedule::builder()
+        .add_system(count_inserted_system(0))
+        .build();
+    schedule.execute(&mut world, &mut resources);
+
+    assert!(resources.get::<EventQueue>().unwrap().is_empty());
+}
+
+#[test]
+#[cfg(feature = "codegen")]
+fn typed_events() {
+    use legion::systems::{EventReader, EventWriter, Events};
+
+    struct Collision(usize);
+
+    #[system]
+    fn detect(mut collisions: EventWriter<Collision>, #[state] frame: &mut usize) {
+        *frame += 1;
+        collisions.send(Collision(*frame));
+    }
+
+    #[system]
+    fn record(mut collisions: EventReader<Collision>, #[resource] seen: &mut Vec<usize>) {
+        seen.extend(collisions.iter().map(|collision| collision.0));
+    }
+
+    let mut world = World::default();
+    let mut resources = Resources::default();
+    resources.insert(Events::<Collision>::default());
+    resources.insert(Vec::<usize>::new());
+
+    let mut schedule = Schedule::builder()
+        .add_system(detect_system(0))
+        .add_system(record_system())
+        .add_system(Events::<Collision>::update_system())
+        .build();
+
+    for _ in 0..3 {
+        schedule.execute(&mut world, &mut resources);
+    }
+
+    // each event is seen exactly once
+    assert_eq!(*resources.get::<Vec<usize>>().unwrap(), vec![1, 2, 3]);
+    assert_eq!(resources.get::<Events<Collision>>().unwrap().len(), 1);
+}
+
+#[test]
+#[cfg(feature = "codegen")]
+fn labelled_systems() {
+    #[system]
+    #[after("early")]
+    fn late(#[resource] order: &mut Vec<&'static str>) {
+        order.push("late");
+    }
+
+    #[system]
+    #[label("early")]
+    fn early(#[resource] order: &mut Vec<&'static str>) {
+        order.push("early");
+    }
+
+    let mut world = World::default();
+    let mut resources = Resources::default();
+    resources.insert(Vec::<&'static str>::new());
+
+    let mut schedule = Schedule::builder()
+        .add_system(late_system())
+        .add_system(early_system())
+        .build();
+    schedule.execute(&mut world, &mut resources);
+
+    assert_eq!(
+        *resources.get::<Vec<&'static str>>().unwrap(),
+        vec!["early", "late"]
+    );
+}
+
+#[test]
+#[cfg(feature = "codegen")]
+fn non_send_resources() {
+    use std::{rc::Rc, thread::ThreadId};
+
+    struct Window(Rc<Vec<ThreadId>>);
+
+    #[system]
+    fn render(#[non_send] window: &mut Window, #[resource] frames: &usize) {
+        Rc::get_mut(&mut window.0)
+            .unwrap()
+            .push(std::thread::current().id());
+        assert_eq!(*frames, 1);
+    }
+
+    let mut world = World::default();
+    let mut resources = Resources::default();
+    resources.insert(Window(Rc::new(Vec::new())));
+    resources.insert(1usize);
+
+    let mut schedule = Schedule::builder().add_system(render_system()).build();
+    schedule.execute(&mut world, &mut resources);
+
+    assert_eq!(
+        *resources.get::<Window>().unwrap().0,
+        vec![std::thread::current().id()]
+    );
+}
@@ -306,6 +306,73 @@ fn delete_entities_on_drop() {
     assert!(entities.is_empty());
 }
 
+#[test]
+#[cfg(feature = "crossbeam-events")]
+fn migration_events() {
+    use legion::storage::ComponentTypeId;
+    use legion::world::Event;
+
+    let mut world = World::default();
+    let (tx, rx) = crossbeam_channel::unbounded::<Event>();
+    world.subscribe(tx, any());
+
+    let entity = world.push((Pos(1., 2., 3.),));
+    world.entry(entity).unwrap().add_component(Rot(0., 0., 0.));
+    world.entry(entity).unwrap().remove_component::<Pos>();
+    world.remove(entity);
+
+    let mut moves = 0;
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut removals = 0;
+    let mut despawns = 0;
+    let mut emptied = 0;
+    for event in rx.try_iter() {
+        match event {
+            Event::EntityMoved {
+                entity: e,
+                from,
+                to,
+            } => {
+                assert_eq!(entity, e);
+                assert_ne!(from, to);
+                moves += 1;
+            }
+            Event::ComponentAdded {
+                entity: e,
+                component,
+            } => {
+                assert_eq!(entity, e);
+                added.push(component);
+            }
+            Event::ComponentRemoved {
+                entity: e,
+                component,
+            } => {
+                assert_eq!(entity, e);
+                removed.push(component);
+            }
+            Event::EntityRemoved(e, _) => {
+                assert_eq!(entity, e);
+                removals += 1;
+            }
+            Event::EntityDespawned(e, _) => {
+                assert_eq!(entity, e);
+                despawns += 1;
+            }
+            Event::ArchetypeEmptied(_) => emptied += 1,
+            _ => {}
+        }
+    }
+
+    assert_eq!(2, moves);
+    assert_eq!(3, removals);
+    assert_eq!(1, despawns);
+    assert_eq!(3, emptied);
+    assert_eq!(vec![ComponentTypeId::of::<Rot>()], added);
+    assert_eq!(vec![ComponentTypeId::of::<Pos>()], removed);
+}
+
 // This test repeatedly creates a world with new entities and drops it, reproducing
 // https://github.com/TomGillen/legion/issues/92
 #[test]
@@ -347,3 +414,105 @@ fn iter_entities() {
     // Verify that no extra entities are included
     assert!(entities.is_empty());
 }
+
+#[test]
+fn removed_log() {
+    let mut world = World::default();
+    world.track_removed::<Rot>();
+
+    let entities = world
+        .extend(vec![
+            (Pos(1., 2., 3.), Rot(0.1, 0.2, 0.3)),
+            (Pos(4., 5., 6.), Rot(0.4, 0.5, 0.6)),
+            (Pos(7., 8., 9.), Rot(0.7, 0.8, 0.9)),
+        ])
+        .to_vec();
+    assert!(world.removed::<Rot>().is_empty());
+
+    // untracked types are never logged
+    world.entry(entities[0]).unwrap().remove_component::<Pos>();
+    assert!(world.removed::<Pos>().is_empty());
+    assert!(world.removed::<Rot>().is_empty());
+
+    world.entry(entities[1]).unwrap().remove_component::<Rot>();
+    world.remove(entities[2]);
+    assert_eq!(&[entities[1], entities[2]], world.removed::<Rot>());
+
+    world.clear_removed();
+    assert!(world.removed::<Rot>().is_empty());
+
+    // removing a component which the entity does not have is not logged
+    world.entry(entities[1]).unwrap().remove_component::<Rot>();
+    assert!(world.removed::<Rot>().is_empty());
+}
+
+#[test]
+fn event_queue() {
+    use legion::world::{Event, EventQueue, OverflowPolicy};
+
+    let mut world = World::default();
+    let events = EventQueue::unbounded();
+    world.subscribe(events.clone(), component::<Pos>());
+
+    let entities = world
+        .extend(vec![(Pos(1., 2., 3.),), (Pos(4., 5., 6.),)])
+        .to_vec();
+    world.push((Rot(0., 0., 0.),));
+
+    let drained = events.drain().collect::<Vec<_>>();
+    assert!(events.is_empty());
+    assert!(matches!(drained[0], Event::ArchetypeCreated(_)));
+    let inserted = drained
+        .iter()
+        .filter_map(|event| match event {
+            Event::EntityInserted(entity, _) => Some(*entity),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(entities, inserted);
+
+    // bounded queues drop events once full
+    let bounded = EventQueue::bounded(2, OverflowPolicy::DropNewest);
+    world.subscribe(bounded.clone(), component::<Pos>());
+    assert_eq!(2, bounded.len());
+    assert_eq!(1, bounded.dropped());
+    assert_eq!(2, bounded.drain().count());
+
+    // an overflowing queue with the unsubscribe policy stops receiving events
+    let unsubscribed = EventQueue::bounded(2, OverflowPolicy::Unsubscribe);
+    world.subscribe(unsubscribed.clone(), component::<Rot>());
+    assert_eq!(2, unsubscribed.drain().count());
+    for _ in 0..3 {
+        world.push((Rot(0., 0., 0.),));
+    }
+    assert_eq!(2, unsubscribed.drain().count());
+    world.push((Rot(0., 0., 0.),));
+    assert!(unsubscribed.is_empty());
+}
+
+#[test]
+fn event_queue_concurrent_push() {
+    use legion::world::{Event, EventQueue};
+
+    let events = EventQueue::unbounded();
+    let threads = (0..4)
+        .map(|_| {
+            let events = events.clone();
+            std::thread::spawn(move || {
+                for i in 0..1000 {
+                    assert!(
+                        events.push(Event::ArchetypeEmptied(legion::storage::ArchetypeIndex(i)))
+                    );
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut received = 0;
+    for thread in threads {
+        thread.join().unwrap();
+        received += events.drain().count();
+    }
+    received += events.drain().count();
+    assert_eq!(4000, received);
+}